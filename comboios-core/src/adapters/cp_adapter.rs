@@ -1,15 +1,19 @@
 use std::fmt::Write as _;
 
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use reqwest::Client;
 
 use crate::domain::cp_types::{CpStation, CpStationStop, CpTimetableResponse, CpTrainTimetable};
 use crate::domain::{
     journey::TrainJourney,
+    occupancy::Occupancy,
     station::Station as DomainStation,
     station::StationResponse,
-    station_timetable::{StationBoard, StationBoardResponse, StationTimetable},
+    station_timetable::{BoardDirection, StationBoard, StationBoardResponse, StationTimetable},
 };
 use crate::error::CoreError;
+use crate::query_builder::BoardWindow;
+use crate::timeline::ServiceDay;
 
 type Result<T> = std::result::Result<T, CoreError>;
 
@@ -44,9 +48,14 @@ impl CpAdapter {
         }
     }
 
-    pub async fn search_stations(&self, query: &str) -> Result<StationResponse> {
+    /// Every station CP knows, with coordinates where available.
+    pub async fn list_stations(&self) -> Result<Vec<CpStation>> {
         let url = format!("{}/services/travel-api/stations", self.base_url);
-        let stations: Vec<CpStation> = self.get(&url).await?;
+        self.get(&url).await
+    }
+
+    pub async fn search_stations(&self, query: &str) -> Result<StationResponse> {
+        let stations = self.list_stations().await?;
 
         let query_lower = query.to_lowercase();
         let matching: Vec<DomainStation> = stations
@@ -67,14 +76,7 @@ impl CpAdapter {
         date: &str,
         start_time: Option<&str>,
     ) -> Result<StationBoardResponse> {
-        let mut url = format!(
-            "{}/services/travel-api/stations/{}/timetable/{}",
-            self.base_url, station_id, date
-        );
-        if let Some(start) = start_time {
-            write!(url, "?start={start}").expect("writing to String never fails");
-        }
-
+        let url = self.timetable_url(station_id, date, start_time);
         let response: CpTimetableResponse = self.get(&url).await?;
 
         let board = Self::convert_timetable_to_board(station_id, &response);
@@ -83,13 +85,67 @@ impl CpAdapter {
         })
     }
 
+    /// Fetch one side of a station board over `window`, issuing one upstream
+    /// request per service day the window touches.
+    pub async fn get_board(
+        &self,
+        station_id: &str,
+        window: &BoardWindow,
+        direction: BoardDirection,
+    ) -> Result<StationBoard> {
+        let rows = self.fetch_dated_rows(station_id, window, direction).await?;
+        Ok(Self::assemble_board(station_id, rows, window))
+    }
+
+    /// The rows of [`get_board`](Self::get_board), each paired with its
+    /// scheduled date-time.
+    pub(crate) async fn get_dated_board(
+        &self,
+        station_id: &str,
+        window: &BoardWindow,
+        direction: BoardDirection,
+    ) -> Result<Vec<(NaiveDateTime, StationTimetable)>> {
+        let rows = self.fetch_dated_rows(station_id, window, direction).await?;
+        Ok(Self::window_rows(rows, window))
+    }
+
+    /// Every dated row of the service days `window` touches.
+    async fn fetch_dated_rows(
+        &self,
+        station_id: &str,
+        window: &BoardWindow,
+        direction: BoardDirection,
+    ) -> Result<Vec<(NaiveDateTime, StationTimetable)>> {
+        window.validate()?;
+
+        let mut rows = Vec::new();
+        for date in window.service_dates() {
+            // Only the first day can be narrowed with `start`; later days are
+            // fetched from midnight.
+            let start =
+                (date == window.start().date()).then(|| window.start().format("%H:%M").to_string());
+            let url = self.timetable_url(
+                station_id,
+                &date.format("%Y-%m-%d").to_string(),
+                start.as_deref(),
+            );
+            let response: CpTimetableResponse = self.get(&url).await?;
+            rows.extend(Self::dated_rows(date, station_id, &response, direction));
+        }
+
+        Ok(rows)
+    }
+
     pub async fn get_train_journey(&self, train_number: &str, date: &str) -> Result<TrainJourney> {
         let url = format!(
             "{}/services/travel-api/trains/{}/timetable/{}",
             self.base_url, train_number, date
         );
         let timetable: CpTrainTimetable = self.get(&url).await?;
-        Ok(timetable.to_train_journey())
+        Ok(match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(service_date) => timetable.to_train_journey_on(service_date),
+            Err(_) => timetable.to_train_journey(),
+        })
     }
 
     pub(crate) fn convert_timetable_to_board(
@@ -97,7 +153,7 @@ impl CpAdapter {
         response: &CpTimetableResponse,
     ) -> StationBoard {
         // CP API does not include the queried station name in the timetable
-        // response. The name is populated server-side from a cached station list.
+        // response. `Comboios` fills it in from its station index.
         let station_name = String::new();
 
         let trains: Vec<StationTimetable> = response
@@ -113,6 +169,69 @@ impl CpAdapter {
         }
     }
 
+    /// Convert one day's timetable response into rows for `direction`, each
+    /// paired with its full scheduled date-time.
+    ///
+    /// CP lists a service day's trains in time order, so a time that jumps
+    /// backwards by more than twelve hours belongs to the following calendar
+    /// day (e.g. a 00:20 train at the end of the 2026-03-14 board).
+    pub(crate) fn dated_rows(
+        date: NaiveDate,
+        station_id: &str,
+        response: &CpTimetableResponse,
+        direction: BoardDirection,
+    ) -> Vec<(NaiveDateTime, StationTimetable)> {
+        let mut day = ServiceDay::new(date);
+        let mut rows = Vec::new();
+
+        for stop in &response.station_stops {
+            let mut row = Self::convert_stop_to_timetable(stop, station_id);
+            let Some(time) = row
+                .scheduled_time(direction)
+                .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+            else {
+                continue;
+            };
+
+            row.is_departure = direction == BoardDirection::Departures;
+            rows.push((day.place(time), row));
+        }
+
+        rows
+    }
+
+    /// Board of the rows inside `window`; see [`window_rows`](Self::window_rows).
+    pub(crate) fn assemble_board(
+        station_id: &str,
+        rows: Vec<(NaiveDateTime, StationTimetable)>,
+        window: &BoardWindow,
+    ) -> StationBoard {
+        StationBoard {
+            station_id: station_id.to_string(),
+            station_name: String::new(),
+            trains: Self::window_rows(rows, window)
+                .into_iter()
+                .map(|(_, row)| row)
+                .collect(),
+        }
+    }
+
+    /// Keep the rows inside `window`, order them by scheduled time, drop the
+    /// duplicates that appear on two consecutive service days and apply the
+    /// window's result limit.
+    pub(crate) fn window_rows(
+        mut rows: Vec<(NaiveDateTime, StationTimetable)>,
+        window: &BoardWindow,
+    ) -> Vec<(NaiveDateTime, StationTimetable)> {
+        rows.retain(|(at, _)| window.contains(*at));
+        rows.sort_by_key(|(at, row)| (*at, row.train_number));
+        rows.dedup_by_key(|(at, row)| (*at, row.train_number));
+        if let Some(max) = window.get_max_results() {
+            rows.truncate(max);
+        }
+        rows
+    }
+
     pub(crate) fn convert_stop_to_timetable(
         stop: &CpStationStop,
         _station_id: &str,
@@ -136,12 +255,24 @@ impl CpAdapter {
             platform: stop.platform.clone(),
             delay: stop.delay,
             observations: stop.supression.clone(),
+            occupancy: Occupancy::from_cp_code(stop.occupancy),
             operator: "CP".to_string(),
             has_passed: false,
             is_departure,
         }
     }
 
+    fn timetable_url(&self, station_id: &str, date: &str, start_time: Option<&str>) -> String {
+        let mut url = format!(
+            "{}/services/travel-api/stations/{}/timetable/{}",
+            self.base_url, station_id, date
+        );
+        if let Some(start) = start_time {
+            write!(url, "?start={start}").expect("writing to String never fails");
+        }
+        url
+    }
+
     async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
         let response = self
             .client
@@ -173,10 +304,17 @@ impl CpAdapter {
 
 #[cfg(test)]
 mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
     use crate::adapters::cp_adapter::CpAdapter;
     use crate::domain::cp_types::{
         CpServiceCode, CpStationSimple, CpStationStop, CpTimetableResponse,
     };
+    use crate::domain::occupancy::Occupancy;
+    use crate::domain::station_timetable::{BoardDirection, StationTimetable};
+    use crate::query_builder::BoardWindow;
 
     fn make_station(code: &str, designation: &str) -> CpStationSimple {
         CpStationSimple {
@@ -198,7 +336,7 @@ mod tests {
             departure_time: Some("10:02".to_string()),
             platform: Some("3".to_string()),
             delay: Some(5),
-            occupancy: None,
+            occupancy: Some(2),
             eta: Some("10:05".to_string()),
             etd: Some("10:07".to_string()),
             supression: Some("Supressão".to_string()),
@@ -221,6 +359,7 @@ mod tests {
         assert_eq!(timetable.platform, Some("3".to_string()));
         assert_eq!(timetable.delay, Some(5));
         assert_eq!(timetable.observations, Some("Supressão".to_string()));
+        assert_eq!(timetable.occupancy, Occupancy::Medium);
         assert_eq!(timetable.operator, "CP");
         assert!(!timetable.has_passed);
         assert!(timetable.is_departure);
@@ -246,4 +385,173 @@ mod tests {
         assert_eq!(board.station_id, "94-123");
         assert_eq!(board.station_name, "");
     }
+
+    fn make_timed_stop(
+        train_number: u64,
+        departure: Option<&str>,
+        arrival: Option<&str>,
+    ) -> CpStationStop {
+        let mut stop = make_stop();
+        stop.train_number = train_number;
+        stop.departure_time = departure.map(str::to_string);
+        stop.arrival_time = arrival.map(str::to_string);
+        stop
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    #[test]
+    fn dated_rows_keeps_only_requested_direction() {
+        let response = CpTimetableResponse {
+            station_stops: vec![
+                make_timed_stop(1, Some("10:00"), None),
+                make_timed_stop(2, None, Some("10:05")),
+                make_timed_stop(3, Some("10:12"), Some("10:10")),
+            ],
+            messages: vec![],
+        };
+
+        let departures =
+            CpAdapter::dated_rows(day(14), "94-123", &response, BoardDirection::Departures);
+        let arrivals =
+            CpAdapter::dated_rows(day(14), "94-123", &response, BoardDirection::Arrivals);
+
+        let numbers = |rows: &[(NaiveDateTime, StationTimetable)]| -> Vec<u64> {
+            rows.iter().map(|(_, r)| r.train_number).collect()
+        };
+        assert_eq!(numbers(&departures), vec![1, 3]);
+        assert_eq!(numbers(&arrivals), vec![2, 3]);
+        assert!(departures.iter().all(|(_, r)| r.is_departure));
+        assert!(arrivals.iter().all(|(_, r)| !r.is_departure));
+    }
+
+    #[test]
+    fn dated_rows_rolls_after_midnight_trains_into_next_day() {
+        let response = CpTimetableResponse {
+            station_stops: vec![
+                make_timed_stop(1, Some("23:40"), None),
+                make_timed_stop(2, Some("00:20"), None),
+            ],
+            messages: vec![],
+        };
+
+        let rows = CpAdapter::dated_rows(day(14), "94-123", &response, BoardDirection::Departures);
+
+        assert_eq!(rows[0].0, day(14).and_hms_opt(23, 40, 0).unwrap());
+        assert_eq!(rows[1].0, day(15).and_hms_opt(0, 20, 0).unwrap());
+    }
+
+    #[test]
+    fn assemble_board_filters_sorts_dedups_and_limits() {
+        let at = |d: u32, h: u32, m: u32| day(d).and_hms_opt(h, m, 0).unwrap();
+        let row = |n: u64| {
+            CpAdapter::convert_stop_to_timetable(&make_timed_stop(n, Some("00:00"), None), "94-123")
+        };
+        let rows = vec![
+            (at(15, 0, 30), row(3)),
+            (at(14, 21, 0), row(0)), // before window
+            (at(14, 23, 30), row(1)),
+            (at(15, 0, 10), row(2)),
+            (at(15, 0, 10), row(2)), // listed on both service days
+            (at(15, 1, 0), row(4)),  // end is exclusive
+        ];
+        let window = BoardWindow::new(at(14, 23, 0)..at(15, 1, 0)).max_results(2);
+
+        let board = CpAdapter::assemble_board("94-123", rows, &window);
+
+        let numbers: Vec<u64> = board.trains.iter().map(|t| t.train_number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn get_board_across_midnight_queries_each_service_day() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-123/timetable/2026-03-14",
+            ))
+            .and(query_param("start", "23:00"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [
+                    stop_json(100, "23:30"),
+                    stop_json(101, "00:15"),
+                ],
+                "messages": []
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-123/timetable/2026-03-15",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [
+                    stop_json(101, "00:15"),
+                    stop_json(102, "00:45"),
+                    stop_json(103, "06:00"),
+                ],
+                "messages": []
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cp = CpAdapter::with_base_url(
+            &mock_server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let window = BoardWindow::new(
+            day(14).and_hms_opt(23, 0, 0).unwrap()..day(15).and_hms_opt(1, 0, 0).unwrap(),
+        );
+
+        let board = cp
+            .get_board("94-123", &window, BoardDirection::Departures)
+            .await
+            .unwrap();
+
+        let numbers: Vec<u64> = board.trains.iter().map(|t| t.train_number).collect();
+        assert_eq!(numbers, vec![100, 101, 102]);
+    }
+
+    #[tokio::test]
+    async fn get_board_rejects_empty_window() {
+        let cp = CpAdapter::with_base_url(
+            "http://unused",
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let at = day(14).and_hms_opt(10, 0, 0).unwrap();
+
+        let err = cp
+            .get_board(
+                "94-123",
+                &BoardWindow::new(at..at),
+                BoardDirection::Arrivals,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, crate::error::CoreError::InvalidInput(_)),
+            "expected InvalidInput, got {err:?}"
+        );
+    }
+
+    fn stop_json(train_number: u64, departure: &str) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train_number,
+            "trainService": {"code": "R", "designation": "Regional"},
+            "trainOrigin": {"code": "94-001", "designation": "Lisboa"},
+            "trainDestination": {"code": "94-002", "designation": "Porto"},
+            "departureTime": departure,
+        })
+    }
 }
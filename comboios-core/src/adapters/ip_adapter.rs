@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
 use reqwest::Client;
 
 use crate::constants::{IP_BASE_URL, USER_AGENT};
@@ -69,10 +71,13 @@ impl IpAdapter {
         self.get(url).await
     }
 
+    /// Fetch a train's journey for `date` (`YYYY-MM-DD`), deriving stop status
+    /// as seen at `now`.
     pub async fn get_train_journey(
         &self,
         train_number: &str,
         date: &str,
+        now: DateTime<Tz>,
     ) -> Result<Option<TrainJourney>, CoreError> {
         let url = format!(
             "{}/negocios-e-servicos/horarios-ncombio/{}/{}",
@@ -82,7 +87,14 @@ impl IpAdapter {
         );
 
         match self.get::<IpTrainJourneyWrapper>(url).await {
-            Ok(wrapper) => Ok(Some(wrapper.response.to_train_journey(train_number))),
+            Ok(wrapper) => {
+                let service_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok();
+                Ok(Some(wrapper.response.to_train_journey_at(
+                    train_number,
+                    service_date,
+                    now,
+                )))
+            }
             Err(CoreError::ApiError { status: 404, .. }) => Ok(None),
             Err(e) => Err(e),
         }
@@ -123,6 +135,7 @@ mod tests {
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     use super::IpAdapter;
+    use crate::clock::{Clock, SystemClock};
     use crate::error::CoreError;
 
     // --- search_stations ---
@@ -248,7 +261,10 @@ mod tests {
             .await;
 
         let ip = IpAdapter::with_url(&mock_server.uri());
-        let result = ip.get_train_journey("999", "2024-01-01").await.unwrap();
+        let result = ip
+            .get_train_journey("999", "2024-01-01", SystemClock.now())
+            .await
+            .unwrap();
 
         assert!(result.is_none(), "expected None for 404, got {result:?}");
     }
@@ -292,7 +308,7 @@ mod tests {
 
         let ip = IpAdapter::with_url(&mock_server.uri());
         let journey = ip
-            .get_train_journey("530", "2024-01-01")
+            .get_train_journey("530", "2024-01-01", SystemClock.now())
             .await
             .unwrap()
             .expect("expected Some(journey)");
@@ -1,11 +1,32 @@
 use std::sync::Arc;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use futures::stream::{self, StreamExt};
 use tokio::sync::RwLock;
 
-use crate::adapters::{CpAdapter, CpConfigProvider, IpAdapter};
+use crate::adapters::{CpAdapter, CpConfigProvider, IpAdapter, normalize_station_id};
+use crate::clock::{Clock, SystemClock};
 use crate::domain::{
-    journey::TrainJourney, station::StationResponse, station_timetable::StationBoardResponse,
+    journey::TrainJourney,
+    position::{Coordinates, TrainPosition},
+    station::{Station, StationIndex, StationResponse},
+    station_timetable::{BoardDirection, StationBoard, StationBoardResponse, StationTimetable},
+    trip::{DirectTrain, MAX_DIRECT_TRIP_HOURS, MAX_ORDER_CHECKS},
 };
 use crate::error::CoreError;
+use crate::gtfs::{self, GtfsFeed};
+use crate::planner::{Itinerary, PlannerConfig};
+use crate::prediction;
+use crate::query_builder::{BoardFilter, BoardWindow};
+use crate::risk::{self, ConnectionRisk, ItineraryAssessment, MAX_ALTERNATIVES};
+use crate::watch::{self, BoardEvent, JourneyEvent};
+
+/// Journeys fetched at once by [`Comboios::find_trains`] to confirm stop
+/// order.
+const FIND_TRAINS_CONCURRENCY: usize = 4;
+
+/// Journeys fetched at once by [`Comboios::gtfs_feed`].
+const GTFS_EXPORT_CONCURRENCY: usize = 4;
 
 /// Async client for the CP (Comboios de Portugal) and IP (Infraestruturas de Portugal) APIs.
 ///
@@ -30,6 +51,8 @@ pub struct Comboios {
     cp: Arc<RwLock<CpAdapter>>,
     ip: IpAdapter,
     config_provider: CpConfigProvider,
+    stations: Arc<RwLock<StationIndex>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl std::fmt::Debug for Comboios {
@@ -67,15 +90,104 @@ impl Comboios {
             &connect_id[..8.min(connect_id.len())]
         );
 
-        Ok(Self {
-            cp: Arc::new(RwLock::new(CpAdapter::new(
-                api_key,
-                connect_id,
-                connect_secret,
-            ))),
-            ip: IpAdapter::new(),
+        let client = Self {
             config_provider,
-        })
+            ..Self::from_adapters(
+                CpAdapter::new(api_key, connect_id, connect_secret),
+                IpAdapter::new(),
+            )
+        };
+
+        // A missing station index only costs us names on boards, so don't
+        // fail construction over it.
+        if let Err(e) = client.refresh_station_index().await {
+            tracing::warn!("Failed to load station index: {e}");
+        }
+
+        Ok(client)
+    }
+
+    /// Build a client around ready-made adapters, without fetching
+    /// credentials or the station index. Used to point the client at mock
+    /// servers in tests.
+    pub(crate) fn from_adapters(cp: CpAdapter, ip: IpAdapter) -> Self {
+        Self {
+            cp: Arc::new(RwLock::new(cp)),
+            ip,
+            config_provider: CpConfigProvider::new(),
+            stations: Arc::new(RwLock::new(StationIndex::default())),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Replace the clock used to evaluate live journey status.
+    ///
+    /// Defaults to [`SystemClock`] (`Europe/Lisbon`). A
+    /// [`FixedClock`](crate::clock::FixedClock) makes status derivation
+    /// deterministic in tests and replays.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// The clock this client evaluates live status against.
+    #[must_use]
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// Today's service date in `Europe/Lisbon`, formatted as `YYYY-MM-DD` for
+    /// the date arguments of this client.
+    #[must_use]
+    pub fn today(&self) -> String {
+        self.clock.today().format("%Y-%m-%d").to_string()
+    }
+
+    /// Reload the station index used to fill station names on boards and
+    /// journeys, returning the number of stations loaded.
+    ///
+    /// Called once by [`new`]; call it again periodically from long-running
+    /// processes to pick up new stations. On failure the previous index is
+    /// kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::NetworkError`] on connectivity failures or
+    /// [`CoreError::ApiError`] if the CP API returns a non-success status.
+    ///
+    /// [`new`]: Self::new
+    pub async fn refresh_station_index(&self) -> Result<usize, CoreError> {
+        let stations = self.cp.read().await.list_stations().await?;
+        let mut index = StationIndex::new(stations.iter().map(|s| Station {
+            code: s.code.clone(),
+            designation: s.designation.clone(),
+        }));
+        for s in &stations {
+            if let Some(at) = Coordinates::parse(s.latitude.as_deref(), s.longitude.as_deref()) {
+                index.set_coordinates(&s.code, at);
+            }
+        }
+        let count = index.len();
+
+        *self.stations.write().await = index;
+        tracing::info!("Loaded {} stations into station index", count);
+
+        Ok(count)
+    }
+
+    /// Return a snapshot of the current station index.
+    pub async fn station_index(&self) -> StationIndex {
+        self.stations.read().await.clone()
+    }
+
+    /// Look up a station's human-readable name by id (CP or IP format).
+    pub async fn station_name(&self, station_id: &str) -> Option<String> {
+        self.stations
+            .read()
+            .await
+            .name_of(station_id)
+            .map(str::to_string)
     }
 
     /// Re-fetch CP API credentials from `cp.pt` and replace the ones currently
@@ -142,8 +254,344 @@ impl Comboios {
         date: &str,
         start_time: Option<&str>,
     ) -> Result<StationBoardResponse, CoreError> {
+        let mut boards = {
+            let cp = self.cp.read().await;
+            cp.get_station_timetable(station_id, date, start_time)
+                .await?
+        };
+
+        let stations = self.stations.read().await;
+        for board in &mut boards.response {
+            stations.fill_board(board);
+        }
+        Ok(boards)
+    }
+
+    /// Retrieve a station board like [`get_station_timetable`], keeping only
+    /// the rows that satisfy `filter`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`get_station_timetable`].
+    ///
+    /// [`get_station_timetable`]: Self::get_station_timetable
+    pub async fn get_station_timetable_filtered(
+        &self,
+        station_id: &str,
+        date: &str,
+        start_time: Option<&str>,
+        filter: &BoardFilter,
+    ) -> Result<StationBoardResponse, CoreError> {
+        let mut boards = self
+            .get_station_timetable(station_id, date, start_time)
+            .await?;
+        for board in &mut boards.response {
+            board.trains.retain(|t| filter.matches(t));
+        }
+        Ok(boards)
+    }
+
+    /// Retrieve the trains departing `station_id` within `window`.
+    ///
+    /// Unlike [`get_station_timetable`], the result is a single board that
+    /// only contains departures, ordered by scheduled departure time. Windows
+    /// that cross midnight are assembled from one upstream request per service
+    /// day, and the window's `max_results` limit is applied last.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chrono::NaiveDate;
+    /// use comboios_core::{Comboios, query_builder::BoardWindow};
+    ///
+    /// # async fn run(client: Comboios) -> Result<(), comboios_core::Error> {
+    /// let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+    /// let window = BoardWindow::new(
+    ///     day.and_hms_opt(23, 0, 0).unwrap()..day.succ_opt().unwrap().and_hms_opt(1, 0, 0).unwrap(),
+    /// )
+    /// .max_results(5);
+    ///
+    /// let board = client.departures("94-31039", window).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidInput`] if the window is empty or longer
+    /// than [`MAX_BOARD_WINDOW_DAYS`], [`CoreError::NetworkError`] on
+    /// connectivity failures, or [`CoreError::ApiError`] for non-success HTTP
+    /// responses.
+    ///
+    /// [`get_station_timetable`]: Self::get_station_timetable
+    /// [`MAX_BOARD_WINDOW_DAYS`]: crate::query_builder::MAX_BOARD_WINDOW_DAYS
+    pub async fn departures(
+        &self,
+        station_id: &str,
+        window: impl Into<BoardWindow>,
+    ) -> Result<StationBoard, CoreError> {
+        self.board(station_id, window.into(), BoardDirection::Departures)
+            .await
+    }
+
+    /// Retrieve the trains arriving at `station_id` within `window`.
+    ///
+    /// The arrival-side counterpart of [`departures`]: a single board holding
+    /// only arrivals, ordered by scheduled arrival time.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`departures`].
+    ///
+    /// [`departures`]: Self::departures
+    pub async fn arrivals(
+        &self,
+        station_id: &str,
+        window: impl Into<BoardWindow>,
+    ) -> Result<StationBoard, CoreError> {
+        self.board(station_id, window.into(), BoardDirection::Arrivals)
+            .await
+    }
+
+    /// The rows of [`departures`](Self::departures), each paired with its
+    /// scheduled date-time, so trains past midnight keep their own day.
+    pub(crate) async fn dated_departures(
+        &self,
+        station_id: &str,
+        window: BoardWindow,
+    ) -> Result<Vec<(NaiveDateTime, StationTimetable)>, CoreError> {
         let cp = self.cp.read().await;
-        cp.get_station_timetable(station_id, date, start_time).await
+        cp.get_dated_board(station_id, &window, BoardDirection::Departures)
+            .await
+    }
+
+    async fn board(
+        &self,
+        station_id: &str,
+        window: BoardWindow,
+        direction: BoardDirection,
+    ) -> Result<StationBoard, CoreError> {
+        let mut board = {
+            let cp = self.cp.read().await;
+            cp.get_board(station_id, &window, direction).await?
+        };
+        self.stations.read().await.fill_board(&mut board);
+        Ok(board)
+    }
+
+    /// List the direct trains from `from` to `to` on `date`, earliest first.
+    ///
+    /// Stations are given by id (CP or IP format) or by name, as in
+    /// [`StationIndex::resolve`]. Candidates are the trains on both the
+    /// departure board of `from` and the arrival board of `to`. Unless the
+    /// train starts at `from` or ends at `to`, its journey is fetched to
+    /// confirm it calls at `from` before `to`, for at most
+    /// [`MAX_ORDER_CHECKS`] trains. Trains whose journey is not checked or
+    /// cannot be fetched are kept on the strength of the boards.
+    ///
+    /// - `date` — calendar date in `YYYY-MM-DD` format.
+    /// - `after` — optional clock time in `HH:MM` format; only trains leaving
+    ///   `from` at or after it are returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use comboios_core::Comboios;
+    ///
+    /// # async fn run(client: Comboios) -> Result<(), comboios_core::Error> {
+    /// let today = client.today();
+    /// for train in client.find_trains("Porto - Campanhã", "Aveiro", &today, Some("17:00")).await? {
+    ///     println!("{} {} -> {}", train.train_number, train.departure, train.arrival);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidInput`] if `date` or `after` are malformed,
+    /// a station cannot be resolved, or both stations are the same;
+    /// otherwise the same errors as [`departures`](Self::departures).
+    pub async fn find_trains(
+        &self,
+        from: &str,
+        to: &str,
+        date: &str,
+        after: Option<&str>,
+    ) -> Result<Vec<DirectTrain>, CoreError> {
+        let start = parse_service_start(date, after)?;
+        let service_date = start.date();
+        let from = self.resolve_station(from).await?;
+        let to = self.resolve_station(to).await?;
+        if normalize_station_id(&from) == normalize_station_id(&to) {
+            return Err(CoreError::InvalidInput(
+                "origin and destination must be different stations".to_string(),
+            ));
+        }
+
+        let end = service_date.and_time(NaiveTime::MIN) + Duration::days(1);
+        let (departures, arrivals) = futures::try_join!(
+            self.departures(&from, start..end),
+            self.arrivals(&to, start..end + Duration::hours(MAX_DIRECT_TRIP_HOURS)),
+        )?;
+
+        let mut checks = 0;
+        let candidates: Vec<_> = DirectTrain::match_boards(&departures, &arrivals, service_date)
+            .into_iter()
+            .map(|train| {
+                let check = !train.order_is_known() && checks < MAX_ORDER_CHECKS;
+                checks += usize::from(check);
+                (train, check)
+            })
+            .collect();
+        let trains = stream::iter(candidates)
+            .map(|(mut train, check)| async move {
+                if !check {
+                    return Some(train);
+                }
+                match self.get_train_journey(&train.train_number, date).await {
+                    Ok(journey) if train.serves_in_order(&journey) == Some(false) => {
+                        tracing::debug!(
+                            "Train {} calls at {} before {}, skipped",
+                            train.train_number,
+                            train.to.code,
+                            train.from.code
+                        );
+                        None
+                    }
+                    Ok(journey) => {
+                        train.delay_minutes = train.delay_minutes.or(journey.delay_minutes);
+                        Some(train)
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Could not confirm stop order of train {}: {}",
+                            train.train_number,
+                            e
+                        );
+                        Some(train)
+                    }
+                }
+            })
+            .buffered(FIND_TRAINS_CONCURRENCY)
+            .filter_map(std::future::ready)
+            .collect()
+            .await;
+
+        Ok(trains)
+    }
+
+    /// Direct itineraries from `from` to `to` on `date`, earliest departure
+    /// first: the trains of [`find_trains`](Self::find_trains), each as a
+    /// single leg.
+    ///
+    /// They come from the same boards and journeys as `find_trains`, so the
+    /// stop order is checked the same way. For itineraries with changes of
+    /// train, use [`TripPlanner`](crate::planner::TripPlanner).
+    ///
+    /// # Errors
+    ///
+    /// The same errors as [`find_trains`](Self::find_trains).
+    pub async fn search_trips(
+        &self,
+        from: &str,
+        to: &str,
+        date: &str,
+        after: Option<&str>,
+    ) -> Result<Vec<Itinerary>, CoreError> {
+        let trains = self.find_trains(from, to, date, after).await?;
+        Ok(trains.iter().map(Itinerary::from).collect())
+    }
+
+    /// Check whether the changes of train in `itinerary` will still work out,
+    /// given where its trains are now.
+    ///
+    /// The live journey of every leg is fetched and the slack at each change
+    /// is worked out from actual and predicted times, or the current delay
+    /// (see [`risk::assess`]), against the minimum transfer times of
+    /// `config` — use the one the itinerary was planned with. For a missed
+    /// connection, up to [`MAX_ALTERNATIVES`] later direct trains from the
+    /// change station to the end of the onward leg are suggested.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last journey error when no leg's journey could be
+    /// fetched; legs that fail on their own are assumed to run on time.
+    pub async fn assess_connections(
+        &self,
+        itinerary: &Itinerary,
+        config: &PlannerConfig,
+    ) -> Result<ItineraryAssessment, CoreError> {
+        let results = futures::future::join_all(itinerary.legs.iter().map(|leg| {
+            let date = leg.departure.format("%Y-%m-%d").to_string();
+            async move { self.get_train_journey(&leg.train_number, &date).await }
+        }))
+        .await;
+
+        let mut last_error = None;
+        let journeys: Vec<Option<TrainJourney>> = results
+            .into_iter()
+            .map(|result| {
+                result
+                    .inspect_err(|e| {
+                        tracing::warn!("Live journey for connection check failed: {e}")
+                    })
+                    .map_err(|e| last_error = Some(e))
+                    .ok()
+            })
+            .collect();
+        if journeys.iter().all(Option::is_none)
+            && let Some(e) = last_error
+        {
+            return Err(e);
+        }
+
+        let mut assessment = risk::assess(itinerary, &journeys, config);
+
+        for (transfer, onward) in assessment
+            .transfers
+            .iter_mut()
+            .zip(itinerary.legs.iter().skip(1))
+            .filter(|(t, _)| t.risk == ConnectionRisk::Missed)
+        {
+            let ready =
+                transfer.expected_arrival + Duration::minutes(transfer.min_transfer_minutes);
+            let date = ready.format("%Y-%m-%d").to_string();
+            let after = ready.format("%H:%M").to_string();
+            match self
+                .find_trains(&transfer.station.code, &onward.to.code, &date, Some(&after))
+                .await
+            {
+                Ok(trains) => {
+                    transfer.alternatives = trains
+                        .into_iter()
+                        .filter(|t| !t.is_cancelled() && t.train_number != onward.train_number)
+                        .take(MAX_ALTERNATIVES)
+                        .collect();
+                }
+                Err(e) => tracing::warn!(
+                    "No alternatives for missed connection at {}: {e}",
+                    transfer.station.code
+                ),
+            }
+        }
+
+        Ok(assessment)
+    }
+
+    /// Resolve a station given by id or name against the station index,
+    /// returning its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidInput`] if `station` is neither a known
+    /// name nor shaped like an id.
+    pub async fn resolve_station(&self, station: &str) -> Result<String, CoreError> {
+        self.stations
+            .read()
+            .await
+            .resolve(station)
+            .ok_or_else(|| CoreError::InvalidInput(format!("unknown station: {station}")))
     }
 
     /// Retrieve live journey details for a train, including stop-by-stop status
@@ -153,6 +601,9 @@ impl Comboios {
     /// CP system), the Infraestruturas de Portugal API is used as a fallback.
     /// Only the last error (from IP) is returned when both sources fail.
     ///
+    /// Stops the train has not reached yet carry a predicted time propagated
+    /// from the current delay (see [`crate::prediction`]).
+    ///
     /// - `train_number` — CP train identifier (e.g. `"120"`).
     /// - `date` — calendar date in `YYYY-MM-DD` format.
     ///
@@ -165,6 +616,57 @@ impl Comboios {
         &self,
         train_number: &str,
         date: &str,
+    ) -> Result<TrainJourney, CoreError> {
+        let mut journey = self.fetch_train_journey(train_number, date).await?;
+        self.stations.read().await.fill_journey(&mut journey);
+        prediction::predict(&mut journey);
+        Ok(journey)
+    }
+
+    /// Follow a train, yielding a [`JourneyEvent`] for every change between
+    /// successive polls of [`get_train_journey`](Self::get_train_journey):
+    /// arrivals, departures, delay and platform changes, cancellations and
+    /// completion (see [`JourneyTracker`](watch::JourneyTracker)).
+    ///
+    /// A running train is polled every `interval`; before departure polls are
+    /// spaced out (see [`watch::next_poll`]). The stream ends once the
+    /// journey is completed or cancelled, or after
+    /// [`MAX_NOT_FOUND_POLLS`](watch::MAX_NOT_FOUND_POLLS) polls in a row
+    /// answered with `404`. Any other failed poll yields its error and
+    /// polling carries on, so drop the stream to stop watching.
+    pub fn watch_train(
+        &self,
+        train_number: &str,
+        date: &str,
+        interval: std::time::Duration,
+    ) -> impl futures::Stream<Item = Result<JourneyEvent, CoreError>> + Send + 'static {
+        watch::watch_train(self.clone(), train_number, date, interval)
+    }
+
+    /// Follow the departure board of `station` (id or name) over `window`,
+    /// yielding a [`BoardEvent`] for every train added, removed, given or
+    /// moved to another platform, delayed, cancelled or departed (see
+    /// [`BoardTracker`](watch::BoardTracker)).
+    ///
+    /// The first poll adds every train on the board. The board is polled
+    /// every `interval` (see [`BOARD_POLL_INTERVAL`](watch::BOARD_POLL_INTERVAL))
+    /// and the window moves along with the clock, keeping its length and
+    /// result limit; late trains stay on it until they leave. The station is
+    /// resolved once, on the first poll. The stream never ends by itself; a
+    /// failed poll yields its error and polling carries on.
+    pub fn watch_board(
+        &self,
+        station: &str,
+        window: impl Into<BoardWindow>,
+        interval: std::time::Duration,
+    ) -> impl futures::Stream<Item = Result<BoardEvent, CoreError>> + Send + 'static {
+        watch::watch_board(self.clone(), station, window.into(), interval)
+    }
+
+    async fn fetch_train_journey(
+        &self,
+        train_number: &str,
+        date: &str,
     ) -> Result<TrainJourney, CoreError> {
         let cp = self.cp.read().await;
         match cp.get_train_journey(train_number, date).await {
@@ -178,7 +680,11 @@ impl Comboios {
                     train_number,
                     e
                 );
-                match self.ip.get_train_journey(train_number, date).await {
+                match self
+                    .ip
+                    .get_train_journey(train_number, date, self.clock.now())
+                    .await
+                {
                     Ok(Some(journey)) => {
                         tracing::info!("IP train journey succeeded for {}", train_number);
                         Ok(journey)
@@ -201,6 +707,95 @@ impl Comboios {
         }
     }
 
+    /// Estimate where a train is right now, for drawing it on a map.
+    ///
+    /// Uses the GPS position when CP reports one. Otherwise the train is
+    /// placed at the station it is standing at, or interpolated between the
+    /// last stop it left and the next one from station coordinates and the
+    /// delayed (or predicted) times. See [`TrainPosition::estimate`].
+    ///
+    /// Returns `Ok(None)` for cancelled trains and when the stations involved
+    /// have no known coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`get_train_journey`](Self::get_train_journey).
+    pub async fn train_position(
+        &self,
+        train_number: &str,
+        date: &str,
+    ) -> Result<Option<TrainPosition>, CoreError> {
+        let journey = self.get_train_journey(train_number, date).await?;
+        let now = self.clock.now().naive_local();
+        let service_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .or(journey.origin_departure.map(|d| d.date()))
+            .unwrap_or(now.date());
+
+        Ok(TrainPosition::estimate(
+            &journey,
+            &*self.stations.read().await,
+            service_date,
+            now,
+        ))
+    }
+
+    /// Build a [GTFS static feed](crate::gtfs) of `trains` running on every
+    /// date from `from` to `to`, inclusive.
+    ///
+    /// Journeys are fetched with [`get_train_journey`](Self::get_train_journey);
+    /// a train that cannot be fetched on a date is logged and left out of
+    /// that date. The feed is not validated here: call
+    /// [`GtfsFeed::validate`] or [`GtfsFeed::write_zip`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidInput`] if no trains are given, `to` is
+    /// before `from`, or the range is longer than
+    /// [`MAX_EXPORT_DAYS`](crate::gtfs::MAX_EXPORT_DAYS).
+    pub async fn gtfs_feed(
+        &self,
+        trains: &[&str],
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<GtfsFeed, CoreError> {
+        if trains.is_empty() {
+            return Err(CoreError::InvalidInput("no trains to export".to_string()));
+        }
+        let days = (to - from).num_days() + 1;
+        if !(1..=gtfs::MAX_EXPORT_DAYS).contains(&days) {
+            return Err(CoreError::InvalidInput(format!(
+                "date range must cover 1 to {} days, got {from} to {to}",
+                gtfs::MAX_EXPORT_DAYS
+            )));
+        }
+
+        let requests = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .flat_map(|date| trains.iter().map(move |train| (date, train.trim())));
+        let journeys: Vec<_> = stream::iter(requests)
+            .map(|(date, train)| async move {
+                let day = date.format("%Y-%m-%d").to_string();
+                match self.get_train_journey(train, &day).await {
+                    Ok(journey) => Some((date, journey)),
+                    Err(e) => {
+                        tracing::warn!("Train {train} on {day} left out of GTFS feed: {e}");
+                        None
+                    }
+                }
+            })
+            .buffered(GTFS_EXPORT_CONCURRENCY)
+            .filter_map(std::future::ready)
+            .collect()
+            .await;
+
+        Ok(GtfsFeed::from_journeys(
+            &*self.stations.read().await,
+            &journeys,
+        ))
+    }
+
     /// Return a reference to the underlying config provider.
     ///
     /// Useful for inspecting cached credential state or integrating custom
@@ -210,3 +805,366 @@ impl Comboios {
         &self.config_provider
     }
 }
+
+/// Parse a `YYYY-MM-DD` service date and an optional `HH:MM` start time
+/// (midnight when absent) into the date-time a search starts from.
+pub(crate) fn parse_service_start(
+    date: &str,
+    after: Option<&str>,
+) -> Result<NaiveDateTime, CoreError> {
+    let service_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| CoreError::InvalidInput(format!("invalid date: {date}")))?;
+    let time = match after {
+        Some(t) => NaiveTime::parse_from_str(t, "%H:%M")
+            .map_err(|_| CoreError::InvalidInput(format!("invalid time: {t}")))?,
+        None => NaiveTime::MIN,
+    };
+    Ok(service_date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::clock::FixedClock;
+
+    fn board_row(train: u64, arrival: &str, departure: &str) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "trainService": {"code": "R", "designation": "Regional"},
+            "trainOrigin": {"code": "94-0", "designation": "O"},
+            "trainDestination": {"code": "94-9", "designation": "T"},
+            "arrivalTime": arrival,
+            "departureTime": departure,
+        })
+    }
+
+    fn timetable(train: u64, codes: [&str; 3]) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "serviceCode": {"code": "R", "designation": "Regional"},
+            "trainStops": [
+                {"station": {"code": codes[0], "designation": "X"}, "departure": "10:00"},
+                {"station": {"code": codes[1], "designation": "Y"}, "arrival": "10:20", "departure": "10:21"},
+                {"station": {"code": codes[2], "designation": "Z"}, "arrival": "10:40"}
+            ],
+            "status": "SCHEDULED",
+            "messages": []
+        })
+    }
+
+    fn client(server: &MockServer) -> Comboios {
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let now = NaiveDate::from_ymd_opt(2026, 3, 14)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()))
+            .with_clock(FixedClock::at_local(now).unwrap())
+    }
+
+    async fn mount_json(server: &MockServer, url: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn find_trains_keeps_trains_calling_in_order() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-1/timetable/2026-03-14",
+            ))
+            .and(query_param("start", "09:30"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [
+                    board_row(100, "10:00", "10:00"),
+                    board_row(200, "10:05", "10:06"),
+                    board_row(300, "10:10", "10:10"),
+                ],
+                "messages": []
+            })))
+            .mount(&server)
+            .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-14",
+            serde_json::json!({
+                "stationStops": [
+                    board_row(100, "10:40", "10:40"),
+                    board_row(200, "10:45", "10:45"),
+                ],
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-15",
+            serde_json::json!({"stationStops": [], "messages": []}),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/trains/100/timetable/2026-03-14",
+            timetable(100, ["94-1", "94-2", "94-3"]),
+        )
+        .await;
+        // Calls at 94-3 first, then 94-1: the wrong way round.
+        mount_json(
+            &server,
+            "/services/travel-api/trains/200/timetable/2026-03-14",
+            timetable(200, ["94-3", "94-2", "94-1"]),
+        )
+        .await;
+
+        let trains = client(&server)
+            .find_trains("94-1", "94-3", "2026-03-14", Some("09:30"))
+            .await
+            .unwrap();
+
+        assert_eq!(trains.len(), 1);
+        assert_eq!(trains[0].train_number, "100");
+        assert_eq!(trains[0].duration_minutes(), 40);
+    }
+
+    #[tokio::test]
+    async fn find_trains_trusts_the_boards_for_trains_starting_at_from() {
+        let server = MockServer::start().await;
+
+        let mut starts_here = board_row(400, "", "10:00");
+        starts_here["trainOrigin"]["code"] = "94-1".into();
+        let mut arrives = board_row(400, "10:40", "");
+        arrives["trainOrigin"]["code"] = "94-1".into();
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-1/timetable/2026-03-14",
+            serde_json::json!({"stationStops": [starts_here], "messages": []}),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-14",
+            serde_json::json!({"stationStops": [arrives], "messages": []}),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-15",
+            serde_json::json!({"stationStops": [], "messages": []}),
+        )
+        .await;
+        Mock::given(method("GET"))
+            .and(path("/services/travel-api/trains/400/timetable/2026-03-14"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let trains = client(&server)
+            .find_trains("94-1", "94-3", "2026-03-14", Some("09:30"))
+            .await
+            .unwrap();
+
+        assert_eq!(trains.len(), 1);
+        assert_eq!(trains[0].train_number, "400");
+    }
+
+    #[tokio::test]
+    async fn find_trains_rejects_bad_input() {
+        let server = MockServer::start().await;
+        let client = client(&server);
+
+        for (from, to, date, after) in [
+            ("94-1", "94-3", "14/03/2026", None),
+            ("94-1", "94-3", "2026-03-14", Some("9h30")),
+            ("94-1", "941", "2026-03-14", None),
+            ("Atlantis", "94-3", "2026-03-14", None),
+        ] {
+            let err = client.find_trains(from, to, date, after).await.unwrap_err();
+            assert!(
+                matches!(err, CoreError::InvalidInput(_)),
+                "expected InvalidInput, got {err:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missed_connection_suggests_alternatives() {
+        let server = MockServer::start().await;
+
+        // Train 100 left 94-1 twenty minutes late and reaches the change at
+        // 94-2 after train 200 has gone.
+        mount_json(
+            &server,
+            "/services/travel-api/trains/100/timetable/2026-03-14",
+            serde_json::json!({
+                "trainNumber": 100,
+                "serviceCode": {"code": "R", "designation": "Regional"},
+                "lastStationCode": "94-1",
+                "delay": 20,
+                "trainStops": [
+                    {"station": {"code": "94-1", "designation": "A"}, "departure": "10:00"},
+                    {"station": {"code": "94-2", "designation": "B"}, "arrival": "10:30"}
+                ],
+                "status": "IN_TRANSIT",
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/trains/200/timetable/2026-03-14",
+            timetable(200, ["94-2", "94-9", "94-3"]),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/trains/300/timetable/2026-03-14",
+            timetable(300, ["94-2", "94-9", "94-3"]),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-2/timetable/2026-03-14",
+            serde_json::json!({
+                "stationStops": [board_row(300, "11:00", "11:00")],
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-14",
+            serde_json::json!({
+                "stationStops": [board_row(300, "11:40", "11:40")],
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-15",
+            serde_json::json!({"stationStops": [], "messages": []}),
+        )
+        .await;
+
+        let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let at = |h, m| day.and_hms_opt(h, m, 0).unwrap();
+        let leg = |train: &str, from: &str, to: &str, dep, arr| crate::planner::TripLeg {
+            train_number: train.to_string(),
+            service_type: "R|Regional".to_string(),
+            operator: "CP".to_string(),
+            from: Station {
+                code: from.to_string(),
+                designation: String::new(),
+            },
+            to: Station {
+                code: to.to_string(),
+                designation: String::new(),
+            },
+            departure: dep,
+            arrival: arr,
+            departure_platform: None,
+            arrival_platform: None,
+        };
+        let itinerary = Itinerary {
+            legs: vec![
+                leg("100", "94-1", "94-2", at(10, 0), at(10, 30)),
+                leg("200", "94-2", "94-3", at(10, 40), at(11, 20)),
+            ],
+            departure: at(10, 0),
+            arrival: at(11, 20),
+            transfers: 1,
+        };
+
+        let config = PlannerConfig::new().transfer_time("94-2", std::time::Duration::from_mins(8));
+        let assessment = client(&server)
+            .assess_connections(&itinerary, &config)
+            .await
+            .unwrap();
+
+        let transfer = &assessment.transfers[0];
+        assert_eq!(transfer.risk, ConnectionRisk::Missed);
+        assert_eq!(transfer.min_transfer_minutes, 8);
+        let alternatives: Vec<&str> = transfer
+            .alternatives
+            .iter()
+            .map(|t| t.train_number.as_str())
+            .collect();
+        assert_eq!(alternatives, vec!["300"]);
+        assert_eq!(assessment.risk, ConnectionRisk::Missed);
+    }
+
+    #[tokio::test]
+    async fn search_trips_are_the_direct_trains_as_single_legs() {
+        let server = MockServer::start().await;
+
+        let departing = |train, time: &str| {
+            let mut row = board_row(train, "", time);
+            row["trainOrigin"]["code"] = "94-1".into();
+            row["platform"] = "2".into();
+            row
+        };
+        let arriving = |train, time: &str| {
+            let mut row = board_row(train, time, "");
+            row["trainOrigin"]["code"] = "94-1".into();
+            row
+        };
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-1/timetable/2026-03-14",
+            serde_json::json!({
+                "stationStops": [departing(500, "19:52"), departing(400, "10:00")],
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-14",
+            serde_json::json!({
+                "stationStops": [arriving(400, "10:40"), arriving(500, "20:30")],
+                "messages": []
+            }),
+        )
+        .await;
+        mount_json(
+            &server,
+            "/services/travel-api/stations/94-3/timetable/2026-03-15",
+            serde_json::json!({"stationStops": [], "messages": []}),
+        )
+        .await;
+
+        let trips = client(&server)
+            .search_trips("94-1", "94-3", "2026-03-14", Some("09:30"))
+            .await
+            .unwrap();
+
+        let trains: Vec<_> = trips
+            .iter()
+            .map(|trip| (trip.legs[0].train_number.as_str(), trip.duration_minutes()))
+            .collect();
+        assert_eq!(trains, [("400", 40), ("500", 38)]);
+        assert!(
+            trips
+                .iter()
+                .all(|trip| trip.transfers == 0 && trip.legs.len() == 1)
+        );
+        assert_eq!(trips[0].legs[0].from.code, "94-1");
+        assert_eq!(trips[0].legs[0].to.code, "94-3");
+        assert_eq!(trips[0].legs[0].departure_platform.as_deref(), Some("2"));
+    }
+}
@@ -0,0 +1,73 @@
+//! Time source used when deriving live journey and board status.
+//!
+//! CP and IP report wall-clock times in Portugal, so "now" is always taken in
+//! `Europe/Lisbon` regardless of the host timezone. Tests and tools that need
+//! deterministic results can substitute a [`FixedClock`].
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Timezone all CP and IP wall-clock times are expressed in.
+pub const TIMEZONE: Tz = chrono_tz::Europe::Lisbon;
+
+/// Source of the current time.
+///
+/// Install a custom clock on the client with [`crate::Comboios::with_clock`].
+pub trait Clock: Send + Sync {
+    /// Current instant in [`TIMEZONE`].
+    fn now(&self) -> DateTime<Tz>;
+
+    /// Current calendar date in [`TIMEZONE`].
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// The system clock, converted to `Europe/Lisbon`. Default for
+/// [`crate::Comboios`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Tz> {
+        Utc::now().with_timezone(&TIMEZONE)
+    }
+}
+
+/// A clock frozen at a single instant.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use comboios_core::clock::{Clock, FixedClock};
+///
+/// let at = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap().and_hms_opt(8, 30, 0).unwrap();
+/// let clock = FixedClock::at_local(at).unwrap();
+/// assert_eq!(clock.today(), at.date());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock {
+    now: DateTime<Tz>,
+}
+
+impl FixedClock {
+    /// Freeze the clock at `now`.
+    #[must_use]
+    pub fn new(now: DateTime<Tz>) -> Self {
+        Self { now }
+    }
+
+    /// Freeze the clock at a Portugal-local wall-clock time. Returns `None`
+    /// for times skipped or repeated by a daylight-saving transition.
+    #[must_use]
+    pub fn at_local(local: NaiveDateTime) -> Option<Self> {
+        TIMEZONE.from_local_datetime(&local).single().map(Self::new)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Tz> {
+        self.now
+    }
+}
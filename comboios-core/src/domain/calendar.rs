@@ -0,0 +1,330 @@
+//! iCalendar (RFC 5545) export of [`TrainJourney`]s.
+//!
+//! Each journey becomes a `VEVENT` from the scheduled departure at its origin
+//! to the scheduled arrival at its destination, in `Europe/Lisbon` time. The
+//! live state — delay, expected arrival, platforms, cancellation — goes in the
+//! summary and description. The `UID` depends only on the train and its
+//! service date, so a calendar subscribed to a regenerated feed updates the
+//! event in place instead of adding a new one.
+
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+use super::journey::{JourneyStatus, TrainJourney};
+use crate::gtfs::trip_id;
+use crate::timeline::Timeline;
+
+/// `TZID` of every event time.
+pub const ICS_TIMEZONE: &str = "Europe/Lisbon";
+
+/// `VTIMEZONE` for [`ICS_TIMEZONE`]: WET in winter, WEST from the last
+/// Sunday of March to the last Sunday of October.
+const LISBON_VTIMEZONE: [&str; 17] = [
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/Lisbon",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:+0000",
+    "TZOFFSETTO:+0100",
+    "TZNAME:WEST",
+    "DTSTART:19700329T010000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:+0100",
+    "TZOFFSETTO:+0000",
+    "TZNAME:WET",
+    "DTSTART:19701025T020000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+/// Longest content line in octets before it is folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A `VCALENDAR` of train journeys.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use chrono::{TimeZone, Utc};
+/// use comboios_core::domain::calendar::Calendar;
+///
+/// let stamp = Utc.with_ymd_and_hms(2026, 3, 14, 21, 0, 0).unwrap();
+/// let ics = Calendar::new(stamp)
+///     .name("Commute")
+///     .refresh_interval(Duration::from_secs(300))
+///     .to_string();
+/// assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+/// assert!(ics.contains("REFRESH-INTERVAL;VALUE=DURATION:PT5M\r\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    name: Option<String>,
+    refresh_interval: Option<Duration>,
+    stamp: DateTime<Utc>,
+    events: Vec<(NaiveDate, TrainJourney)>,
+}
+
+impl Calendar {
+    /// An empty calendar whose events are stamped (`DTSTAMP`) at `stamp`,
+    /// usually when it is generated.
+    #[must_use]
+    pub fn new(stamp: DateTime<Utc>) -> Self {
+        Self {
+            name: None,
+            refresh_interval: None,
+            stamp,
+            events: Vec::new(),
+        }
+    }
+
+    /// Name shown by calendar apps (`X-WR-CALNAME`).
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// How often subscribers should fetch the calendar again
+    /// (`REFRESH-INTERVAL` and `X-PUBLISHED-TTL`).
+    #[must_use]
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = Some(interval);
+        self
+    }
+
+    /// Add `journey`, running on the service day `date`. Journeys without
+    /// stops have no times and are left out.
+    #[must_use]
+    pub fn journey(mut self, journey: &TrainJourney, date: NaiveDate) -> Self {
+        self.events.push((date, journey.clone()));
+        self
+    }
+
+    #[must_use]
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[must_use]
+    pub fn get_refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//comboios//comboios-core//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            "METHOD:PUBLISH".to_string(),
+            format!("X-WR-TIMEZONE:{ICS_TIMEZONE}"),
+        ];
+        if let Some(name) = &self.name {
+            lines.push(format!("X-WR-CALNAME:{}", escape(name)));
+        }
+        if let Some(interval) = self.refresh_interval {
+            let duration = ics_duration(interval);
+            lines.push(format!("REFRESH-INTERVAL;VALUE=DURATION:{duration}"));
+            lines.push(format!("X-PUBLISHED-TTL:{duration}"));
+        }
+        lines.extend(LISBON_VTIMEZONE.iter().map(|line| (*line).to_string()));
+        for (date, journey) in &self.events {
+            lines.extend(journey.vevent(*date, self.stamp));
+        }
+        lines.push("END:VCALENDAR".to_string());
+        lines
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            write_folded(f, &line)?;
+        }
+        Ok(())
+    }
+}
+
+impl TrainJourney {
+    /// The journey as an iCalendar file with a single event, for the
+    /// service day `date`, stamped at `stamp`.
+    #[must_use]
+    pub fn to_ics(&self, date: NaiveDate, stamp: DateTime<Utc>) -> String {
+        Calendar::new(stamp).journey(self, date).to_string()
+    }
+
+    /// Scheduled departure from the origin and arrival at the destination on
+    /// the service day `date`, rolling past midnight; `None` without stops.
+    #[must_use]
+    pub fn scheduled_times(&self, date: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let last = self.stops.len().checked_sub(1)?;
+        let timeline = Timeline::new(&self.stops);
+        let midnight = date.and_time(NaiveTime::MIN);
+        let at = |minutes: i32| midnight + chrono::Duration::minutes(minutes.into());
+        Some((at(timeline.departure(0)), at(timeline.arrival(last))))
+    }
+
+    fn vevent(&self, date: NaiveDate, stamp: DateTime<Utc>) -> Vec<String> {
+        let Some((start, end)) = self.scheduled_times(date) else {
+            return Vec::new();
+        };
+        let cancelled = self.status == JourneyStatus::Cancelled;
+
+        vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@comboios", trip_id(&self.train_number, date)),
+            format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART;TZID={ICS_TIMEZONE}:{}", local(start)),
+            format!("DTEND;TZID={ICS_TIMEZONE}:{}", local(end)),
+            format!("SUMMARY:{}", escape(&self.summary())),
+            format!(
+                "LOCATION:{}",
+                escape(&format!(
+                    "{} → {}",
+                    self.origin.designation, self.destination.designation
+                ))
+            ),
+            format!("DESCRIPTION:{}", escape(&self.description())),
+            format!(
+                "STATUS:{}",
+                if cancelled { "CANCELLED" } else { "CONFIRMED" }
+            ),
+            "TRANSP:OPAQUE".to_string(),
+            "END:VEVENT".to_string(),
+        ]
+    }
+
+    /// e.g. `IC 520 Lisboa - Santa Apolónia → Porto - Campanhã (+12 min)`.
+    fn summary(&self) -> String {
+        let code = self
+            .service_type
+            .split_once('|')
+            .map_or(self.service_type.as_str(), |(code, _)| code)
+            .trim();
+        let train = if code.is_empty() {
+            self.train_number.clone()
+        } else {
+            format!("{code} {}", self.train_number)
+        };
+        let summary = format!(
+            "{train} {} → {}",
+            self.origin.designation, self.destination.designation
+        );
+        match (&self.status, self.delay_minutes) {
+            (JourneyStatus::Cancelled, _) => format!("Cancelled: {summary}"),
+            (_, Some(delay)) if delay > 0 => format!("{summary} (+{delay} min)"),
+            _ => summary,
+        }
+    }
+
+    fn description(&self) -> String {
+        let mut lines = Vec::new();
+        match (&self.status, self.delay_minutes) {
+            (JourneyStatus::Cancelled, _) => lines.push("Cancelled.".to_string()),
+            (_, Some(delay)) if delay > 0 => {
+                lines.push(format!("Running {delay} min late."));
+                if let Some(arrival) = self.estimated_arrival() {
+                    lines.push(format!("Expected arrival {arrival}."));
+                }
+            }
+            (JourneyStatus::InProgress | JourneyStatus::Completed, _) => {
+                lines.push("On time.".to_string());
+            }
+            _ => {}
+        }
+        if let Some(platform) = self.stops.first().and_then(|s| s.platform.as_deref()) {
+            lines.push(format!("Departs from platform {platform}."));
+        }
+        if let Some(platform) = self.stops.last().and_then(|s| s.platform.as_deref()) {
+            lines.push(format!("Arrives at platform {platform}."));
+        }
+        if let Some(observations) = &self.observations {
+            lines.push(observations.clone());
+        }
+        lines.push(format!("{} train {}.", self.operator, self.train_number));
+        lines.join("\n")
+    }
+}
+
+fn local(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// An RFC 5545 `DURATION` in whole hours, minutes or seconds.
+fn ics_duration(interval: Duration) -> String {
+    let secs = interval.as_secs().max(1);
+    if secs.is_multiple_of(3600) {
+        format!("PT{}H", secs / 3600)
+    } else if secs.is_multiple_of(60) {
+        format!("PT{}M", secs / 60)
+    } else {
+        format!("PT{secs}S")
+    }
+}
+
+/// Escape a `TEXT` value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write `line` ending in CRLF, folded so no physical line exceeds
+/// [`MAX_LINE_OCTETS`] and no UTF-8 character is split.
+fn write_folded(f: &mut fmt::Formatter<'_>, line: &str) -> fmt::Result {
+    let mut rest = line;
+    let mut limit = MAX_LINE_OCTETS;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        f.write_str(&rest[..split])?;
+        f.write_str("\r\n ")?;
+        rest = &rest[split..];
+        // The leading space counts towards the continuation line.
+        limit = MAX_LINE_OCTETS - 1;
+    }
+    f.write_str(rest)?;
+    f.write_str("\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(escape("a, b; c\\d\ne"), "a\\, b\\; c\\\\d\\ne");
+    }
+
+    #[test]
+    fn durations_use_the_largest_whole_unit() {
+        assert_eq!(ics_duration(Duration::from_secs(300)), "PT5M");
+        assert_eq!(ics_duration(Duration::from_secs(7200)), "PT2H");
+        assert_eq!(ics_duration(Duration::from_secs(90)), "PT90S");
+    }
+
+    #[test]
+    fn long_lines_are_folded_on_character_boundaries() {
+        let name = "é".repeat(60);
+        let ics = Calendar::new(Utc::now()).name(name.clone()).to_string();
+
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("X-WR-CALNAME:{name}\r\n")));
+    }
+}
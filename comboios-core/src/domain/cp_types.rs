@@ -91,6 +91,11 @@ pub struct CpTrainTimetable {
     pub has_disruptions: Option<bool>,
     pub duration: Option<String>,
     pub messages: Vec<CpMessage>,
+    /// Last GPS position of the train, reported only while it is running.
+    #[serde(default)]
+    pub latitude: Option<String>,
+    #[serde(default)]
+    pub longitude: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -0,0 +1,165 @@
+//! `GeoJSON` (RFC 7946) export of journeys and station boards, ready for
+//! map libraries such as Leaflet.
+//!
+//! Every feature carries a `kind` property (`route`, `stop`, `train` or
+//! `station`) to style it by. Coordinates come from the [`StationIndex`] and
+//! are written `[longitude, latitude]`; stations without coordinates are
+//! left off the map.
+
+use serde_json::{Value, json};
+
+use super::journey::TrainJourney;
+use super::position::Coordinates;
+use super::station::StationIndex;
+use super::station_timetable::StationBoard;
+
+impl TrainJourney {
+    /// The journey as a `FeatureCollection`: a `LineString` of the route
+    /// through the stops with known coordinates, a `Point` per such stop
+    /// with its times, status, delay and platform, and a `Point` for the
+    /// train when CP reports its GPS position.
+    #[must_use]
+    pub fn to_geojson(&self, stations: &StationIndex) -> Value {
+        let placed: Vec<_> = self
+            .stops
+            .iter()
+            .filter_map(|stop| Some((stop, stations.coordinates(&stop.station.code)?)))
+            .collect();
+
+        let mut features = Vec::with_capacity(placed.len() + 2);
+        if placed.len() >= 2 {
+            let line: Vec<_> = placed.iter().map(|(_, c)| position(*c)).collect();
+            features.push(json!({
+                "type": "Feature",
+                "id": self.train_number,
+                "geometry": { "type": "LineString", "coordinates": line },
+                "properties": {
+                    "kind": "route",
+                    "train_number": self.train_number,
+                    "service_type": self.service_type,
+                    "operator": self.operator,
+                    "origin": self.origin.designation,
+                    "destination": self.destination.designation,
+                    "status": self.status,
+                    "delay_minutes": self.delay_minutes,
+                    "predicted_arrival": self.predicted_arrival,
+                },
+            }));
+        }
+        features.extend(placed.iter().map(|(stop, coordinates)| {
+            json!({
+                "type": "Feature",
+                "id": format!("{}-{}", self.train_number, stop.stop_number),
+                "geometry": point(*coordinates),
+                "properties": {
+                    "kind": "stop",
+                    "stop_number": stop.stop_number,
+                    "station_id": stop.station.code,
+                    "name": stop.station.designation,
+                    "scheduled_arrival": non_empty(&stop.scheduled_arrival),
+                    "scheduled_departure": non_empty(&stop.scheduled_departure),
+                    "actual_arrival": stop.actual_arrival,
+                    "actual_departure": stop.actual_departure,
+                    "predicted_time": stop.predicted_time,
+                    "platform": stop.platform,
+                    "status": stop.status,
+                    "delay_minutes": stop.delay_minutes,
+                    "has_passed": stop.has_passed,
+                },
+            })
+        }));
+        if let Some(coordinates) = self.live_position {
+            features.push(json!({
+                "type": "Feature",
+                "id": format!("{}-train", self.train_number),
+                "geometry": point(coordinates),
+                "properties": {
+                    "kind": "train",
+                    "train_number": self.train_number,
+                    "status": self.status,
+                    "delay_minutes": self.delay_minutes,
+                },
+            }));
+        }
+
+        feature_collection(features)
+    }
+}
+
+impl StationBoard {
+    /// The board as a `FeatureCollection`: a `Point` for the station and a
+    /// feature per row whose properties are the row's fields. A row's
+    /// geometry is a `LineString` from the train's origin through this
+    /// station to its destination, skipping ends without coordinates, or
+    /// `null` when fewer than two points are known.
+    #[must_use]
+    pub fn to_geojson(&self, stations: &StationIndex) -> Value {
+        let here = stations.coordinates(&self.station_id);
+        let mut features = Vec::with_capacity(self.trains.len() + 1);
+        if let Some(coordinates) = here {
+            features.push(json!({
+                "type": "Feature",
+                "id": self.station_id,
+                "geometry": point(coordinates),
+                "properties": {
+                    "kind": "station",
+                    "station_id": self.station_id,
+                    "name": self.station_name,
+                },
+            }));
+        }
+
+        features.extend(self.trains.iter().map(|row| {
+            let mut line: Vec<Coordinates> = Vec::with_capacity(3);
+            let ends = [
+                stations.coordinates(&row.origin_station_id),
+                here,
+                stations.coordinates(&row.destination_station_id),
+            ];
+            for coordinates in ends.into_iter().flatten() {
+                if line.last() != Some(&coordinates) {
+                    line.push(coordinates);
+                }
+            }
+            let geometry = if line.len() >= 2 {
+                let line: Vec<_> = line.into_iter().map(position).collect();
+                json!({ "type": "LineString", "coordinates": line })
+            } else {
+                Value::Null
+            };
+
+            let mut properties = serde_json::to_value(row).unwrap_or_else(|_| json!({}));
+            if let Value::Object(fields) = &mut properties {
+                fields.insert("kind".to_string(), json!("train"));
+            }
+            json!({
+                "type": "Feature",
+                "id": row.train_number.to_string(),
+                "geometry": geometry,
+                "properties": properties,
+            })
+        }));
+
+        feature_collection(features)
+    }
+}
+
+fn feature_collection(features: Vec<Value>) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+fn point(coordinates: Coordinates) -> Value {
+    json!({ "type": "Point", "coordinates": position(coordinates) })
+}
+
+/// A `GeoJSON` position: longitude first.
+fn position(coordinates: Coordinates) -> Value {
+    json!([coordinates.longitude, coordinates.latitude])
+}
+
+fn non_empty(time: &str) -> Option<&str> {
+    Some(time).filter(|t| !t.is_empty())
+}
@@ -4,8 +4,11 @@
 //! and departure times, real-time delay data, and overall journey status.
 //! Journey data is sourced from the CP API (primary) with IP as a fallback.
 
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
+use super::occupancy::Occupancy;
+use super::position::Coordinates;
 use super::station::Station;
 
 /// Complete information for a single train journey, including all stops and
@@ -28,6 +31,9 @@ pub struct TrainJourney {
     pub status: JourneyStatus,
     /// Net delay in minutes at the time the data was fetched, if known.
     pub delay_minutes: Option<i32>,
+    /// Expected crowding level of the train, if reported by CP.
+    #[serde(default)]
+    pub occupancy: Occupancy,
     /// Operating company name.
     pub operator: String,
     /// Free-text status message from CP (e.g. `"Circula com atraso de 6 min."`).
@@ -38,6 +44,24 @@ pub struct TrainJourney {
     /// the API.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<String>,
+    /// Scheduled departure date-time from the origin (Portugal local time),
+    /// when the source reports a full date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_departure: Option<NaiveDateTime>,
+    /// Scheduled arrival date-time at the destination (Portugal local time),
+    /// when the source reports a full date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_arrival: Option<NaiveDateTime>,
+    /// Predicted arrival time at the destination (`HH:MM`) while the train is
+    /// still on its way. Filled by [`crate::prediction::predict`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicted_arrival: Option<String>,
+    /// How reliable [`predicted_arrival`](Self::predicted_arrival) is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prediction_confidence: Option<PredictionConfidence>,
+    /// Last GPS position of the train, when CP reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_position: Option<Coordinates>,
 }
 
 /// Real-time information for one stop within a [`TrainJourney`].
@@ -65,10 +89,15 @@ pub struct JourneyStop {
     /// `None` when the information is not available from the data source.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub has_passed: Option<bool>,
-    /// Predicted arrival/departure time sourced from IP API observations.
-    /// `None` when IP data is not available or not applicable.
+    /// Predicted arrival/departure time (`HH:MM`), either forecast by the
+    /// source or propagated from the current delay by
+    /// [`crate::prediction::predict`]. `None` when no prediction applies.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub predicted_time: Option<String>,
+    /// How reliable [`predicted_time`](Self::predicted_time) is, when it was
+    /// filled by [`crate::prediction::predict`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prediction_confidence: Option<PredictionConfidence>,
 }
 
 /// Overall real-time status of a [`TrainJourney`].
@@ -107,6 +136,33 @@ pub enum StopStatus {
     Unknown,
 }
 
+/// Reliability of a predicted time.
+///
+/// Predictions close to the train's last observed position are more reliable
+/// than those several stops ahead, and those starting from actual times more
+/// than those starting from a bare delay figure.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PredictionConfidence {
+    /// Far ahead of the last observation, or based only on a reported delay.
+    Low,
+    /// Within about an hour and a half of the last observation.
+    Medium,
+    /// Forecast by the source, or within half an hour of an observed time.
+    High,
+}
+
+impl PredictionConfidence {
+    /// The next lower confidence level (`Low` stays `Low`).
+    #[must_use]
+    pub fn lower(self) -> Self {
+        match self {
+            Self::High => Self::Medium,
+            Self::Medium | Self::Low => Self::Low,
+        }
+    }
+}
+
 impl JourneyStop {
     /// Returns `true` if the train has arrived at (or departed from) this stop.
     #[must_use]
@@ -195,10 +251,14 @@ impl TrainJourney {
     }
 
     /// Returns the actual arrival time at the destination if the train has
-    /// already arrived, or `None` while the journey is still in progress.
+    /// already arrived, otherwise the [predicted arrival](Self::predicted_arrival).
+    /// `None` when neither is known.
     #[must_use]
     pub fn estimated_arrival(&self) -> Option<&str> {
-        self.stops.last().and_then(|s| s.actual_arrival.as_deref())
+        self.stops
+            .last()
+            .and_then(|s| s.actual_arrival.as_deref())
+            .or(self.predicted_arrival.as_deref())
     }
 }
 
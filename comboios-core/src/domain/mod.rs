@@ -1,7 +1,13 @@
 pub mod alert;
+pub mod calendar;
 pub mod cp_types;
+pub mod geojson;
 pub mod journey;
+pub mod occupancy;
+pub mod position;
+pub mod segment;
 pub mod station;
 pub mod station_timetable;
 pub mod train;
 pub mod train_journey;
+pub mod trip;
@@ -0,0 +1,61 @@
+//! Train occupancy (crowding) levels.
+//!
+//! CP reports occupancy as a small integer on both station board rows and
+//! train timetables. This module normalises that code into a typed level.
+//!
+//! CP does not document the code, and no recorded response in this
+//! repository shows which values it takes. The mapping below assumes `1` to
+//! `3` rise with crowding; treat the levels as unconfirmed until a captured
+//! payload is added to the tests.
+
+use serde::{Deserialize, Serialize};
+
+/// Expected crowding level of a train.
+///
+/// Obtained from the `occupancy` code reported by the CP API. Codes outside the
+/// known range, or a missing code, map to [`Occupancy::Unknown`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Occupancy {
+    /// Plenty of seats available.
+    Low,
+    /// Some seats available.
+    Medium,
+    /// Few or no seats available; standing likely.
+    High,
+    /// Occupancy was not reported for this train.
+    #[default]
+    Unknown,
+}
+
+impl Occupancy {
+    /// Map a raw CP occupancy code to an [`Occupancy`] level, assuming
+    /// `1` = low, `2` = medium and `3` = high (unconfirmed, see the module
+    /// docs).
+    #[must_use]
+    pub fn from_cp_code(code: Option<u32>) -> Self {
+        match code {
+            Some(1) => Self::Low,
+            Some(2) => Self::Medium,
+            Some(3) => Self::High,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns `true` when CP reported an occupancy level for this train.
+    #[must_use]
+    pub fn is_known(self) -> bool {
+        self != Self::Unknown
+    }
+}
+
+impl std::fmt::Display for Occupancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Occupancy::Low => write!(f, "low"),
+            Occupancy::Medium => write!(f, "medium"),
+            Occupancy::High => write!(f, "high"),
+            Occupancy::Unknown => write!(f, "unknown"),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Geographic position of a running train.
+//!
+//! CP sometimes reports a train's GPS coordinates. When it does not, the
+//! position is estimated by interpolating between the last stop the train
+//! left and the next one, using station coordinates and the scheduled times
+//! shifted by the current delay (or the predicted arrival).
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use super::journey::{JourneyStatus, StopStatus, TrainJourney};
+use super::station::{Station, StationIndex};
+use crate::timeline::{Timeline, has_reached, minutes, wrap};
+
+/// Mean Earth radius in kilometres, used for great-circle distances.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A WGS 84 latitude/longitude pair, in decimal degrees.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    #[must_use]
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Parse the string coordinates CP reports. Returns `None` when either is
+    /// missing, unparseable or out of range, and for CP's `0,0` placeholder.
+    #[must_use]
+    pub fn parse(latitude: Option<&str>, longitude: Option<&str>) -> Option<Self> {
+        let latitude: f64 = latitude?.trim().parse().ok()?;
+        let longitude: f64 = longitude?.trim().parse().ok()?;
+        let valid = (-90.0..=90.0).contains(&latitude)
+            && (-180.0..=180.0).contains(&longitude)
+            && (latitude, longitude) != (0.0, 0.0);
+        valid.then_some(Self::new(latitude, longitude))
+    }
+
+    /// Initial great-circle bearing towards `other`, in degrees clockwise
+    /// from north (`0.0..360.0`).
+    #[must_use]
+    pub fn bearing_to(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let dlon = (other.longitude - self.longitude).to_radians();
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Great-circle distance to `other`, in kilometres.
+    #[must_use]
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.longitude - self.longitude).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+
+    /// The point `fraction` (`0.0..=1.0`) of the way towards `other`.
+    ///
+    /// Linear in latitude and longitude, which is indistinguishable from the
+    /// great circle over the few kilometres between two stations.
+    #[must_use]
+    pub fn interpolate(&self, other: &Coordinates, fraction: f64) -> Coordinates {
+        let f = fraction.clamp(0.0, 1.0);
+        Coordinates::new(
+            self.latitude + (other.latitude - self.latitude) * f,
+            self.longitude + (other.longitude - self.longitude) * f,
+        )
+    }
+}
+
+/// How a [`TrainPosition`] was obtained.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PositionSource {
+    /// GPS coordinates reported by CP.
+    Live,
+    /// Interpolated between two stations from the timetable and delay.
+    Interpolated,
+    /// The train is at (or has not yet left, or has reached the end at) a
+    /// station, so the station's coordinates are used.
+    Station,
+}
+
+/// The leg a train is on: the last stop it left and the next one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionSegment {
+    pub from: Station,
+    pub to: Station,
+    /// Share of the leg's expected running time already elapsed
+    /// (`0.0..=1.0`).
+    pub progress: f64,
+}
+
+/// Where a train is, or is estimated to be.
+///
+/// Obtain a value of this type via [`crate::Comboios::train_position`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainPosition {
+    pub train_number: String,
+    pub coordinates: Coordinates,
+    /// Direction of travel in degrees clockwise from north, when the train is
+    /// moving between two known stations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<f64>,
+    /// The leg the train is on; `None` while it is at a station.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment: Option<PositionSegment>,
+    pub source: PositionSource,
+    /// Portugal-local time the position refers to.
+    pub as_of: NaiveDateTime,
+}
+
+impl TrainPosition {
+    /// Estimate where the train of `journey` is at `now` (Portugal local
+    /// time), using `stations` for station coordinates.
+    ///
+    /// Live coordinates reported by CP take precedence. Otherwise the train
+    /// is placed at the station it is at, or interpolated between the last
+    /// stop it left and the next one. Times are taken relative to
+    /// `service_date`.
+    ///
+    /// Returns `None` for cancelled journeys and when the stations involved
+    /// have no known coordinates.
+    #[must_use]
+    pub fn estimate(
+        journey: &TrainJourney,
+        stations: &StationIndex,
+        service_date: NaiveDate,
+        now: NaiveDateTime,
+    ) -> Option<TrainPosition> {
+        if journey.status == JourneyStatus::Cancelled || journey.stops.is_empty() {
+            return None;
+        }
+
+        let timeline = Timeline::new(&journey.stops);
+        let last = journey.stops.len() - 1;
+        let anchor = journey.stops.iter().rposition(has_reached);
+        let delay = journey.delay_minutes.unwrap_or(0);
+
+        // The leg the train is on, if it is between two stations.
+        let leg = match anchor {
+            _ if journey.status == JourneyStatus::Completed => None,
+            // Standing at an intermediate stop.
+            Some(i) if journey.stops[i].status == StopStatus::AtStop => None,
+            Some(i) if i < last => Some(i),
+            _ => None,
+        };
+
+        let position = |coordinates, heading, segment, source| TrainPosition {
+            train_number: journey.train_number.clone(),
+            coordinates,
+            heading,
+            segment,
+            source,
+            as_of: now,
+        };
+
+        let Some(i) = leg else {
+            let at = match anchor {
+                _ if journey.status == JourneyStatus::Completed => last,
+                Some(i) => i,
+                None => 0,
+            };
+            let station = &journey.stops[at].station;
+            let live = journey.live_position;
+            let coordinates = live.or_else(|| stations.coordinates(&station.code))?;
+            let source = if live.is_some() {
+                PositionSource::Live
+            } else {
+                PositionSource::Station
+            };
+            return Some(position(coordinates, None, None, source));
+        };
+
+        let (from, to) = (&journey.stops[i], &journey.stops[i + 1]);
+        let from_at = stations.coordinates(&from.station.code);
+        let to_at = stations.coordinates(&to.station.code);
+
+        let departed = timeline
+            .actual_departure(from, i)
+            .unwrap_or(timeline.departure(i) + delay);
+        let arriving = to
+            .predicted_time
+            .as_deref()
+            .and_then(minutes)
+            .map(|t| timeline.arrival(i + 1) + wrap(t - timeline.arrival(i + 1)))
+            .unwrap_or(timeline.arrival(i + 1) + delay);
+
+        let elapsed = i32::try_from((now - service_date.and_time(NaiveTime::MIN)).num_minutes())
+            .unwrap_or(i32::MAX);
+        let progress = if arriving > departed {
+            (f64::from(elapsed - departed) / f64::from(arriving - departed)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        let heading = from_at.zip(to_at).map(|(a, b)| a.bearing_to(&b));
+        let segment = Some(PositionSegment {
+            from: from.station.clone(),
+            to: to.station.clone(),
+            progress,
+        });
+
+        if let Some(live) = journey.live_position {
+            let heading = to_at.map(|b| live.bearing_to(&b)).or(heading);
+            return Some(position(live, heading, segment, PositionSource::Live));
+        }
+
+        let (a, b) = from_at.zip(to_at)?;
+        Some(position(
+            a.interpolate(&b, progress),
+            heading,
+            segment,
+            PositionSource::Interpolated,
+        ))
+    }
+}
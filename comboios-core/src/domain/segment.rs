@@ -0,0 +1,114 @@
+//! Per-segment analytics for a [`TrainJourney`].
+//!
+//! A segment is the run between two consecutive stops plus the stop at its
+//! end. Comparing scheduled and actual times on each segment shows where a
+//! train lost time and where it made it up.
+
+use serde::{Deserialize, Serialize};
+
+use super::journey::TrainJourney;
+use super::station::Station;
+use crate::timeline::Timeline;
+
+/// The leg between two consecutive stops of a [`TrainJourney`].
+///
+/// All durations are in minutes. Actual values are `None` until the times
+/// they depend on have been reported, i.e. for legs the train has not yet
+/// completed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JourneySegment {
+    /// Stop the segment starts from.
+    pub from: Station,
+    /// Stop the segment ends at.
+    pub to: Station,
+    /// 1-based stop number of [`to`](Self::to); `from` is the stop before.
+    pub stop_number: usize,
+    /// Timetabled running time from departing `from` to arriving at `to`.
+    pub scheduled_running_minutes: i32,
+    /// Observed running time from departing `from` to arriving at `to`.
+    pub actual_running_minutes: Option<i32>,
+    /// Timetabled dwell at `to` (zero at the destination and pass-throughs).
+    pub scheduled_dwell_minutes: i32,
+    /// Observed dwell at `to`.
+    pub actual_dwell_minutes: Option<i32>,
+    /// Delay gained while running (positive) or recovered (negative).
+    pub delay_gained_minutes: Option<i32>,
+}
+
+impl JourneySegment {
+    /// Minutes the dwell at `to` ran over (positive) or under (negative) the
+    /// timetable.
+    #[must_use]
+    pub fn dwell_overrun_minutes(&self) -> Option<i32> {
+        self.actual_dwell_minutes
+            .map(|actual| actual - self.scheduled_dwell_minutes)
+    }
+}
+
+impl TrainJourney {
+    /// Splits the journey into the legs between consecutive stops.
+    ///
+    /// Times past midnight are treated as the following day. A journey with
+    /// fewer than two stops has no segments.
+    #[must_use]
+    pub fn segments(&self) -> Vec<JourneySegment> {
+        let timeline = Timeline::new(&self.stops);
+
+        self.stops
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let (from, to) = (&pair[0], &pair[1]);
+                let j = i + 1;
+
+                let departed = timeline.actual_departure(from, i);
+                let arrived = timeline.actual_arrival(to, j);
+                let left = timeline.actual_departure(to, j);
+
+                let scheduled_running = timeline.arrival(j) - timeline.departure(i);
+                let actual_running = departed.zip(arrived).map(|(d, a)| a - d);
+
+                JourneySegment {
+                    from: from.station.clone(),
+                    to: to.station.clone(),
+                    stop_number: to.stop_number,
+                    scheduled_running_minutes: scheduled_running,
+                    actual_running_minutes: actual_running,
+                    scheduled_dwell_minutes: timeline.departure(j) - timeline.arrival(j),
+                    actual_dwell_minutes: arrived.zip(left).map(|(a, l)| l - a),
+                    delay_gained_minutes: actual_running.map(|r| r - scheduled_running),
+                }
+            })
+            .collect()
+    }
+
+    /// The segment where the train lost the most time while running, if it
+    /// lost any.
+    #[must_use]
+    pub fn worst_segment(&self) -> Option<JourneySegment> {
+        self.segments()
+            .into_iter()
+            .filter(|s| s.delay_gained_minutes.is_some_and(|d| d > 0))
+            .max_by_key(|s| s.delay_gained_minutes)
+    }
+
+    /// The segment where the train recovered the most time while running, if
+    /// it recovered any.
+    #[must_use]
+    pub fn best_segment(&self) -> Option<JourneySegment> {
+        self.segments()
+            .into_iter()
+            .filter(|s| s.delay_gained_minutes.is_some_and(|d| d < 0))
+            .min_by_key(|s| s.delay_gained_minutes)
+    }
+
+    /// Net delay gained (positive) or recovered (negative) over the segments
+    /// completed so far, running and dwell combined.
+    #[must_use]
+    pub fn delay_gained_minutes(&self) -> i32 {
+        self.segments()
+            .iter()
+            .map(|s| s.delay_gained_minutes.unwrap_or(0) + s.dwell_overrun_minutes().unwrap_or(0))
+            .sum()
+    }
+}
@@ -1,5 +1,12 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Deserializer, Serialize};
 
+use super::journey::TrainJourney;
+use super::position::Coordinates;
+use super::station_timetable::StationBoard;
+use crate::adapters::normalize_station_id;
+
 fn int_to_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -16,7 +23,7 @@ where
 }
 
 /// A CP station with its unique code and human-readable name.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Station {
     /// CP station identifier (e.g. `"94-31039"` for Lisboa-Oriente).
     ///
@@ -38,3 +45,170 @@ pub struct StationResponse {
     /// Matching stations; may be empty when no station name contains the query.
     pub response: Vec<Station>,
 }
+
+/// In-memory lookup of CP stations by id and by name.
+///
+/// [`crate::Comboios`] keeps one of these loaded from the CP station list and
+/// uses it to fill station names on boards and missing codes or names on
+/// journeys. Ids are matched in either CP (`"94-31039"`) or IP (`"9431039"`)
+/// format; names are matched ignoring case, accents and punctuation, so
+/// `"Porto Campanha"` finds `"Porto - Campanhã"`.
+#[derive(Debug, Clone, Default)]
+pub struct StationIndex {
+    by_id: HashMap<String, Station>,
+    by_name: HashMap<String, Station>,
+    coordinates: HashMap<String, Coordinates>,
+}
+
+impl StationIndex {
+    /// Build an index from a list of stations. Later duplicates win.
+    pub fn new(stations: impl IntoIterator<Item = Station>) -> Self {
+        let mut index = Self::default();
+        for station in stations {
+            index
+                .by_name
+                .insert(normalize_name(&station.designation), station.clone());
+            index
+                .by_id
+                .insert(normalize_station_id(&station.code), station);
+        }
+        index
+    }
+
+    /// Number of stations in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    /// Returns `true` when no stations have been loaded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// Look up a station by id, in CP or IP format.
+    #[must_use]
+    pub fn get(&self, code: &str) -> Option<&Station> {
+        self.by_id.get(&normalize_station_id(code))
+    }
+
+    /// Look up a station by its exact name, ignoring case, accents and
+    /// punctuation.
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<&Station> {
+        self.by_name.get(&normalize_name(name))
+    }
+
+    /// Resolve a station given by id (CP or IP format) or by name to its CP
+    /// id. Unknown entries that look like an id are returned unchanged, so
+    /// stations missing from the index can still be queried.
+    #[must_use]
+    pub fn resolve(&self, id_or_name: &str) -> Option<String> {
+        self.get(id_or_name)
+            .or_else(|| self.find_by_name(id_or_name))
+            .map(|s| s.code.clone())
+            .or_else(|| {
+                let id_shaped = !id_or_name.is_empty()
+                    && id_or_name.chars().all(|c| c.is_ascii_digit() || c == '-');
+                id_shaped.then(|| id_or_name.to_string())
+            })
+    }
+
+    /// Record where the station with id `code` is.
+    pub fn set_coordinates(&mut self, code: &str, coordinates: Coordinates) {
+        self.coordinates
+            .insert(normalize_station_id(code), coordinates);
+    }
+
+    /// Location of the station with id `code`, in CP or IP format, if known.
+    #[must_use]
+    pub fn coordinates(&self, code: &str) -> Option<Coordinates> {
+        self.coordinates.get(&normalize_station_id(code)).copied()
+    }
+
+    /// Human-readable name for the station with id `code`, if known.
+    #[must_use]
+    pub fn name_of(&self, code: &str) -> Option<&str> {
+        self.get(code).map(|s| s.designation.as_str())
+    }
+
+    /// Iterate over every station in the index, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Station> {
+        self.by_id.values()
+    }
+
+    /// Fill whichever of `station.code` and `station.designation` is empty
+    /// from the index, and rewrite IP-format codes to the CP format. Stations
+    /// that cannot be resolved are left unchanged.
+    pub fn complete(&self, station: &mut Station) {
+        if station.code.is_empty() {
+            if let Some(found) = self.find_by_name(&station.designation) {
+                station.code.clone_from(&found.code);
+            }
+        } else if let Some(found) = self.get(&station.code) {
+            station.code.clone_from(&found.code);
+            if station.designation.is_empty() {
+                station.designation.clone_from(&found.designation);
+            }
+        }
+    }
+
+    /// Set the board's station name when CP left it empty.
+    pub fn fill_board(&self, board: &mut StationBoard) {
+        if board.station_name.is_empty()
+            && let Some(name) = self.name_of(&board.station_id)
+        {
+            board.station_name = name.to_string();
+        }
+    }
+
+    /// Resolve the journey's origin and destination codes and names.
+    ///
+    /// Missing endpoints are first taken from the first and last stop, then
+    /// completed from the index.
+    pub fn fill_journey(&self, journey: &mut TrainJourney) {
+        if let Some(first) = journey.stops.first() {
+            fill_from_stop(&mut journey.origin, &first.station);
+        }
+        if let Some(last) = journey.stops.last() {
+            fill_from_stop(&mut journey.destination, &last.station);
+        }
+        self.complete(&mut journey.origin);
+        self.complete(&mut journey.destination);
+    }
+}
+
+/// Copy the stop's code and name into `endpoint` where the endpoint is
+/// missing them and the stop refers to the same station.
+fn fill_from_stop(endpoint: &mut Station, stop: &Station) {
+    let same_station = endpoint.designation.is_empty()
+        || normalize_name(&endpoint.designation) == normalize_name(&stop.designation);
+    if !same_station {
+        return;
+    }
+    if endpoint.code.is_empty() {
+        endpoint.code.clone_from(&stop.code);
+    }
+    if endpoint.designation.is_empty() {
+        endpoint.designation.clone_from(&stop.designation);
+    }
+}
+
+/// Reduce a station name to lowercase ASCII letters and digits so that CP and
+/// IP spellings of the same station compare equal.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            other => other,
+        })
+        .filter(char::is_ascii_alphanumeric)
+        .collect()
+}
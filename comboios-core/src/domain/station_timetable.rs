@@ -2,6 +2,8 @@ use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
 
+use super::occupancy::Occupancy;
+
 /// Response wrapper returned by [`crate::Comboios::get_station_timetable`].
 ///
 /// Contains one [`StationBoard`] entry per station returned by the CP API
@@ -25,7 +27,7 @@ pub struct StationBoard {
 }
 
 /// A single train movement (one row on the departure/arrival board).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StationTimetable {
     /// CP train number.
     pub train_number: u64,
@@ -58,6 +60,9 @@ pub struct StationTimetable {
     pub estimated_arrival: Option<String>,
     /// Free-text observations from CP (e.g. cancellation notices).
     pub observations: Option<String>,
+    /// Expected crowding level of the train at this station.
+    #[serde(default)]
+    pub occupancy: Occupancy,
     /// Operating company name.
     pub operator: String,
     /// `true` if the train has already passed through this station.
@@ -66,6 +71,68 @@ pub struct StationTimetable {
     pub is_departure: bool,
 }
 
+/// Which side of a station board a row belongs to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BoardDirection {
+    /// Trains leaving the station.
+    Departures,
+    /// Trains arriving at the station.
+    Arrivals,
+}
+
+impl StationTimetable {
+    /// Scheduled clock time (`HH:MM`) of this movement on the given side of the
+    /// board: the departure time for [`BoardDirection::Departures`] and the
+    /// arrival time for [`BoardDirection::Arrivals`].
+    #[must_use]
+    pub fn scheduled_time(&self, direction: BoardDirection) -> Option<&str> {
+        match direction {
+            BoardDirection::Departures => self.departure_time.as_deref(),
+            BoardDirection::Arrivals => self.arrival_time.as_deref(),
+        }
+    }
+
+    /// Scheduled clock time (`HH:MM`) on the side of the board this row was
+    /// listed under, falling back to the other side when missing.
+    #[must_use]
+    pub fn board_time(&self) -> Option<&str> {
+        if self.is_departure {
+            self.departure_time
+                .as_deref()
+                .or(self.arrival_time.as_deref())
+        } else {
+            self.arrival_time
+                .as_deref()
+                .or(self.departure_time.as_deref())
+        }
+    }
+
+    /// Service code part of [`service_type`](Self::service_type), e.g. `"IC"`
+    /// for `"IC|Intercidades"`.
+    #[must_use]
+    pub fn service_code(&self) -> &str {
+        self.service_type
+            .split_once('|')
+            .map_or(self.service_type.as_str(), |(code, _)| code)
+    }
+
+    /// Returns `true` when the train is running behind schedule.
+    #[must_use]
+    pub fn is_delayed(&self) -> bool {
+        self.delay.is_some_and(|d| d > 0)
+    }
+
+    /// Returns `true` when CP has flagged this movement as suppressed
+    /// (e.g. `"Supressão"` / `"Suprimido"` in the observations).
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.observations
+            .as_deref()
+            .is_some_and(|o| o.to_lowercase().contains("supr"))
+    }
+}
+
 /// Raw train entry as returned by the CP API, before normalisation into
 /// [`StationTimetable`].
 ///
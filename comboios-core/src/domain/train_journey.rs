@@ -1,10 +1,18 @@
 use std::sync::OnceLock;
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+
+use crate::adapters::to_cp_id;
+use crate::clock::{Clock, SystemClock};
 use crate::domain::{
     cp_types::CpTrainTimetable,
     journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney},
+    occupancy::Occupancy,
+    position::Coordinates,
     station::Station,
 };
+use crate::timeline::ServiceDay;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +54,8 @@ pub struct TrainPassage {
     pub station_name: String,
     #[serde(alias = "Observacoes")]
     pub observations: String,
+    #[serde(alias = "Plataforma", default)]
+    pub platform: Option<String>,
 }
 
 static PREDICTED_TIME_RE: OnceLock<regex::Regex> = OnceLock::new();
@@ -100,9 +110,40 @@ fn parse_delay_from_status(status: &str) -> Option<i32> {
 }
 
 impl IpTrainJourneyResponse {
+    /// Convert to a [`TrainJourney`], evaluating stop status against the
+    /// current time in `Europe/Lisbon`.
+    ///
+    /// See [`to_train_journey_at`](Self::to_train_journey_at) to supply the
+    /// service date and current time explicitly.
     pub fn to_train_journey(&self, train_number: &str) -> TrainJourney {
-        let now = chrono::Local::now();
-        let current_time = now.format("%H:%M").to_string();
+        self.to_train_journey_at(train_number, None, SystemClock.now())
+    }
+
+    /// Convert to a [`TrainJourney`] as seen at `now`.
+    ///
+    /// Stop times are placed on `service_date` (falling back to the date of
+    /// `DataHoraOrigem`, then to `now`'s date) and roll over to the next day
+    /// after midnight, so journeys on past or future dates are evaluated
+    /// relative to their own day rather than today's wall clock.
+    pub fn to_train_journey_at(
+        &self,
+        train_number: &str,
+        service_date: Option<NaiveDate>,
+        now: DateTime<Tz>,
+    ) -> TrainJourney {
+        let origin_departure = parse_ip_datetime(&self.origin_time);
+        let service_date = service_date
+            .or(origin_departure.map(|d| d.date()))
+            .unwrap_or_else(|| now.date_naive());
+        let stop_times = dated_times(
+            service_date,
+            self.stops.iter().map(|p| Some(p.scheduled_time.as_str())),
+        );
+        let now = now.naive_local();
+
+        let last_passed_idx = self.stops.iter().rposition(|p| p.has_passed);
+        let next_idx = last_passed_idx.map_or(0, |i| i + 1);
+        let cancelled = is_suppressed(&self.status);
 
         let stops: Vec<JourneyStop> = self
             .stops
@@ -114,58 +155,152 @@ impl IpTrainJourneyResponse {
                     .as_ref()
                     .and_then(|pred| calculate_delay_from_predicted(&p.scheduled_time, pred));
 
-                let stop_time = &p.scheduled_time;
-                let is_past = stop_time < &current_time && stop_time != "00:00";
+                let is_past = stop_times[i].is_some_and(|t| t < now);
 
+                // Only the stop right after the last passed one can be where
+                // the train currently is; later stops are merely late.
                 let status = if p.has_passed {
                     StopStatus::Passed
-                } else if is_past && i != 0 {
+                } else if cancelled {
+                    StopStatus::Cancelled
+                } else if is_past && i != 0 && i == next_idx {
                     StopStatus::AtStop
                 } else {
                     StopStatus::Scheduled
                 };
 
+                // Once a stop is passed, IP's "Hora Prevista" is the observed
+                // time rather than a forecast.
+                let observed = p.has_passed.then(|| predicted_time.clone()).flatten();
+
                 JourneyStop {
                     station: Station {
-                        code: p.node_id.to_string(),
+                        code: to_cp_id(&p.node_id.to_string()),
                         designation: p.station_name.clone(),
                     },
                     scheduled_arrival: p.scheduled_time.clone(),
                     scheduled_departure: p.scheduled_time.clone(),
-                    actual_arrival: None,
-                    actual_departure: None,
-                    platform: None,
+                    actual_arrival: observed.clone(),
+                    actual_departure: observed,
+                    platform: p.platform.clone().filter(|s| !s.trim().is_empty()),
                     status,
                     delay_minutes: delay,
                     stop_number: i + 1,
-                    has_passed: Some(p.has_passed || is_past),
+                    has_passed: Some(p.has_passed),
                     predicted_time,
+                    prediction_confidence: None,
                 }
             })
             .collect();
 
+        let status = if cancelled {
+            JourneyStatus::Cancelled
+        } else if !self.stops.is_empty() && last_passed_idx == Some(self.stops.len() - 1) {
+            JourneyStatus::Completed
+        } else if last_passed_idx.is_some() {
+            JourneyStatus::InProgress
+        } else {
+            JourneyStatus::Scheduled
+        };
+
         TrainJourney {
             train_number: train_number.to_string(),
             service_type: self.service_type.clone(),
             origin: Station {
-                code: String::new(),
+                code: stops
+                    .first()
+                    .map(|s| s.station.code.clone())
+                    .unwrap_or_default(),
                 designation: self.origin.clone(),
             },
             destination: Station {
-                code: String::new(),
+                code: stops
+                    .last()
+                    .map(|s| s.station.code.clone())
+                    .unwrap_or_default(),
                 designation: self.destination.clone(),
             },
             stops,
-            status: JourneyStatus::Scheduled,
+            status,
             delay_minutes: parse_delay_from_status(&self.status),
+            occupancy: Occupancy::Unknown,
             operator: self.operator.clone(),
             observations: Some(self.status.clone()),
-            duration: None,
+            duration: normalize_duration(&self.duration),
+            origin_departure,
+            destination_arrival: parse_ip_datetime(&self.destination_time),
+            predicted_arrival: None,
+            prediction_confidence: None,
+            live_position: None,
         }
     }
 }
 
+/// Place each `HH:MM` time on `service_date`, rolling past midnight as
+/// [`ServiceDay`] does. Missing or unparseable times yield `None` and do not
+/// move the day.
+fn dated_times<'a>(
+    service_date: NaiveDate,
+    times: impl IntoIterator<Item = Option<&'a str>>,
+) -> Vec<Option<NaiveDateTime>> {
+    let mut day = ServiceDay::new(service_date);
+    times
+        .into_iter()
+        .map(|t| {
+            let time = NaiveTime::parse_from_str(t?, "%H:%M").ok()?;
+            Some(day.place(time))
+        })
+        .collect()
+}
+
+/// Returns `true` when IP's `SituacaoComboio` reports the train as suppressed.
+fn is_suppressed(status: &str) -> bool {
+    status.to_lowercase().contains("suprimido")
+}
+
+/// Parse IP's `DataHoraOrigem`/`DataHoraDestino`, which come as either
+/// `YYYY-MM-DD HH:MM` or `DD-MM-YYYY HH:MM`.
+fn parse_ip_datetime(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    ["%Y-%m-%d %H:%M", "%d-%m-%Y %H:%M", "%Y-%m-%d %H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+}
+
+/// Normalise IP's `HH:MM` journey duration to the `HH:MM:SS` form used by CP.
+fn normalize_duration(duration: &str) -> Option<String> {
+    let duration = duration.trim();
+    match duration.split(':').count() {
+        2 => Some(format!("{duration}:00")),
+        3 => Some(duration.to_string()),
+        _ => None,
+    }
+}
+
 impl CpTrainTimetable {
+    /// Convert to a [`TrainJourney`] running on `service_date`, which also
+    /// fills the journey's scheduled origin and destination date-times.
+    pub fn to_train_journey_on(&self, service_date: NaiveDate) -> TrainJourney {
+        let mut journey = self.to_train_journey();
+
+        let departures = dated_times(
+            service_date,
+            self.train_stops
+                .iter()
+                .map(|s| s.departure.as_deref().or(s.arrival.as_deref())),
+        );
+        journey.origin_departure = departures.first().copied().flatten();
+        journey.destination_arrival = self.train_stops.last().and_then(|last| {
+            // Re-date the final arrival against the day its departure rolled to.
+            let day = departures.last().copied().flatten()?.date();
+            let time = last.arrival.as_deref().or(last.departure.as_deref())?;
+            let time = NaiveTime::parse_from_str(time, "%H:%M").ok()?;
+            Some(day.and_time(time))
+        });
+
+        journey
+    }
+
     pub fn to_train_journey(&self) -> TrainJourney {
         let last_passed_idx = self
             .last_station_code
@@ -240,6 +375,7 @@ impl CpTrainTimetable {
                     } else {
                         None
                     },
+                    prediction_confidence: None,
                 }
             })
             .collect();
@@ -288,9 +424,15 @@ impl CpTrainTimetable {
             stops,
             status: journey_status,
             delay_minutes: self.delay,
+            occupancy: Occupancy::from_cp_code(self.occupancy),
             operator: "CP".to_string(),
             observations: None,
             duration: self.duration.clone(),
+            origin_departure: None,
+            destination_arrival: None,
+            predicted_arrival: None,
+            prediction_confidence: None,
+            live_position: Coordinates::parse(self.latitude.as_deref(), self.longitude.as_deref()),
         }
     }
 }
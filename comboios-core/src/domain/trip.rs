@@ -0,0 +1,221 @@
+//! Direct trains between two stations.
+//!
+//! A direct train calls at both stations, the boarding station first.
+//! Candidates are found by pairing the departure board of the boarding
+//! station with the arrival board of the alighting station on train number;
+//! [`crate::Comboios::find_trains`] then confirms the stop order against each
+//! train's journey.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use super::journey::TrainJourney;
+use super::occupancy::Occupancy;
+use super::station::Station;
+use super::station_timetable::{StationBoard, StationTimetable};
+use crate::adapters::normalize_station_id;
+use crate::timeline::{datetime_near, minutes};
+
+/// Longest direct run looked for, in hours. The arrival board is searched
+/// this far past the end of the departure window.
+pub const MAX_DIRECT_TRIP_HOURS: i64 = 8;
+
+/// Most journeys [`Comboios::find_trains`](crate::Comboios::find_trains)
+/// fetches to confirm stop order; later candidates are kept on the strength
+/// of the boards.
+pub const MAX_ORDER_CHECKS: usize = 12;
+
+/// A train that runs from one station to another without a change.
+///
+/// Obtain values of this type via [`crate::Comboios::find_trains`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectTrain {
+    /// CP train number.
+    pub train_number: String,
+    /// Service category, as on the station board (e.g. `"IC|Intercidades"`).
+    pub service_type: String,
+    /// Operating company name.
+    pub operator: String,
+    /// Where the train starts its journey.
+    pub origin: Station,
+    /// Where the train ends its journey.
+    pub destination: Station,
+    /// Station the passenger boards at.
+    pub from: Station,
+    /// Station the passenger alights at.
+    pub to: Station,
+    /// Scheduled departure from `from` (Portugal local time).
+    pub departure: NaiveDateTime,
+    /// Scheduled arrival at `to` (Portugal local time).
+    pub arrival: NaiveDateTime,
+    /// Estimated departure from `from` (`HH:MM`), when reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_departure: Option<String>,
+    /// Estimated arrival at `to` (`HH:MM`), when reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_arrival: Option<String>,
+    /// Platform at `from`, if assigned.
+    pub platform: Option<String>,
+    /// Platform at `to`, if assigned.
+    pub arrival_platform: Option<String>,
+    /// Current delay in minutes, if known.
+    pub delay_minutes: Option<i32>,
+    /// Expected crowding at `from`.
+    #[serde(default)]
+    pub occupancy: Occupancy,
+    /// Free-text observations from CP (e.g. cancellation notices).
+    pub observations: Option<String>,
+}
+
+impl DirectTrain {
+    /// Pair the rows of a departure board at `from` with the rows of an
+    /// arrival board at `to` that belong to the same train.
+    ///
+    /// `departures` must hold departures of the service day `date` only; each
+    /// arrival is placed on the first occurrence of its clock time after the
+    /// departure. Trains whose arrival would be more than
+    /// [`MAX_DIRECT_TRIP_HOURS`] after departing are dropped, as are rows
+    /// without a departure (at `from`) or arrival (at `to`) time. The result
+    /// is ordered by departure.
+    #[must_use]
+    pub fn match_boards(
+        departures: &StationBoard,
+        arrivals: &StationBoard,
+        date: NaiveDate,
+    ) -> Vec<DirectTrain> {
+        let mut arriving: HashMap<u64, &StationTimetable> = HashMap::new();
+        for row in &arrivals.trains {
+            if row.arrival_time.is_some() {
+                arriving.entry(row.train_number).or_insert(row);
+            }
+        }
+
+        let from = Station {
+            code: departures.station_id.clone(),
+            designation: departures.station_name.clone(),
+        };
+        let to = Station {
+            code: arrivals.station_id.clone(),
+            designation: arrivals.station_name.clone(),
+        };
+
+        let mut trains: Vec<DirectTrain> = departures
+            .trains
+            .iter()
+            .filter_map(|dep| {
+                let arr = arriving.get(&dep.train_number)?;
+                let leaves = minutes(dep.departure_time.as_deref()?)?;
+                let arrives = minutes(arr.arrival_time.as_deref()?)?;
+
+                let departure = date.and_time(NaiveTime::MIN) + Duration::minutes(leaves.into());
+                let running = Duration::minutes((arrives - leaves).rem_euclid(24 * 60).into());
+                if running > Duration::hours(MAX_DIRECT_TRIP_HOURS) {
+                    return None;
+                }
+
+                Some(DirectTrain {
+                    train_number: dep.train_number.to_string(),
+                    service_type: dep.service_type.clone(),
+                    operator: dep.operator.clone(),
+                    origin: Station {
+                        code: dep.origin_station_id.clone(),
+                        designation: dep.origin_station_name.clone(),
+                    },
+                    destination: Station {
+                        code: dep.destination_station_id.clone(),
+                        designation: dep.destination_station_name.clone(),
+                    },
+                    from: from.clone(),
+                    to: to.clone(),
+                    departure,
+                    arrival: departure + running,
+                    estimated_departure: dep.estimated_departure.clone(),
+                    estimated_arrival: arr.estimated_arrival.clone(),
+                    platform: dep.platform.clone(),
+                    arrival_platform: arr.platform.clone(),
+                    delay_minutes: dep.delay.or(arr.delay),
+                    occupancy: dep.occupancy,
+                    observations: dep.observations.clone().or(arr.observations.clone()),
+                })
+            })
+            .collect();
+
+        trains.sort_by(|a, b| (a.departure, &a.train_number).cmp(&(b.departure, &b.train_number)));
+        trains
+    }
+
+    /// Scheduled time from `from` to `to`, in minutes.
+    #[must_use]
+    pub fn duration_minutes(&self) -> i64 {
+        (self.arrival - self.departure).num_minutes()
+    }
+
+    /// Expected departure: the estimate when reported, otherwise the
+    /// scheduled time shifted by the current delay.
+    #[must_use]
+    pub fn expected_departure(&self) -> NaiveDateTime {
+        expected(
+            self.departure,
+            self.estimated_departure.as_deref(),
+            self.delay_minutes,
+        )
+    }
+
+    /// Expected arrival: the estimate when reported, otherwise the scheduled
+    /// time shifted by the current delay.
+    #[must_use]
+    pub fn expected_arrival(&self) -> NaiveDateTime {
+        expected(
+            self.arrival,
+            self.estimated_arrival.as_deref(),
+            self.delay_minutes,
+        )
+    }
+
+    /// Returns `true` when CP has flagged the train as suppressed.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.observations
+            .as_deref()
+            .is_some_and(|o| o.to_lowercase().contains("supr"))
+    }
+
+    /// Whether the stop order follows from the boards alone: a train
+    /// boarded at its origin, or left at its destination, calls at `from`
+    /// before `to`.
+    #[must_use]
+    pub fn order_is_known(&self) -> bool {
+        let code_of = |station: &Station| normalize_station_id(&station.code);
+        code_of(&self.origin) == code_of(&self.from)
+            || code_of(&self.destination) == code_of(&self.to)
+    }
+
+    /// Whether `journey` calls at `from` before `to`.
+    ///
+    /// Returns `None` when either station cannot be found among the
+    /// journey's stops, so the order cannot be confirmed either way.
+    #[must_use]
+    pub fn serves_in_order(&self, journey: &TrainJourney) -> Option<bool> {
+        let code_of = |station: &Station| normalize_station_id(&station.code);
+        let (from, to) = (code_of(&self.from), code_of(&self.to));
+        let boards = journey
+            .stops
+            .iter()
+            .position(|s| code_of(&s.station) == from)?;
+        let alights = journey
+            .stops
+            .iter()
+            .rposition(|s| code_of(&s.station) == to)?;
+        Some(boards < alights)
+    }
+}
+
+/// `scheduled` moved to `estimate` (placed within twelve hours of it), or
+/// shifted by `delay` when there is no estimate.
+fn expected(scheduled: NaiveDateTime, estimate: Option<&str>, delay: Option<i32>) -> NaiveDateTime {
+    estimate
+        .and_then(|t| datetime_near(scheduled, t))
+        .unwrap_or_else(|| scheduled + Duration::minutes(delay.unwrap_or(0).into()))
+}
@@ -28,4 +28,10 @@ pub enum CoreError {
     /// The inner string describes which parameter is invalid and why.
     #[error("invalid input: {0}")]
     InvalidInput(String),
+
+    /// A GTFS feed failed validation or could not be read or written.
+    ///
+    /// The inner string lists the problems found or the I/O failure.
+    #[error("GTFS error: {0}")]
+    GtfsError(String),
 }
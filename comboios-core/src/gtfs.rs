@@ -0,0 +1,641 @@
+//! GTFS static export.
+//!
+//! [`GtfsFeed::from_journeys`] turns the [`TrainJourney`]s of a set of
+//! trains over a range of dates into the tables of a
+//! [GTFS](https://gtfs.org/schedule/reference/) feed: `agency.txt`,
+//! `stops.txt` (with coordinates from the [`StationIndex`]), `routes.txt`
+//! (one route per service type), `trips.txt`, `stop_times.txt` and
+//! `calendar_dates.txt` (one service per date). [`GtfsFeed::write_zip`]
+//! checks the feed with [`GtfsFeed::validate`] before writing it, so
+//! OpenTripPlanner and other tools get a consistent feed or none at all.
+//!
+//! Ids are stable across exports: stops use the IP-format station id
+//! ([`stop_id`]), trips the train number and date ([`trip_id`]), which is
+//! what GTFS-Realtime feeds refer to.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chrono::NaiveDate;
+//! use comboios_core::Comboios;
+//!
+//! # async fn run(client: Comboios) -> Result<(), comboios_core::Error> {
+//! let from = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+//! let to = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+//! let feed = client.gtfs_feed(&["520", "522"], from, to).await?;
+//! feed.write_zip(std::fs::File::create("cp-gtfs.zip").unwrap())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{Seek, Write};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::adapters::normalize_station_id;
+use crate::domain::journey::{JourneyStop, StopStatus, TrainJourney};
+use crate::domain::station::StationIndex;
+use crate::error::CoreError;
+use crate::timeline::{Timeline, datetime_near, has_reached, minutes};
+
+/// GTFS `route_type` for rail.
+pub const ROUTE_TYPE_RAIL: u16 = 2;
+
+/// Timezone of every agency in the feed.
+pub const AGENCY_TIMEZONE: &str = "Europe/Lisbon";
+
+/// Longest date range [`Comboios::gtfs_feed`](crate::Comboios::gtfs_feed)
+/// exports in one go.
+pub const MAX_EXPORT_DAYS: i64 = 62;
+
+/// Problems listed in a validation error before the rest are counted.
+const PROBLEMS_SHOWN: usize = 10;
+
+/// GTFS `stop_id` of a CP station: its IP-format id, e.g. `"9431039"`.
+#[must_use]
+pub fn stop_id(station_code: &str) -> String {
+    normalize_station_id(station_code)
+}
+
+/// GTFS `trip_id` of a train on a service date, e.g. `"520_20260314"`.
+#[must_use]
+pub fn trip_id(train_number: &str, date: NaiveDate) -> String {
+    format!("{}_{}", train_number.trim(), service_id(date))
+}
+
+/// GTFS `route_id` of a service type run by `operator`, e.g. `"CP-IC"` for
+/// `"IC|Intercidades"`.
+#[must_use]
+pub fn route_id(operator: &str, service_type: &str) -> String {
+    format!("{}-{}", operator.trim(), service_code(service_type).0)
+}
+
+/// GTFS `service_id` of a service date, e.g. `"20260314"`.
+#[must_use]
+pub fn service_id(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// A row of `agency.txt`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agency {
+    pub agency_id: String,
+    pub agency_name: String,
+    pub agency_url: String,
+    pub agency_timezone: String,
+}
+
+/// A row of `stops.txt`. Coordinates are required by GTFS; a stop without
+/// them fails validation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stop {
+    pub stop_id: String,
+    pub stop_name: String,
+    pub stop_lat: Option<f64>,
+    pub stop_lon: Option<f64>,
+}
+
+/// A row of `routes.txt`: one per service type, e.g. `IC` / `Intercidades`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub route_id: String,
+    pub agency_id: String,
+    pub route_short_name: String,
+    pub route_long_name: String,
+    pub route_type: u16,
+}
+
+/// A row of `trips.txt`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trip {
+    pub route_id: String,
+    pub service_id: String,
+    pub trip_id: String,
+    /// The train number.
+    pub trip_short_name: String,
+    /// The destination station's name.
+    pub trip_headsign: String,
+}
+
+/// A row of `stop_times.txt`. Times are `HH:MM:SS` since the start of the
+/// service day and pass `24:00:00` for trains running past midnight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopTime {
+    pub trip_id: String,
+    pub arrival_time: String,
+    pub departure_time: String,
+    pub stop_id: String,
+    pub stop_sequence: u32,
+}
+
+/// A row of `calendar_dates.txt`: the service runs on `date`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarDate {
+    pub service_id: String,
+    pub date: NaiveDate,
+}
+
+/// The tables of a GTFS static feed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GtfsFeed {
+    pub agencies: Vec<Agency>,
+    pub stops: Vec<Stop>,
+    pub routes: Vec<Route>,
+    pub trips: Vec<Trip>,
+    pub stop_times: Vec<StopTime>,
+    pub calendar_dates: Vec<CalendarDate>,
+}
+
+impl GtfsFeed {
+    /// Build a feed from `journeys`, each with its service date, taking stop
+    /// names and coordinates from `index`.
+    ///
+    /// Scheduled times are used throughout. Journeys with fewer than two
+    /// stops or no scheduled time at the origin are left out, as is a second journey of
+    /// the same train on the same date.
+    #[must_use]
+    pub fn from_journeys(index: &StationIndex, journeys: &[(NaiveDate, TrainJourney)]) -> Self {
+        let mut agencies = BTreeMap::new();
+        let mut stops = BTreeMap::new();
+        let mut routes = BTreeMap::new();
+        let mut dates = BTreeSet::new();
+        let mut seen = HashSet::new();
+        let mut feed = Self::default();
+
+        for (date, journey) in journeys {
+            let trip_id = trip_id(&journey.train_number, *date);
+            if journey.stops.len() < 2 || seen.contains(&trip_id) {
+                continue;
+            }
+            let Some(times) = stop_times(&journey.stops) else {
+                tracing::debug!("Skipping trip {trip_id}: no scheduled time at the origin");
+                continue;
+            };
+            seen.insert(trip_id.clone());
+
+            let agency_id = journey.operator.trim().to_string();
+            agencies
+                .entry(agency_id.clone())
+                .or_insert_with(|| agency(&agency_id));
+
+            let (code, name) = service_code(&journey.service_type);
+            let route_id = route_id(&agency_id, &journey.service_type);
+            routes.entry(route_id.clone()).or_insert_with(|| Route {
+                route_id: route_id.clone(),
+                agency_id: agency_id.clone(),
+                route_short_name: code.to_string(),
+                route_long_name: name.to_string(),
+                route_type: ROUTE_TYPE_RAIL,
+            });
+
+            dates.insert(*date);
+            feed.trips.push(Trip {
+                route_id,
+                service_id: service_id(*date),
+                trip_id: trip_id.clone(),
+                trip_short_name: journey.train_number.trim().to_string(),
+                trip_headsign: journey.destination.designation.clone(),
+            });
+
+            for (sequence, (stop, (arrival, departure))) in
+                journey.stops.iter().zip(times).enumerate()
+            {
+                let stop_id = stop_id(&stop.station.code);
+                stops.entry(stop_id.clone()).or_insert_with(|| {
+                    let at = index.coordinates(&stop.station.code);
+                    Stop {
+                        stop_id: stop_id.clone(),
+                        stop_name: index
+                            .name_of(&stop.station.code)
+                            .unwrap_or(&stop.station.designation)
+                            .to_string(),
+                        stop_lat: at.map(|c| c.latitude),
+                        stop_lon: at.map(|c| c.longitude),
+                    }
+                });
+                feed.stop_times.push(StopTime {
+                    trip_id: trip_id.clone(),
+                    arrival_time: gtfs_time(arrival),
+                    departure_time: gtfs_time(departure),
+                    stop_id,
+                    stop_sequence: u32::try_from(sequence + 1).unwrap_or(u32::MAX),
+                });
+            }
+        }
+
+        feed.agencies = agencies.into_values().collect();
+        feed.stops = stops.into_values().collect();
+        feed.routes = routes.into_values().collect();
+        feed.calendar_dates = dates
+            .into_iter()
+            .map(|date| CalendarDate {
+                service_id: service_id(date),
+                date,
+            })
+            .collect();
+        feed
+    }
+
+    /// Check that every reference in the feed resolves (trips to routes and
+    /// services, routes to agencies, stop times to trips and stops), that
+    /// every stop has coordinates and that each trip's stop times run
+    /// forward.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::GtfsError`] listing the problems found.
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let mut problems = Vec::new();
+
+        let agencies: HashSet<_> = self.agencies.iter().map(|a| &a.agency_id).collect();
+        let stops: HashSet<_> = self.stops.iter().map(|s| &s.stop_id).collect();
+        let routes: HashSet<_> = self.routes.iter().map(|r| &r.route_id).collect();
+        let services: HashSet<_> = self.calendar_dates.iter().map(|c| &c.service_id).collect();
+        let trips: HashSet<_> = self.trips.iter().map(|t| &t.trip_id).collect();
+
+        if self.agencies.is_empty() {
+            problems.push("no agency".to_string());
+        }
+        for stop in &self.stops {
+            if stop.stop_lat.is_none() || stop.stop_lon.is_none() {
+                problems.push(format!("stop {} has no coordinates", stop.stop_id));
+            }
+        }
+        for route in &self.routes {
+            if !agencies.contains(&route.agency_id) {
+                problems.push(format!(
+                    "route {} refers to unknown agency {}",
+                    route.route_id, route.agency_id
+                ));
+            }
+        }
+        for trip in &self.trips {
+            if !routes.contains(&trip.route_id) {
+                problems.push(format!(
+                    "trip {} refers to unknown route {}",
+                    trip.trip_id, trip.route_id
+                ));
+            }
+            if !services.contains(&trip.service_id) {
+                problems.push(format!(
+                    "trip {} refers to unknown service {}",
+                    trip.trip_id, trip.service_id
+                ));
+            }
+        }
+
+        let mut last: Option<(&str, u32, u32)> = None;
+        let mut with_stop_times = HashSet::new();
+        for stop_time in &self.stop_times {
+            if !trips.contains(&stop_time.trip_id) {
+                problems.push(format!("stop time of unknown trip {}", stop_time.trip_id));
+            }
+            if !stops.contains(&stop_time.stop_id) {
+                problems.push(format!(
+                    "trip {} stops at unknown stop {}",
+                    stop_time.trip_id, stop_time.stop_id
+                ));
+            }
+            with_stop_times.insert(&stop_time.trip_id);
+
+            let (Some(arrival), Some(departure)) = (
+                parse_gtfs_time(&stop_time.arrival_time),
+                parse_gtfs_time(&stop_time.departure_time),
+            ) else {
+                problems.push(format!(
+                    "trip {} has an invalid time at stop {}",
+                    stop_time.trip_id, stop_time.stop_sequence
+                ));
+                continue;
+            };
+            if departure < arrival {
+                problems.push(format!(
+                    "trip {} departs stop {} before arriving",
+                    stop_time.trip_id, stop_time.stop_sequence
+                ));
+            }
+            if let Some((trip, sequence, previous)) = last
+                && trip == stop_time.trip_id
+                && (stop_time.stop_sequence <= sequence || arrival < previous)
+            {
+                problems.push(format!(
+                    "trip {} goes back in time or sequence at stop {}",
+                    stop_time.trip_id, stop_time.stop_sequence
+                ));
+            }
+            last = Some((&stop_time.trip_id, stop_time.stop_sequence, departure));
+        }
+        for trip in &self.trips {
+            if !with_stop_times.contains(&trip.trip_id) {
+                problems.push(format!("trip {} has no stop times", trip.trip_id));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let mut message = problems[..problems.len().min(PROBLEMS_SHOWN)].join("; ");
+        if problems.len() > PROBLEMS_SHOWN {
+            message.push_str(&format!("; and {} more", problems.len() - PROBLEMS_SHOWN));
+        }
+        Err(CoreError::GtfsError(message))
+    }
+
+    /// Validate the feed and write it as a zip of CSV files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::GtfsError`] if the feed is invalid or cannot be
+    /// written.
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<(), CoreError> {
+        self.validate()?;
+
+        let mut zip = zip::ZipWriter::new(writer);
+        for (name, contents) in self.files() {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .map_err(|e| CoreError::GtfsError(format!("writing {name}: {e}")))?;
+            zip.write_all(contents.as_bytes())
+                .map_err(|e| CoreError::GtfsError(format!("writing {name}: {e}")))?;
+        }
+        zip.finish()
+            .map_err(|e| CoreError::GtfsError(format!("finishing zip: {e}")))?;
+        Ok(())
+    }
+
+    /// The feed's files as CSV, by file name.
+    #[must_use]
+    pub fn files(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "agency.txt",
+                csv(
+                    &["agency_id", "agency_name", "agency_url", "agency_timezone"],
+                    self.agencies.iter().map(|a| {
+                        vec![
+                            a.agency_id.clone(),
+                            a.agency_name.clone(),
+                            a.agency_url.clone(),
+                            a.agency_timezone.clone(),
+                        ]
+                    }),
+                ),
+            ),
+            (
+                "stops.txt",
+                csv(
+                    &["stop_id", "stop_name", "stop_lat", "stop_lon"],
+                    self.stops.iter().map(|s| {
+                        vec![
+                            s.stop_id.clone(),
+                            s.stop_name.clone(),
+                            s.stop_lat.map(|v| format!("{v:.6}")).unwrap_or_default(),
+                            s.stop_lon.map(|v| format!("{v:.6}")).unwrap_or_default(),
+                        ]
+                    }),
+                ),
+            ),
+            (
+                "routes.txt",
+                csv(
+                    &[
+                        "route_id",
+                        "agency_id",
+                        "route_short_name",
+                        "route_long_name",
+                        "route_type",
+                    ],
+                    self.routes.iter().map(|r| {
+                        vec![
+                            r.route_id.clone(),
+                            r.agency_id.clone(),
+                            r.route_short_name.clone(),
+                            r.route_long_name.clone(),
+                            r.route_type.to_string(),
+                        ]
+                    }),
+                ),
+            ),
+            (
+                "trips.txt",
+                csv(
+                    &[
+                        "route_id",
+                        "service_id",
+                        "trip_id",
+                        "trip_short_name",
+                        "trip_headsign",
+                    ],
+                    self.trips.iter().map(|t| {
+                        vec![
+                            t.route_id.clone(),
+                            t.service_id.clone(),
+                            t.trip_id.clone(),
+                            t.trip_short_name.clone(),
+                            t.trip_headsign.clone(),
+                        ]
+                    }),
+                ),
+            ),
+            (
+                "stop_times.txt",
+                csv(
+                    &[
+                        "trip_id",
+                        "arrival_time",
+                        "departure_time",
+                        "stop_id",
+                        "stop_sequence",
+                    ],
+                    self.stop_times.iter().map(|s| {
+                        vec![
+                            s.trip_id.clone(),
+                            s.arrival_time.clone(),
+                            s.departure_time.clone(),
+                            s.stop_id.clone(),
+                            s.stop_sequence.to_string(),
+                        ]
+                    }),
+                ),
+            ),
+            (
+                "calendar_dates.txt",
+                csv(
+                    &["service_id", "date", "exception_type"],
+                    self.calendar_dates
+                        .iter()
+                        .map(|c| vec![c.service_id.clone(), service_id(c.date), "1".to_string()]),
+                ),
+            ),
+        ]
+    }
+}
+
+/// Live times of one stop of a trip, as a GTFS-Realtime `StopTimeUpdate`
+/// describes them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopUpdate {
+    pub stop_id: String,
+    /// 1-based, as in `stop_times.txt`.
+    pub stop_sequence: u32,
+    pub arrival: StopEvent,
+    pub departure: StopEvent,
+    /// The train no longer calls here.
+    pub skipped: bool,
+}
+
+/// Scheduled time of an arrival or departure and how late it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopEvent {
+    /// Portugal-local scheduled time.
+    pub scheduled: NaiveDateTime,
+    /// Observed delay for stops the train has reached, predicted delay for
+    /// the others; `None` when nothing is known.
+    pub delay_minutes: Option<i32>,
+}
+
+impl StopEvent {
+    /// The scheduled time moved by the delay, if there is one.
+    #[must_use]
+    pub fn expected(&self) -> Option<NaiveDateTime> {
+        self.delay_minutes
+            .map(|delay| self.scheduled + Duration::minutes(delay.into()))
+    }
+}
+
+/// The stops of `journey` on service date `date` with their live delays,
+/// numbered and timed as in the static feed. Empty when the journey would
+/// not be in the static feed.
+///
+/// Reached stops use their actual times, falling back to the delay
+/// reported at the stop. Stops ahead use the
+/// [predicted](JourneyStop::predicted_time) arrival and leave no earlier
+/// than scheduled.
+#[must_use]
+pub fn stop_updates(journey: &TrainJourney, date: NaiveDate) -> Vec<StopUpdate> {
+    if journey.stops.len() < 2 {
+        return Vec::new();
+    }
+    let Some(times) = stop_times(&journey.stops) else {
+        return Vec::new();
+    };
+    let midnight = date.and_time(NaiveTime::MIN);
+    let at = |minutes: i32| midnight + Duration::minutes(minutes.into());
+
+    journey
+        .stops
+        .iter()
+        .zip(times)
+        .enumerate()
+        .map(|(i, (stop, (arrival, departure)))| {
+            let (arrival, departure) = (at(arrival), at(departure));
+            let (arrival_delay, departure_delay) = if has_reached(stop) {
+                let observed = |scheduled: NaiveDateTime, actual: Option<&str>| {
+                    actual
+                        .and_then(|time| datetime_near(scheduled, time))
+                        .map(|actual| minutes_between(scheduled, actual))
+                        .or(stop.delay_minutes)
+                };
+                (
+                    observed(arrival, stop.actual_arrival.as_deref()),
+                    observed(departure, stop.actual_departure.as_deref()),
+                )
+            } else {
+                let predicted = stop
+                    .predicted_time
+                    .as_deref()
+                    .and_then(|time| datetime_near(arrival, time));
+                (
+                    predicted.map(|p| minutes_between(arrival, p)),
+                    predicted.map(|p| minutes_between(departure, p).max(0)),
+                )
+            };
+
+            StopUpdate {
+                stop_id: stop_id(&stop.station.code),
+                stop_sequence: u32::try_from(i + 1).unwrap_or(u32::MAX),
+                arrival: StopEvent {
+                    scheduled: arrival,
+                    delay_minutes: arrival_delay,
+                },
+                departure: StopEvent {
+                    scheduled: departure,
+                    delay_minutes: departure_delay,
+                },
+                skipped: stop.status == StopStatus::Cancelled,
+            }
+        })
+        .collect()
+}
+
+fn minutes_between(from: NaiveDateTime, to: NaiveDateTime) -> i32 {
+    i32::try_from((to - from).num_minutes()).unwrap_or_default()
+}
+
+/// Code and name of a `"CODE|Designation"` service type.
+fn service_code(service_type: &str) -> (&str, &str) {
+    match service_type.split_once('|') {
+        Some((code, name)) => (code.trim(), name.trim()),
+        None => (service_type.trim(), service_type.trim()),
+    }
+}
+
+fn agency(agency_id: &str) -> Agency {
+    let (name, url) = match agency_id {
+        "CP" => ("CP - Comboios de Portugal", "https://www.cp.pt"),
+        "Fertagus" => ("Fertagus", "https://www.fertagus.pt"),
+        _ => (agency_id, "https://www.cp.pt"),
+    };
+    Agency {
+        agency_id: agency_id.to_string(),
+        agency_name: name.to_string(),
+        agency_url: url.to_string(),
+        agency_timezone: AGENCY_TIMEZONE.to_string(),
+    }
+}
+
+/// Scheduled (arrival, departure) of each stop in minutes since midnight of
+/// the service day, increasing across midnight. The origin has no arrival
+/// and borrows its departure; `None` if the origin has no time at all.
+fn stop_times(stops: &[JourneyStop]) -> Option<Vec<(i32, i32)>> {
+    let origin = stops.first()?;
+    minutes(&origin.scheduled_departure).or_else(|| minutes(&origin.scheduled_arrival))?;
+
+    let timeline = Timeline::new(stops);
+    let times = (0..stops.len())
+        .map(|i| (timeline.arrival(i), timeline.departure(i)))
+        .enumerate()
+        .map(|(i, (arrival, departure))| {
+            if i == 0 && minutes(&origin.scheduled_arrival).is_none() {
+                (departure, departure)
+            } else {
+                (arrival, departure)
+            }
+        })
+        .collect();
+    Some(times)
+}
+
+fn gtfs_time(minutes: i32) -> String {
+    format!("{:02}:{:02}:00", minutes / 60, minutes % 60)
+}
+
+/// Parse a GTFS `H:MM:SS` time, which may pass 24 hours, into seconds.
+pub(crate) fn parse_gtfs_time(time: &str) -> Option<u32> {
+    let mut parts = time.trim().split(':');
+    let hours: u32 = parts.next()?.parse().ok()?;
+    let mins: u32 = parts.next()?.parse().ok()?;
+    let secs: u32 = parts.next()?.parse().ok()?;
+    (mins < 60 && secs < 60 && parts.next().is_none()).then_some(hours * 3600 + mins * 60 + secs)
+}
+
+/// A CSV file: `header`, then `rows`, quoting fields where needed.
+fn csv(header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(header).expect("writing CSV to memory");
+    for row in rows {
+        writer.write_record(&row).expect("writing CSV to memory");
+    }
+    let bytes = writer.into_inner().expect("writing CSV to memory");
+    String::from_utf8(bytes).expect("CSV of strings is UTF-8")
+}
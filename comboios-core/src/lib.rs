@@ -29,7 +29,7 @@
 //!     }
 //!
 //!     // Get live departure board
-//!     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+//!     let today = client.today();
 //!     let board = client.get_station_timetable("94-31039", &today, None).await?;
 //!
 //!     // Track a train
@@ -41,11 +41,20 @@
 //! ```
 
 pub mod adapters;
+pub mod clock;
 pub mod domain;
 pub mod error;
+pub mod gtfs;
+pub mod monitor;
+pub mod planner;
+pub mod prediction;
 pub mod query_builder;
+pub mod risk;
+pub mod transit;
+pub mod watch;
 
 pub(crate) mod constants;
+pub(crate) mod timeline;
 
 pub use client::Comboios;
 pub use error::CoreError as Error;
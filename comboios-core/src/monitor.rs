@@ -0,0 +1,631 @@
+//! Network-wide table of the trains running right now.
+//!
+//! Neither CP nor IP can list the trains currently on the network, but every
+//! running train appears on the departure board of some station it calls at.
+//! [`NetworkMonitor`] sweeps the boards of a set of major stations, fetches
+//! the journey of every train it finds and keeps the ones in progress, with
+//! their delay and estimated position, in an in-memory table.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use comboios_core::Comboios;
+//! use comboios_core::monitor::{MonitorConfig, NetworkMonitor};
+//!
+//! # async fn run() -> Result<(), comboios_core::Error> {
+//! let monitor = NetworkMonitor::new(Comboios::new().await?, MonitorConfig::default());
+//! monitor.spawn();
+//!
+//! // ... later
+//! for train in monitor.trains().await {
+//!     println!("{} {:?} min late", train.train_number, train.delay_minutes);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::Comboios;
+use crate::domain::journey::{JourneyStatus, TrainJourney};
+use crate::domain::position::TrainPosition;
+use crate::domain::station::{Station, StationIndex};
+use crate::error::CoreError;
+use crate::query_builder::BoardWindow;
+
+/// Stations swept by default: the main interchanges of the long-distance,
+/// regional and suburban networks. Entries are CP ids or station names.
+pub const MAJOR_STATIONS: &[&str] = &[
+    "94-31039", // Lisboa - Oriente
+    "94-30007", // Lisboa - Santa Apolónia
+    "94-2006",  // Porto - Campanhã
+    "Lisboa - Rossio",
+    "Cais do Sodré",
+    "Sintra",
+    "Cascais",
+    "Pinhal Novo",
+    "Setúbal",
+    "Entroncamento",
+    "Coimbra-B",
+    "Aveiro",
+    "Porto - São Bento",
+    "Braga",
+    "Guimarães",
+    "Viana do Castelo",
+    "Régua",
+    "Guarda",
+    "Évora",
+    "Tunes",
+    "Faro",
+];
+
+/// Settings for a [`NetworkMonitor`].
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    stations: Vec<String>,
+    interval: Duration,
+    concurrency: usize,
+    lookback: Duration,
+    lookahead: Duration,
+}
+
+impl Default for MonitorConfig {
+    /// Sweep [`MAJOR_STATIONS`] every two minutes, four requests at a time,
+    /// looking at departures from two hours ago to fifteen minutes ahead.
+    fn default() -> Self {
+        Self {
+            stations: MAJOR_STATIONS.iter().map(ToString::to_string).collect(),
+            interval: Duration::from_mins(2),
+            concurrency: 4,
+            lookback: Duration::from_hours(2),
+            lookahead: Duration::from_mins(15),
+        }
+    }
+}
+
+impl MonitorConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stations whose boards are swept, as CP/IP ids or station names.
+    /// Entries that cannot be resolved against the station index are skipped.
+    #[must_use]
+    pub fn stations<I, S>(mut self, stations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stations = stations.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Time between the start of two sweeps.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Maximum number of requests in flight at once (at least one).
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Board window around "now": trains that departed up to `lookback` ago
+    /// or depart within `lookahead`.
+    #[must_use]
+    pub fn window(mut self, lookback: Duration, lookahead: Duration) -> Self {
+        self.lookback = lookback;
+        self.lookahead = lookahead;
+        self
+    }
+
+    #[must_use]
+    pub fn get_stations(&self) -> &[String] {
+        &self.stations
+    }
+
+    #[must_use]
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    #[must_use]
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    fn board_window(&self, now: NaiveDateTime) -> BoardWindow {
+        let lookback = chrono::Duration::from_std(self.lookback).unwrap_or_default();
+        let lookahead = chrono::Duration::from_std(self.lookahead).unwrap_or_default();
+        BoardWindow::new(now - lookback..now + lookahead)
+    }
+}
+
+/// A train in the [`NetworkMonitor`] table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveTrain {
+    pub train_number: String,
+    pub service_type: String,
+    pub origin: Station,
+    pub destination: Station,
+    pub operator: String,
+    pub status: JourneyStatus,
+    pub delay_minutes: Option<i32>,
+    /// The stop the train is at or heading to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_stop: Option<Station>,
+    /// Predicted arrival at the destination (`HH:MM`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predicted_arrival: Option<String>,
+    /// Live or estimated position; `None` when it cannot be placed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<TrainPosition>,
+    /// Portugal-local time the entry was refreshed.
+    pub updated_at: NaiveDateTime,
+}
+
+impl LiveTrain {
+    /// Summarise `journey` as seen at `now`.
+    #[must_use]
+    pub fn from_journey(
+        journey: &TrainJourney,
+        position: Option<TrainPosition>,
+        now: NaiveDateTime,
+    ) -> Self {
+        Self {
+            train_number: journey.train_number.clone(),
+            service_type: journey.service_type.clone(),
+            origin: journey.origin.clone(),
+            destination: journey.destination.clone(),
+            operator: journey.operator.clone(),
+            status: journey.status.clone(),
+            delay_minutes: journey.delay_minutes,
+            next_stop: journey.current_stop().map(|s| s.station.clone()),
+            predicted_arrival: journey.predicted_arrival.clone(),
+            position,
+            updated_at: now,
+        }
+    }
+
+    /// Whether a journey in this state counts as running.
+    #[must_use]
+    pub fn is_running(status: &JourneyStatus) -> bool {
+        matches!(status, JourneyStatus::InProgress | JourneyStatus::Delayed)
+    }
+}
+
+/// Outcome of one [`NetworkMonitor::sweep`].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SweepReport {
+    /// Stations whose boards were requested.
+    pub stations: usize,
+    /// Boards that could not be fetched.
+    pub stations_failed: usize,
+    /// Distinct trains found on the boards.
+    pub discovered: usize,
+    /// Trains in progress, now in the table.
+    pub running: usize,
+}
+
+/// Keeps a live table of the trains running on the network.
+///
+/// Cheap to clone; clones share the same table.
+#[derive(Debug, Clone)]
+pub struct NetworkMonitor {
+    api: Comboios,
+    config: Arc<MonitorConfig>,
+    trains: Arc<RwLock<HashMap<String, LiveTrain>>>,
+    journeys: Arc<RwLock<Vec<(NaiveDate, TrainJourney)>>>,
+    last_sweep: Arc<RwLock<Option<NaiveDateTime>>>,
+}
+
+impl NetworkMonitor {
+    #[must_use]
+    pub fn new(api: Comboios, config: MonitorConfig) -> Self {
+        Self {
+            api,
+            config: Arc::new(config),
+            trains: Arc::default(),
+            journeys: Arc::default(),
+            last_sweep: Arc::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &MonitorConfig {
+        &self.config
+    }
+
+    /// Every running train, ordered by train number.
+    pub async fn trains(&self) -> Vec<LiveTrain> {
+        let mut trains: Vec<LiveTrain> = self.trains.read().await.values().cloned().collect();
+        trains.sort_by(|a, b| {
+            a.train_number
+                .parse::<u64>()
+                .ok()
+                .cmp(&b.train_number.parse::<u64>().ok())
+                .then_with(|| a.train_number.cmp(&b.train_number))
+        });
+        trains
+    }
+
+    /// The journey of every train found on the boards in the last sweep,
+    /// running or not, with the service date it was looked up on: the day
+    /// it passed the board's station. Ordered by train number, then date.
+    pub async fn journeys(&self) -> Vec<(NaiveDate, TrainJourney)> {
+        self.journeys.read().await.clone()
+    }
+
+    /// The entry for one train, if it is running.
+    pub async fn train(&self, train_number: &str) -> Option<LiveTrain> {
+        self.trains.read().await.get(train_number).cloned()
+    }
+
+    /// When the table was last refreshed (Portugal local time).
+    pub async fn last_sweep(&self) -> Option<NaiveDateTime> {
+        *self.last_sweep.read().await
+    }
+
+    /// Sweep the configured boards once and replace the table with the
+    /// trains found running.
+    ///
+    /// Boards and journeys that fail to load are skipped, so one station or
+    /// train being unavailable does not empty the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last board error when no board could be fetched; the table
+    /// is left unchanged in that case.
+    pub async fn sweep(&self) -> Result<SweepReport, CoreError> {
+        let index = self.api.station_index().await;
+        let codes = resolve_stations(&index, &self.config.stations);
+        let now = self.api.clock().now().naive_local();
+        let window = self.config.board_window(now);
+        let concurrency = self.config.concurrency;
+
+        let boards: Vec<_> = stream::iter(codes.clone())
+            .map(|code| {
+                let (api, window) = (self.api.clone(), window.clone());
+                async move { api.dated_departures(&code, window).await }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        let mut report = SweepReport {
+            stations: codes.len(),
+            ..SweepReport::default()
+        };
+        let mut last_error = None;
+        // A train is looked up on the day it passes the station, so one
+        // seen at 23:50 is still found on the day before after midnight.
+        let mut runs = BTreeSet::new();
+        for board in boards {
+            match board {
+                Ok(rows) => runs.extend(rows.iter().map(|(at, t)| (at.date(), t.train_number))),
+                Err(e) => {
+                    tracing::warn!("Live sweep: board failed: {e}");
+                    report.stations_failed += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+        if report.stations_failed == report.stations
+            && let Some(e) = last_error
+        {
+            return Err(e);
+        }
+        report.discovered = runs.len();
+
+        let journeys: Vec<_> = stream::iter(runs)
+            .map(|(service_date, n)| {
+                let api = self.api.clone();
+                let date = service_date.format("%Y-%m-%d").to_string();
+                async move {
+                    let journey = api.get_train_journey(&n.to_string(), &date).await;
+                    journey.map(|j| (service_date, j))
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        let mut journeys: Vec<(NaiveDate, TrainJourney)> =
+            journeys.into_iter().filter_map(Result::ok).collect();
+        journeys.sort_by_key(|(date, j)| (j.train_number.parse::<u64>().ok(), *date));
+        let table = build_table(&journeys, &index, now);
+        report.running = table.len();
+
+        *self.trains.write().await = table;
+        *self.journeys.write().await = journeys;
+        *self.last_sweep.write().await = Some(now);
+        tracing::info!(
+            "Live sweep: {} running of {} trains on {} boards ({} failed)",
+            report.running,
+            report.discovered,
+            report.stations,
+            report.stations_failed
+        );
+
+        Ok(report)
+    }
+
+    /// Run [`sweep`](Self::sweep) in the background every
+    /// [`interval`](MonitorConfig::interval) until the handle is aborted.
+    pub fn spawn(&self) -> tokio::task::JoinHandle<()> {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(monitor.config.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = monitor.sweep().await {
+                    tracing::warn!("Live sweep failed: {e}");
+                }
+            }
+        })
+    }
+}
+
+/// Map configured entries to station ids with [`StationIndex::resolve`],
+/// dropping unknown and duplicate entries.
+fn resolve_stations(index: &StationIndex, entries: &[String]) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match index.resolve(entry) {
+            Some(code) if !codes.contains(&code) => codes.push(code),
+            Some(_) => {}
+            None => tracing::debug!("Live sweep: unknown station {entry:?}, skipped"),
+        }
+    }
+    codes
+}
+
+fn build_table(
+    journeys: &[(NaiveDate, TrainJourney)],
+    index: &StationIndex,
+    now: NaiveDateTime,
+) -> HashMap<String, LiveTrain> {
+    journeys
+        .iter()
+        .filter(|(_, j)| LiveTrain::is_running(&j.status))
+        .map(|(service_date, j)| {
+            let position = TrainPosition::estimate(j, index, *service_date, now);
+            (
+                j.train_number.clone(),
+                LiveTrain::from_journey(j, position, now),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use wiremock::matchers::{method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::adapters::{CpAdapter, IpAdapter};
+    use crate::clock::FixedClock;
+
+    fn station(code: &str, name: &str) -> Station {
+        Station {
+            code: code.to_string(),
+            designation: name.to_string(),
+        }
+    }
+
+    fn board_row(train: u64, time: &str) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "trainService": {"code": "R", "designation": "Regional"},
+            "trainOrigin": {"code": "94-1", "designation": "A"},
+            "trainDestination": {"code": "94-3", "designation": "C"},
+            "departureTime": time,
+        })
+    }
+
+    fn timetable(train: u64, status: &str, last_station: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "serviceCode": {"code": "R", "designation": "Regional"},
+            "lastStationCode": last_station,
+            "delay": 4,
+            "trainStops": [
+                {"station": {"code": "94-1", "designation": "A"}, "departure": "10:00"},
+                {"station": {"code": "94-2", "designation": "B"}, "arrival": "10:20", "departure": "10:21"},
+                {"station": {"code": "94-3", "designation": "C"}, "arrival": "10:40"}
+            ],
+            "status": status,
+            "messages": []
+        })
+    }
+
+    fn client(server: &MockServer) -> Comboios {
+        let now = NaiveDate::from_ymd_opt(2026, 3, 14)
+            .unwrap()
+            .and_hms_opt(10, 10, 0)
+            .unwrap();
+        client_at(server, now)
+    }
+
+    fn client_at(server: &MockServer, now: NaiveDateTime) -> Comboios {
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()))
+            .with_clock(FixedClock::at_local(now).unwrap())
+    }
+
+    #[test]
+    fn resolve_stations_by_id_or_name_and_dedups() {
+        let index = StationIndex::new([
+            station("94-31039", "Lisboa - Oriente"),
+            station("94-2006", "Porto - Campanhã"),
+        ]);
+        let entries = [
+            "9431039",
+            "Porto Campanha",
+            "94-31039",
+            "Atlantis",
+            "94-777",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            resolve_stations(&index, &entries),
+            vec!["94-31039", "94-2006", "94-777"]
+        );
+    }
+
+    #[test]
+    fn config_concurrency_is_at_least_one() {
+        assert_eq!(MonitorConfig::new().concurrency(0).get_concurrency(), 1);
+    }
+
+    #[tokio::test]
+    async fn sweep_keeps_only_running_trains() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(
+                r"^/services/travel-api/stations/94-[12]/timetable/2026-03-14$",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [board_row(100, "10:00"), board_row(200, "10:05")],
+                "messages": []
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/services/travel-api/trains/100/timetable/2026-03-14"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(timetable(
+                100,
+                "IN_TRANSIT",
+                Some("94-1"),
+            )))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/services/travel-api/trains/200/timetable/2026-03-14"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(timetable(
+                200,
+                "SCHEDULED",
+                None,
+            )))
+            .mount(&server)
+            .await;
+
+        let monitor = NetworkMonitor::new(
+            client(&server),
+            MonitorConfig::new().stations(["94-1", "94-2"]),
+        );
+        let report = monitor.sweep().await.unwrap();
+
+        assert_eq!(
+            report,
+            SweepReport {
+                stations: 2,
+                stations_failed: 0,
+                discovered: 2,
+                running: 1
+            }
+        );
+        let trains = monitor.trains().await;
+        assert_eq!(trains.len(), 1);
+        assert_eq!(trains[0].train_number, "100");
+        assert_eq!(trains[0].delay_minutes, Some(4));
+        assert_eq!(trains[0].next_stop.as_ref().unwrap().code, "94-2");
+        assert!(monitor.last_sweep().await.is_some());
+
+        let journeys = monitor.journeys().await;
+        let numbers: Vec<_> = journeys
+            .iter()
+            .map(|(_, j)| j.train_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["100", "200"]);
+    }
+
+    #[tokio::test]
+    async fn sweep_after_midnight_looks_trains_up_on_their_own_day() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-1/timetable/2026-03-14",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [board_row(300, "23:50"), board_row(400, "00:05")],
+                "messages": []
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-1/timetable/2026-03-15",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [board_row(400, "00:05")],
+                "messages": []
+            })))
+            .mount(&server)
+            .await;
+        for (train, date) in [(300, "2026-03-14"), (400, "2026-03-15")] {
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/services/travel-api/trains/{train}/timetable/{date}"
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_json(timetable(
+                    train,
+                    "IN_TRANSIT",
+                    Some("94-1"),
+                )))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let now = NaiveDate::from_ymd_opt(2026, 3, 15)
+            .unwrap()
+            .and_hms_opt(0, 10, 0)
+            .unwrap();
+        let monitor = NetworkMonitor::new(
+            client_at(&server, now),
+            MonitorConfig::new().stations(["94-1"]),
+        );
+        let report = monitor.sweep().await.unwrap();
+
+        assert_eq!(report.discovered, 2);
+        assert_eq!(report.running, 2);
+    }
+
+    #[tokio::test]
+    async fn sweep_fails_when_every_board_fails() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let monitor = NetworkMonitor::new(client(&server), MonitorConfig::new().stations(["94-1"]));
+
+        assert!(monitor.sweep().await.is_err());
+        assert!(monitor.last_sweep().await.is_none());
+    }
+}
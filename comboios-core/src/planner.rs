@@ -0,0 +1,811 @@
+//! Journey planning with changes of train.
+//!
+//! The planner lays the timetable out as a time-expanded graph: every run of
+//! a train between two consecutive stops is a [`Connection`]. The trains are
+//! found on the departure boards of the origin and of the main interchanges,
+//! and their stops come from each train's journey. A round-based connection
+//! scan (CSA) over that graph finds, for every number of changes up to a
+//! limit, the earliest arrival at the destination; the itineraries where an
+//! extra change buys an earlier arrival form the Pareto set returned.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use comboios_core::Comboios;
+//! use comboios_core::planner::{PlannerConfig, TripPlanner};
+//!
+//! # async fn run(client: Comboios) -> Result<(), comboios_core::Error> {
+//! let config = PlannerConfig::new()
+//!     .max_transfers(2)
+//!     .transfer_time("94-31039", Duration::from_mins(8));
+//! let planner = TripPlanner::new(client.clone(), config);
+//!
+//! for trip in planner.plan("Braga", "Faro", &client.today(), Some("07:00")).await? {
+//!     println!("{} change(s), arriving {}", trip.transfers, trip.arrival);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::Comboios;
+use crate::adapters::normalize_station_id;
+use crate::client::parse_service_start;
+use crate::domain::journey::{JourneyStatus, StopStatus, TrainJourney};
+use crate::domain::station::Station;
+use crate::domain::trip::DirectTrain;
+use crate::error::CoreError;
+use crate::query_builder::BoardWindow;
+use crate::timeline::Timeline;
+
+/// Interchanges whose departure boards are searched for onward trains by
+/// default. Entries are CP ids or station names.
+pub const INTERCHANGES: &[&str] = &[
+    "94-31039", // Lisboa - Oriente
+    "94-30007", // Lisboa - Santa Apolónia
+    "94-2006",  // Porto - Campanhã
+    "Entroncamento",
+    "Coimbra-B",
+    "Aveiro",
+    "Nine",
+    "Pinhal Novo",
+    "Tunes",
+];
+
+/// Most changes of train callers should let users ask for; every extra
+/// change is one more scan of the connection graph.
+pub const MAX_TRANSFERS: usize = 5;
+
+/// How long a [`TripPlanner`] and its clones reuse the boards and journeys
+/// they fetched.
+const CACHE_TTL: Duration = Duration::from_mins(2);
+
+/// Settings for a [`TripPlanner`].
+#[derive(Debug, Clone)]
+pub struct PlannerConfig {
+    interchanges: Vec<String>,
+    min_transfer: Duration,
+    transfer_times: HashMap<String, Duration>,
+    max_transfers: usize,
+    horizon: Duration,
+    max_journeys: usize,
+    concurrency: usize,
+}
+
+impl Default for PlannerConfig {
+    /// Change at [`INTERCHANGES`] with five minutes to change trains, up to
+    /// three changes, searching the first 150 trains that leave within twelve
+    /// hours.
+    fn default() -> Self {
+        Self {
+            interchanges: INTERCHANGES.iter().map(ToString::to_string).collect(),
+            min_transfer: Duration::from_mins(5),
+            transfer_times: HashMap::new(),
+            max_transfers: 3,
+            horizon: Duration::from_hours(12),
+            max_journeys: 150,
+            concurrency: 4,
+        }
+    }
+}
+
+impl PlannerConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stations whose departure boards are searched for onward trains, as
+    /// CP/IP ids or station names. Changes can happen at any station the
+    /// trains found call at, not only at these.
+    #[must_use]
+    pub fn interchanges<I, S>(mut self, stations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.interchanges = stations.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Minimum time to change trains at stations without their own
+    /// [`transfer_time`](Self::transfer_time).
+    #[must_use]
+    pub fn min_transfer(mut self, min_transfer: Duration) -> Self {
+        self.min_transfer = min_transfer;
+        self
+    }
+
+    /// Minimum time to change trains at `station` (a CP or IP id).
+    #[must_use]
+    pub fn transfer_time(mut self, station: &str, time: Duration) -> Self {
+        self.transfer_times
+            .insert(normalize_station_id(station), time);
+        self
+    }
+
+    /// Most changes of train an itinerary may have.
+    #[must_use]
+    pub fn max_transfers(mut self, max_transfers: usize) -> Self {
+        self.max_transfers = max_transfers;
+        self
+    }
+
+    /// How long after the requested time trains may leave. The search never
+    /// looks past the end of the service day.
+    #[must_use]
+    pub fn horizon(mut self, horizon: Duration) -> Self {
+        self.horizon = horizon;
+        self
+    }
+
+    /// Most train journeys fetched for one plan: the trains leaving the
+    /// searched boards soonest.
+    #[must_use]
+    pub fn max_journeys(mut self, max_journeys: usize) -> Self {
+        self.max_journeys = max_journeys;
+        self
+    }
+
+    /// Maximum number of requests in flight at once (at least one).
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn get_interchanges(&self) -> &[String] {
+        &self.interchanges
+    }
+
+    #[must_use]
+    pub fn get_max_transfers(&self) -> usize {
+        self.max_transfers
+    }
+
+    #[must_use]
+    pub fn get_horizon(&self) -> Duration {
+        self.horizon
+    }
+
+    #[must_use]
+    pub fn get_max_journeys(&self) -> usize {
+        self.max_journeys
+    }
+
+    #[must_use]
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Minimum time to change trains at `station` (a CP or IP id).
+    #[must_use]
+    pub fn get_transfer_time(&self, station: &str) -> Duration {
+        self.transfer_times
+            .get(&normalize_station_id(station))
+            .copied()
+            .unwrap_or(self.min_transfer)
+    }
+}
+
+/// A train running between two consecutive stops: one edge of the
+/// time-expanded graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Connection {
+    pub train_number: String,
+    pub service_type: String,
+    pub operator: String,
+    pub from: Station,
+    pub to: Station,
+    /// Scheduled departure from `from` (Portugal local time).
+    pub departure: NaiveDateTime,
+    /// Scheduled arrival at `to` (Portugal local time).
+    pub arrival: NaiveDateTime,
+    pub departure_platform: Option<String>,
+    pub arrival_platform: Option<String>,
+}
+
+impl Connection {
+    /// Split `journey`, running on `service_date`, into connections between
+    /// consecutive stops, on the timetable.
+    ///
+    /// Cancelled journeys have none, and cancelled stops are left out so no
+    /// one is routed through them.
+    #[must_use]
+    pub fn from_journey(journey: &TrainJourney, service_date: NaiveDate) -> Vec<Connection> {
+        if journey.status == JourneyStatus::Cancelled {
+            return Vec::new();
+        }
+
+        let timeline = Timeline::new(&journey.stops);
+        let midnight = service_date.and_time(NaiveTime::MIN);
+        let at = |minutes: i32| midnight + chrono::Duration::minutes(minutes.into());
+
+        let calls: Vec<usize> = (0..journey.stops.len())
+            .filter(|&i| journey.stops[i].status != StopStatus::Cancelled)
+            .collect();
+
+        calls
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = (&journey.stops[pair[0]], &journey.stops[pair[1]]);
+                Connection {
+                    train_number: journey.train_number.clone(),
+                    service_type: journey.service_type.clone(),
+                    operator: journey.operator.clone(),
+                    from: from.station.clone(),
+                    to: to.station.clone(),
+                    departure: at(timeline.departure(pair[0])),
+                    arrival: at(timeline.arrival(pair[1])),
+                    departure_platform: from.platform.clone(),
+                    arrival_platform: to.platform.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// One train ridden from boarding to alighting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripLeg {
+    pub train_number: String,
+    pub service_type: String,
+    pub operator: String,
+    pub from: Station,
+    pub to: Station,
+    pub departure: NaiveDateTime,
+    pub arrival: NaiveDateTime,
+    pub departure_platform: Option<String>,
+    pub arrival_platform: Option<String>,
+}
+
+/// A way of getting from one station to another, possibly changing trains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Itinerary {
+    /// Trains ridden, in order.
+    pub legs: Vec<TripLeg>,
+    /// Departure of the first leg.
+    pub departure: NaiveDateTime,
+    /// Arrival of the last leg.
+    pub arrival: NaiveDateTime,
+    /// Number of changes of train (`legs.len() - 1`).
+    pub transfers: usize,
+}
+
+impl Itinerary {
+    /// Time from the first departure to the final arrival, in minutes.
+    #[must_use]
+    pub fn duration_minutes(&self) -> i64 {
+        (self.arrival - self.departure).num_minutes()
+    }
+}
+
+impl From<&DirectTrain> for Itinerary {
+    /// A single-leg itinerary riding `train` from its boarding to its
+    /// alighting station.
+    fn from(train: &DirectTrain) -> Self {
+        Self {
+            legs: vec![TripLeg {
+                train_number: train.train_number.clone(),
+                service_type: train.service_type.clone(),
+                operator: train.operator.clone(),
+                from: train.from.clone(),
+                to: train.to.clone(),
+                departure: train.departure,
+                arrival: train.arrival,
+                departure_platform: train.platform.clone(),
+                arrival_platform: train.arrival_platform.clone(),
+            }],
+            departure: train.departure,
+            arrival: train.arrival,
+            transfers: 0,
+        }
+    }
+}
+
+/// Best arrival at a station within a number of trains, and how it was
+/// reached.
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    arrival: NaiveDateTime,
+    /// Indices of the boarding and alighting connections of the last leg;
+    /// `None` at the origin.
+    leg: Option<(usize, usize)>,
+    /// Round (number of trains) in which the label was set.
+    round: usize,
+}
+
+/// Find the Pareto-optimal itineraries from `from` to `to` leaving at or
+/// after `depart_after`, by arrival time and number of changes.
+///
+/// Station ids may be in CP or IP format. The result holds at most one
+/// itinerary per number of changes, ordered by changes; each one arrives
+/// strictly earlier than those with fewer changes.
+#[must_use]
+pub fn search(
+    connections: &[Connection],
+    from: &str,
+    to: &str,
+    depart_after: NaiveDateTime,
+    config: &PlannerConfig,
+) -> Vec<Itinerary> {
+    let (origin, target) = (normalize_station_id(from), normalize_station_id(to));
+    if origin == target {
+        return Vec::new();
+    }
+
+    let mut scan: Vec<&Connection> = connections.iter().collect();
+    scan.sort_by_key(|c| (c.departure, c.arrival));
+    let codes: Vec<(String, String)> = scan
+        .iter()
+        .map(|c| {
+            (
+                normalize_station_id(&c.from.code),
+                normalize_station_id(&c.to.code),
+            )
+        })
+        .collect();
+
+    // best[k]: earliest arrival per station using at most k trains.
+    let mut best: Vec<HashMap<&str, Label>> = vec![HashMap::from([(
+        origin.as_str(),
+        Label {
+            arrival: depart_after,
+            leg: None,
+            round: 0,
+        },
+    )])];
+
+    for round in 1..=config.max_transfers + 1 {
+        let previous = &best[round - 1];
+        let mut current = previous.clone();
+        let mut boarded: HashMap<&str, usize> = HashMap::new();
+
+        for (i, c) in scan.iter().enumerate() {
+            let (from_code, to_code) = (&codes[i].0, &codes[i].1);
+
+            if !boarded.contains_key(c.train_number.as_str())
+                && let Some(label) = previous.get(from_code.as_str())
+            {
+                let ready = match label.leg {
+                    None => label.arrival,
+                    Some(_) => label.arrival + transfer(config, from_code),
+                };
+                if ready <= c.departure {
+                    boarded.insert(&c.train_number, i);
+                }
+            }
+
+            if let Some(&board) = boarded.get(c.train_number.as_str())
+                && current
+                    .get(to_code.as_str())
+                    .is_none_or(|l| c.arrival < l.arrival)
+            {
+                current.insert(
+                    to_code,
+                    Label {
+                        arrival: c.arrival,
+                        leg: Some((board, i)),
+                        round,
+                    },
+                );
+            }
+        }
+
+        best.push(current);
+    }
+
+    (1..best.len())
+        .filter(|&round| {
+            best[round]
+                .get(target.as_str())
+                .is_some_and(|l| l.round == round)
+        })
+        .map(|round| rebuild(&scan, &codes, &best, round, &target))
+        .collect()
+}
+
+fn transfer(config: &PlannerConfig, station: &str) -> chrono::Duration {
+    chrono::Duration::from_std(config.get_transfer_time(station)).unwrap_or_default()
+}
+
+/// Walk the labels back from `target` in `round` to the origin.
+fn rebuild(
+    scan: &[&Connection],
+    codes: &[(String, String)],
+    best: &[HashMap<&str, Label>],
+    round: usize,
+    target: &str,
+) -> Itinerary {
+    let mut legs = Vec::new();
+    let (mut round, mut station) = (round, target);
+
+    while let Some(label) = best[round].get(station)
+        && let Some((board, alight)) = label.leg
+    {
+        let (first, last) = (scan[board], scan[alight]);
+        legs.push(TripLeg {
+            train_number: first.train_number.clone(),
+            service_type: first.service_type.clone(),
+            operator: first.operator.clone(),
+            from: first.from.clone(),
+            to: last.to.clone(),
+            departure: first.departure,
+            arrival: last.arrival,
+            departure_platform: first.departure_platform.clone(),
+            arrival_platform: last.arrival_platform.clone(),
+        });
+        station = &codes[board].0;
+        round = label.round - 1;
+    }
+    legs.reverse();
+
+    Itinerary {
+        departure: legs.first().map_or(NaiveDateTime::MIN, |l| l.departure),
+        arrival: legs.last().map_or(NaiveDateTime::MIN, |l| l.arrival),
+        transfers: legs.len().saturating_sub(1),
+        legs,
+    }
+}
+
+/// Plans journeys with changes of train from live CP data.
+///
+/// A plan fetches the departure boards of the origin and the configured
+/// interchanges, then the journeys of the first
+/// [`max_journeys`](PlannerConfig::max_journeys) trains found on them. Boards
+/// (by station and hour) and journeys are reused for a couple of minutes by
+/// the planner and its clones, so repeated plans share most requests.
+#[derive(Debug, Clone)]
+pub struct TripPlanner {
+    api: Comboios,
+    config: PlannerConfig,
+    cache: Arc<Mutex<PlanCache>>,
+}
+
+/// Departures from a station over a window, as (scheduled time, train
+/// number), and when they were fetched.
+type CachedBoard = (Instant, Vec<(NaiveDateTime, u64)>);
+
+/// Board rows and journeys recently fetched by a [`TripPlanner`].
+#[derive(Debug, Default)]
+struct PlanCache {
+    boards: HashMap<(String, NaiveDateTime, NaiveDateTime), CachedBoard>,
+    journeys: HashMap<(u64, NaiveDate), (Instant, TrainJourney)>,
+}
+
+impl PlanCache {
+    fn evict_expired(&mut self) {
+        self.boards.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        self.journeys.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+    }
+}
+
+impl TripPlanner {
+    #[must_use]
+    pub fn new(api: Comboios, config: PlannerConfig) -> Self {
+        Self {
+            api,
+            config,
+            cache: Arc::default(),
+        }
+    }
+
+    /// A planner with other settings that shares this one's cache.
+    #[must_use]
+    pub fn with_config(&self, config: PlannerConfig) -> Self {
+        Self {
+            api: self.api.clone(),
+            config,
+            cache: self.cache.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &PlannerConfig {
+        &self.config
+    }
+
+    /// Plan journeys from `from` to `to` on `date`, leaving at or after
+    /// `after` (`HH:MM`, midnight when `None`). Stations are given by id or
+    /// name. See [`search`] for what is returned.
+    ///
+    /// Boards of interchanges and journeys that fail to load are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::InvalidInput`] if `date` or `after` are malformed
+    /// or a station cannot be resolved, and the board error if the origin's
+    /// departure board cannot be fetched.
+    pub async fn plan(
+        &self,
+        from: &str,
+        to: &str,
+        date: &str,
+        after: Option<&str>,
+    ) -> Result<Vec<Itinerary>, CoreError> {
+        let start = parse_service_start(date, after)?;
+        let service_date = start.date();
+        let from = self.api.resolve_station(from).await?;
+        let to = self.api.resolve_station(to).await?;
+
+        let index = self.api.station_index().await;
+        let mut stations = vec![from.clone()];
+        for entry in &self.config.interchanges {
+            match index.resolve(entry) {
+                Some(code)
+                    if !stations
+                        .iter()
+                        .any(|s| normalize_station_id(s) == normalize_station_id(&code))
+                        && normalize_station_id(&code) != normalize_station_id(&to) =>
+                {
+                    stations.push(code);
+                }
+                Some(_) => {}
+                None => tracing::debug!("Trip planner: unknown interchange {entry:?}, skipped"),
+            }
+        }
+
+        // Boards are fetched from the start of the hour, so plans made
+        // within the same hour share them.
+        let horizon = chrono::Duration::from_std(self.config.horizon).unwrap_or_default();
+        let end_of_day = service_date.and_time(NaiveTime::MIN) + chrono::Duration::days(1);
+        let end = (start + horizon).min(end_of_day);
+        let hour = start
+            .date()
+            .and_hms_opt(start.hour(), 0, 0)
+            .unwrap_or(start);
+        let window = (
+            hour,
+            (hour + chrono::Duration::hours(1) + horizon).min(end_of_day),
+        );
+
+        let boards: Vec<_> = stream::iter(stations.iter().cloned().enumerate())
+            .map(|(i, code)| async move { (i, self.departures(code, window).await) })
+            .buffer_unordered(self.config.concurrency)
+            .collect()
+            .await;
+
+        let mut departures = Vec::new();
+        for (i, board) in boards {
+            match board {
+                Ok(rows) => departures.extend(rows),
+                Err(e) if i == 0 => return Err(e),
+                Err(e) => tracing::warn!("Trip planner: board {} failed: {e}", stations[i]),
+            }
+        }
+        departures.retain(|(at, _)| (start..end).contains(at));
+        departures.sort_unstable();
+        let mut seen = HashSet::new();
+        let numbers: Vec<u64> = departures
+            .into_iter()
+            .filter_map(|(_, number)| seen.insert(number).then_some(number))
+            .take(self.config.max_journeys)
+            .collect();
+
+        let journeys: Vec<_> = stream::iter(numbers)
+            .map(|number| self.journey(number, service_date))
+            .buffer_unordered(self.config.concurrency)
+            .collect()
+            .await;
+
+        let connections: Vec<Connection> = journeys
+            .into_iter()
+            .filter_map(|journey| {
+                journey
+                    .inspect_err(|e| tracing::debug!("Trip planner: journey failed: {e}"))
+                    .ok()
+            })
+            .flat_map(|journey| Connection::from_journey(&journey, service_date))
+            .collect();
+
+        Ok(search(&connections, &from, &to, start, &self.config))
+    }
+
+    /// Departures from `station` over `window`, from the cache when fresh.
+    async fn departures(
+        &self,
+        station: String,
+        window: (NaiveDateTime, NaiveDateTime),
+    ) -> Result<Vec<(NaiveDateTime, u64)>, CoreError> {
+        let key = (station, window.0, window.1);
+        if let Some((at, rows)) = self.cache().boards.get(&key)
+            && at.elapsed() < CACHE_TTL
+        {
+            return Ok(rows.clone());
+        }
+
+        let rows: Vec<_> = self
+            .api
+            .dated_departures(&key.0, BoardWindow::new(window.0..window.1))
+            .await?
+            .into_iter()
+            .map(|(at, row)| (at, row.train_number))
+            .collect();
+        let mut cache = self.cache();
+        cache.evict_expired();
+        cache.boards.insert(key, (Instant::now(), rows.clone()));
+        Ok(rows)
+    }
+
+    /// The journey of `number` on `date`, from the cache when fresh.
+    async fn journey(&self, number: u64, date: NaiveDate) -> Result<TrainJourney, CoreError> {
+        if let Some((at, journey)) = self.cache().journeys.get(&(number, date))
+            && at.elapsed() < CACHE_TTL
+        {
+            return Ok(journey.clone());
+        }
+
+        let journey = self
+            .api
+            .get_train_journey(&number.to_string(), &date.format("%Y-%m-%d").to_string())
+            .await?;
+        let mut cache = self.cache();
+        cache.evict_expired();
+        cache
+            .journeys
+            .insert((number, date), (Instant::now(), journey.clone()));
+        Ok(journey)
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, PlanCache> {
+        self.cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::adapters::{CpAdapter, IpAdapter};
+
+    fn board_row(train: u64, departure: &str) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "trainService": {"code": "R", "designation": "Regional"},
+            "trainOrigin": {"code": "94-1", "designation": "A"},
+            "trainDestination": {"code": "94-3", "designation": "C"},
+            "departureTime": departure,
+        })
+    }
+
+    fn timetable(train: u64, stops: [(&str, &str); 2]) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "serviceCode": {"code": "R", "designation": "Regional"},
+            "trainStops": [
+                {"station": {"code": stops[0].0, "designation": "X"}, "departure": stops[0].1},
+                {"station": {"code": stops[1].0, "designation": "Y"}, "arrival": stops[1].1}
+            ],
+            "status": "SCHEDULED",
+            "messages": []
+        })
+    }
+
+    async fn mount_json(server: &MockServer, url: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn plan_changes_at_an_interchange() {
+        let server = MockServer::start().await;
+        let stations = "/services/travel-api/stations";
+        let trains = "/services/travel-api/trains";
+
+        mount_json(
+            &server,
+            &format!("{stations}/94-1/timetable/2026-03-14"),
+            serde_json::json!({"stationStops": [board_row(100, "10:00")], "messages": []}),
+        )
+        .await;
+        mount_json(
+            &server,
+            &format!("{stations}/94-2/timetable/2026-03-14"),
+            serde_json::json!({"stationStops": [board_row(200, "10:40")], "messages": []}),
+        )
+        .await;
+        mount_json(
+            &server,
+            &format!("{trains}/100/timetable/2026-03-14"),
+            timetable(100, [("94-1", "10:00"), ("94-2", "10:30")]),
+        )
+        .await;
+        mount_json(
+            &server,
+            &format!("{trains}/200/timetable/2026-03-14"),
+            timetable(200, [("94-2", "10:40"), ("94-3", "11:10")]),
+        )
+        .await;
+
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let api = Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()));
+        let planner = TripPlanner::new(api, PlannerConfig::new().interchanges(["94-2"]));
+
+        let trips = planner
+            .plan("94-1", "94-3", "2026-03-14", Some("09:00"))
+            .await
+            .unwrap();
+
+        assert_eq!(trips.len(), 1);
+        assert_eq!(trips[0].transfers, 1);
+        assert_eq!(trips[0].legs[1].train_number, "200");
+        assert_eq!(trips[0].duration_minutes(), 70);
+    }
+
+    #[tokio::test]
+    async fn plans_fetch_the_soonest_trains_once() {
+        let server = MockServer::start().await;
+        let stations = "/services/travel-api/stations";
+        let trains = "/services/travel-api/trains";
+
+        Mock::given(method("GET"))
+            .and(path(format!("{stations}/94-1/timetable/2026-03-14")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [board_row(100, "10:00"), board_row(300, "11:00")],
+                "messages": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{trains}/100/timetable/2026-03-14")))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(timetable(100, [("94-1", "10:00"), ("94-3", "10:30")])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{trains}/300/timetable/2026-03-14")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let api = Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()));
+        let planner = TripPlanner::new(
+            api,
+            PlannerConfig::new()
+                .interchanges(Vec::<String>::new())
+                .max_journeys(1),
+        );
+
+        for after in ["09:10", "09:40"] {
+            let trips = planner
+                .with_config(planner.config().clone())
+                .plan("94-1", "94-3", "2026-03-14", Some(after))
+                .await
+                .unwrap();
+            assert_eq!(trips[0].legs[0].train_number, "100");
+        }
+    }
+}
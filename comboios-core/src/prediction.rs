@@ -0,0 +1,159 @@
+//! Delay propagation and arrival-time prediction for upcoming stops.
+//!
+//! CP only reports an `eta` for stops the train has already reached, so a
+//! journey on its own says how late the train is *now* but not when it will
+//! reach the stops ahead. [`predict`] carries the latest known delay forward
+//! stop by stop, letting the train win some of it back where the timetable
+//! has slack:
+//!
+//! - **running slack**: a share of every scheduled run between two stops
+//!   ([`RECOVERY_PERCENT`]) can be made up by running at line speed;
+//! - **dwell slack**: a late train only needs [`MIN_DWELL_MINUTES`] at a
+//!   stop, so any longer scheduled dwell absorbs delay.
+//!
+//! Forecasts published by the source (IP's "Hora Prevista") are kept as they
+//! are and used as a fresh starting point for the stops after them.
+
+use crate::domain::journey::{
+    JourneyStatus, JourneyStop, PredictionConfidence, StopStatus, TrainJourney,
+};
+use crate::timeline::{Timeline, format_minutes, has_reached, minutes, wrap};
+
+/// Share of each scheduled running time, in percent, assumed to be
+/// recoverable by a late train.
+pub const RECOVERY_PERCENT: i32 = 5;
+
+/// Shortest stop a late train is assumed to make, in minutes.
+pub const MIN_DWELL_MINUTES: i32 = 1;
+
+/// Predictions up to this many minutes past the last observation are
+/// [`PredictionConfidence::High`].
+const HIGH_CONFIDENCE_HORIZON: i32 = 30;
+
+/// Predictions up to this many minutes past the last observation are
+/// [`PredictionConfidence::Medium`]; later ones are `Low`.
+const MEDIUM_CONFIDENCE_HORIZON: i32 = 90;
+
+/// How the delay the prediction starts from was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DelaySource {
+    /// Actual or forecast times reported for a stop.
+    Observed,
+    /// A delay figure without times (stop or journey `delay_minutes`).
+    Reported,
+    /// Nothing reported for a running train; assumed on time.
+    Assumed,
+}
+
+/// Fill [`JourneyStop::predicted_time`] for every stop the train has not yet
+/// reached, plus the journey's
+/// [`predicted_arrival`](TrainJourney::predicted_arrival) at its destination.
+///
+/// Journeys that are cancelled or completed are left untouched, as are trains
+/// that have not departed and carry no delay: their timetable is the best
+/// prediction available. Existing `predicted_time` values are never
+/// overwritten.
+pub fn predict(journey: &mut TrainJourney) {
+    journey.predicted_arrival = None;
+    journey.prediction_confidence = None;
+
+    if matches!(
+        journey.status,
+        JourneyStatus::Cancelled | JourneyStatus::Completed
+    ) || journey.stops.is_empty()
+    {
+        return;
+    }
+
+    let timeline = Timeline::new(&journey.stops);
+    let anchor = journey.stops.iter().rposition(has_reached);
+
+    let (first, mut delay, mut source, mut observed_at) = match anchor {
+        Some(i) => {
+            let (delay, source) = departure_delay(&journey.stops[i], &timeline, i)
+                .map(|d| (d, DelaySource::Observed))
+                .or_else(|| {
+                    journey.stops[i]
+                        .delay_minutes
+                        .or(journey.delay_minutes)
+                        .map(|d| (d, DelaySource::Reported))
+                })
+                .unwrap_or((0, DelaySource::Assumed));
+            (i + 1, delay, source, timeline.departure(i))
+        }
+        None => {
+            // Not yet departed: only worth predicting when held at origin.
+            let Some(delay) = journey.stops[0].delay_minutes.or(journey.delay_minutes) else {
+                return;
+            };
+            (0, delay, DelaySource::Reported, timeline.departure(0))
+        }
+    };
+
+    for j in first..journey.stops.len() {
+        if journey.stops[j].status == StopStatus::Cancelled {
+            continue;
+        }
+
+        let arrival_delay = if j == 0 {
+            delay.max(0)
+        } else {
+            let run = (timeline.arrival(j) - timeline.departure(j - 1)).max(0);
+            (delay - run * RECOVERY_PERCENT / 100).max(0)
+        };
+
+        let stop = &mut journey.stops[j];
+        let (arrival_delay, confidence) = match stop.predicted_time.as_deref().and_then(minutes) {
+            Some(forecast) => {
+                source = DelaySource::Observed;
+                observed_at = timeline.arrival(j);
+                (
+                    wrap(forecast - timeline.arrival(j)),
+                    PredictionConfidence::High,
+                )
+            }
+            None => {
+                let horizon = timeline.arrival(j) - observed_at;
+                stop.predicted_time = Some(format_minutes(timeline.arrival(j) + arrival_delay));
+                (arrival_delay, confidence(horizon, source))
+            }
+        };
+        stop.prediction_confidence = Some(confidence);
+
+        let dwell = timeline.departure(j) - timeline.arrival(j);
+        delay = (arrival_delay - (dwell - MIN_DWELL_MINUTES).max(0)).max(0);
+    }
+
+    if let Some(last) = journey.stops.last()
+        && !has_reached(last)
+    {
+        journey.predicted_arrival = last.predicted_time.clone();
+        journey.prediction_confidence = last.prediction_confidence;
+    }
+}
+
+/// Delay on leaving stop `i`, from its actual departure or, for a train still
+/// at the stop, its actual arrival less the dwell it can skip.
+fn departure_delay(stop: &JourneyStop, timeline: &Timeline, i: usize) -> Option<i32> {
+    if let Some(actual) = timeline.actual_departure(stop, i) {
+        return Some(actual - timeline.departure(i));
+    }
+    let arrival_delay = timeline.actual_arrival(stop, i)? - timeline.arrival(i);
+    let dwell = timeline.departure(i) - timeline.arrival(i);
+    Some((arrival_delay - (dwell - MIN_DWELL_MINUTES).max(0)).max(0))
+}
+
+fn confidence(horizon: i32, source: DelaySource) -> PredictionConfidence {
+    let by_horizon = if horizon <= HIGH_CONFIDENCE_HORIZON {
+        PredictionConfidence::High
+    } else if horizon <= MEDIUM_CONFIDENCE_HORIZON {
+        PredictionConfidence::Medium
+    } else {
+        PredictionConfidence::Low
+    };
+
+    match source {
+        DelaySource::Observed => by_horizon,
+        DelaySource::Reported | DelaySource::Assumed => by_horizon.lower(),
+    }
+}
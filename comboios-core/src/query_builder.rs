@@ -3,6 +3,14 @@
 //! These types offer a fluent interface for building queries before passing
 //! them to the [`crate::Comboios`] client methods.
 
+use std::ops::Range;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::normalize_station_id;
+use crate::domain::station_timetable::StationTimetable;
+
 /// Builder for station search queries.
 ///
 /// Construct a query string to pass to [`crate::Comboios::search_stations`].
@@ -57,6 +65,140 @@ impl StationQuery {
     }
 }
 
+/// Longest time span a single [`BoardWindow`] may cover.
+///
+/// Each service day in the window costs one upstream request, so the span is
+/// capped to keep board queries cheap.
+pub const MAX_BOARD_WINDOW_DAYS: i64 = 7;
+
+/// Time window for [`crate::Comboios::departures`] and
+/// [`crate::Comboios::arrivals`].
+///
+/// Times are Portugal-local (`Europe/Lisbon`) wall-clock times, matching the CP
+/// API. The window is half-open: a train scheduled exactly at `end` is not
+/// included. Windows that cross midnight are served by querying every service
+/// day they touch.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use comboios_core::query_builder::BoardWindow;
+///
+/// let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+/// let start = day.and_hms_opt(22, 0, 0).unwrap();
+/// let end = day.succ_opt().unwrap().and_hms_opt(2, 0, 0).unwrap();
+///
+/// let window = BoardWindow::new(start..end).max_results(10);
+/// assert_eq!(window.service_dates().len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardWindow {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    max_results: Option<usize>,
+}
+
+impl BoardWindow {
+    /// Create a window covering `range` with no result limit.
+    #[must_use]
+    pub fn new(range: Range<NaiveDateTime>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+            max_results: None,
+        }
+    }
+
+    /// Create a window covering the whole of `date`, from `00:00` up to (but
+    /// excluding) `00:00` on the following day.
+    #[must_use]
+    pub fn for_date(date: NaiveDate) -> Self {
+        let start = date.and_time(NaiveTime::MIN);
+        Self::new(start..start + chrono::Duration::days(1))
+    }
+
+    /// Return at most `max_results` trains, earliest first.
+    #[must_use]
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Inclusive start of the window.
+    #[must_use]
+    pub fn start(&self) -> NaiveDateTime {
+        self.start
+    }
+
+    /// Exclusive end of the window.
+    #[must_use]
+    pub fn end(&self) -> NaiveDateTime {
+        self.end
+    }
+
+    /// Return the configured result limit, if any.
+    #[must_use]
+    pub fn get_max_results(&self) -> Option<usize> {
+        self.max_results
+    }
+
+    /// Returns `true` if `time` falls inside `[start, end)`.
+    #[must_use]
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// Every calendar date the window touches, in order. One upstream board
+    /// request is issued per date.
+    #[must_use]
+    pub fn service_dates(&self) -> Vec<NaiveDate> {
+        if self.end <= self.start {
+            return Vec::new();
+        }
+
+        // The end is exclusive, so a window ending exactly at midnight does
+        // not need the following day's board.
+        let last = if self.end.time() == NaiveTime::MIN {
+            self.end.date().pred_opt().unwrap_or(self.end.date())
+        } else {
+            self.end.date()
+        };
+
+        self.start
+            .date()
+            .iter_days()
+            .take_while(|d| *d <= last)
+            .collect()
+    }
+
+    /// Check that the window is non-empty and no longer than
+    /// [`MAX_BOARD_WINDOW_DAYS`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidInput`] describing the problem.
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        if self.end <= self.start {
+            return Err(crate::Error::InvalidInput(
+                "board window end must be after its start".to_string(),
+            ));
+        }
+        if self.end - self.start > chrono::Duration::days(MAX_BOARD_WINDOW_DAYS) {
+            return Err(crate::Error::InvalidInput(format!(
+                "board window must not exceed {MAX_BOARD_WINDOW_DAYS} days"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl From<Range<NaiveDateTime>> for BoardWindow {
+    fn from(range: Range<NaiveDateTime>) -> Self {
+        Self::new(range)
+    }
+}
+
 /// Filter for [`crate::domain::station_timetable::TrainEntry`] results returned
 /// by the CP timetable API.
 ///
@@ -142,6 +284,221 @@ impl TrainEntryFilter {
     }
 }
 
+/// Status restriction applied by [`BoardFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardStatus {
+    /// Only trains running with a positive delay.
+    Delayed,
+    /// Only trains CP has flagged as suppressed.
+    Cancelled,
+}
+
+/// Filter for [`StationTimetable`] rows on a station board.
+///
+/// Pass it to [`crate::Comboios::get_station_timetable_filtered`] or call
+/// [`BoardFilter::matches`] directly. All conditions are combined with AND
+/// semantics and text comparisons are case-insensitive.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::NaiveTime;
+/// use comboios_core::query_builder::BoardFilter;
+///
+/// let filter = BoardFilter::new()
+///     .service_type("IC")
+///     .destination("Porto")
+///     .delayed_only()
+///     .time_range(
+///         NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+///         NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+///     );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BoardFilter {
+    service_type: Option<String>,
+    origin: Option<String>,
+    destination: Option<String>,
+    platform: Option<String>,
+    operator: Option<String>,
+    status: Option<BoardStatus>,
+    min_delay: Option<i32>,
+    time_range: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl BoardFilter {
+    /// Create a new, empty filter that matches every row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict matches to rows whose service code (`"IC"`) or designation
+    /// (`"Intercidades"`) equals `service_type`.
+    #[must_use]
+    pub fn service_type(mut self, service_type: impl Into<String>) -> Self {
+        self.service_type = Some(service_type.into());
+        self
+    }
+
+    /// Restrict matches to trains starting at `station`, given either as a
+    /// station id (`"94-31039"` or `"9431039"`) or as part of its name.
+    #[must_use]
+    pub fn origin(mut self, station: impl Into<String>) -> Self {
+        self.origin = Some(station.into());
+        self
+    }
+
+    /// Restrict matches to trains terminating at `station`, given either as a
+    /// station id or as part of its name.
+    #[must_use]
+    pub fn destination(mut self, station: impl Into<String>) -> Self {
+        self.destination = Some(station.into());
+        self
+    }
+
+    /// Restrict matches to rows assigned to `platform`.
+    #[must_use]
+    pub fn platform(mut self, platform: impl Into<String>) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    /// Restrict matches to trains run by `operator` (e.g. `"CP"`).
+    #[must_use]
+    pub fn operator(mut self, operator: impl Into<String>) -> Self {
+        self.operator = Some(operator.into());
+        self
+    }
+
+    /// Restrict matches to rows in the given [`BoardStatus`].
+    #[must_use]
+    pub fn status(mut self, status: BoardStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only match trains running with a positive delay.
+    #[must_use]
+    pub fn delayed_only(self) -> Self {
+        self.status(BoardStatus::Delayed)
+    }
+
+    /// Only match trains flagged as suppressed.
+    #[must_use]
+    pub fn cancelled_only(self) -> Self {
+        self.status(BoardStatus::Cancelled)
+    }
+
+    /// Only match trains delayed by at least `minutes`.
+    #[must_use]
+    pub fn min_delay(mut self, minutes: i32) -> Self {
+        self.min_delay = Some(minutes);
+        self
+    }
+
+    /// Only match rows whose scheduled board time is within `start..=end`.
+    ///
+    /// When `start` is later than `end` the range wraps around midnight
+    /// (e.g. `23:00..=01:00`).
+    #[must_use]
+    pub fn time_range(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+        self.time_range = Some((start, end));
+        self
+    }
+
+    /// Returns `true` if `row` satisfies every condition set on this filter.
+    ///
+    /// An empty filter matches every row. Rows without a parseable board time
+    /// never match a [`time_range`](Self::time_range).
+    #[must_use]
+    pub fn matches(&self, row: &StationTimetable) -> bool {
+        if let Some(ref service) = self.service_type {
+            let designation = row.service_type.split_once('|').map_or("", |(_, d)| d);
+            if !row.service_code().eq_ignore_ascii_case(service)
+                && !designation.eq_ignore_ascii_case(service)
+            {
+                return false;
+            }
+        }
+
+        if let Some(ref origin) = self.origin
+            && !station_matches(origin, &row.origin_station_id, &row.origin_station_name)
+        {
+            return false;
+        }
+
+        if let Some(ref dest) = self.destination
+            && !station_matches(
+                dest,
+                &row.destination_station_id,
+                &row.destination_station_name,
+            )
+        {
+            return false;
+        }
+
+        if let Some(ref platform) = self.platform
+            && !row
+                .platform
+                .as_deref()
+                .is_some_and(|p| p.trim().eq_ignore_ascii_case(platform.trim()))
+        {
+            return false;
+        }
+
+        if let Some(ref operator) = self.operator
+            && !row.operator.eq_ignore_ascii_case(operator)
+        {
+            return false;
+        }
+
+        match self.status {
+            Some(BoardStatus::Delayed) if !row.is_delayed() => return false,
+            Some(BoardStatus::Cancelled) if !row.is_cancelled() => return false,
+            _ => {}
+        }
+
+        if let Some(min) = self.min_delay
+            && row.delay.unwrap_or(0) < min
+        {
+            return false;
+        }
+
+        if let Some((start, end)) = self.time_range {
+            let Some(time) = row
+                .board_time()
+                .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+            else {
+                return false;
+            };
+            let inside = if start <= end {
+                start <= time && time <= end
+            } else {
+                time >= start || time <= end
+            };
+            if !inside {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Match a station given as an id (with or without the CP dash) or as a
+/// case-insensitive substring of its name.
+fn station_matches(wanted: &str, id: &str, name: &str) -> bool {
+    let wanted_id = normalize_station_id(wanted);
+    if !wanted_id.is_empty()
+        && wanted_id.chars().all(|c| c.is_ascii_digit())
+        && wanted_id == normalize_station_id(id)
+    {
+        return true;
+    }
+    name.to_uppercase().contains(&wanted.to_uppercase())
+}
+
 /// Deprecated: Old filter for Timetable struct
 #[deprecated(since = "0.2.0", note = "Use TrainEntryFilter instead")]
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,222 @@
+//! Live risk assessment of the changes of train in an [`Itinerary`].
+//!
+//! Each leg's train is looked up live and its expected arrival at the change
+//! station is compared with the expected departure of the next train. What
+//! is left after the minimum transfer time is the slack; a negative slack
+//! means the connection will be missed.
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::normalize_station_id;
+use crate::domain::journey::{
+    JourneyStatus, JourneyStop, PredictionConfidence, StopStatus, TrainJourney,
+};
+use crate::domain::station::Station;
+use crate::domain::trip::DirectTrain;
+use crate::planner::{Itinerary, PlannerConfig, TripLeg};
+use crate::timeline::datetime_near;
+
+/// Slack below which a connection is reported [`ConnectionRisk::AtRisk`].
+/// Doubled when the arrival is only a low-confidence prediction.
+pub const AT_RISK_SLACK_MINUTES: i64 = 5;
+
+/// Alternatives suggested for a missed connection.
+pub const MAX_ALTERNATIVES: usize = 3;
+
+/// How likely a change of train is to work out.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConnectionRisk {
+    /// Enough slack to make the change.
+    Safe,
+    /// Little slack left; the change may be missed.
+    AtRisk,
+    /// The arriving train gets in too late, or either train is cancelled.
+    Missed,
+}
+
+/// Live view of one change of train.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferAssessment {
+    /// Where the change happens.
+    pub station: Station,
+    pub arriving_train: String,
+    pub departing_train: String,
+    pub scheduled_arrival: NaiveDateTime,
+    pub scheduled_departure: NaiveDateTime,
+    /// Arrival of the incoming train: actual, predicted, or scheduled plus
+    /// its current delay.
+    pub expected_arrival: NaiveDateTime,
+    /// Departure of the onward train, never earlier than scheduled.
+    pub expected_departure: NaiveDateTime,
+    pub min_transfer_minutes: i64,
+    /// Minutes to spare once the minimum transfer time is allowed for;
+    /// negative when the connection cannot be made.
+    pub slack_minutes: i64,
+    pub risk: ConnectionRisk,
+    /// Later direct trains from the change station to the end of the onward
+    /// leg; only filled for missed connections.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<DirectTrain>,
+}
+
+/// Live view of every change in an itinerary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItineraryAssessment {
+    /// One entry per change of train, in order.
+    pub transfers: Vec<TransferAssessment>,
+    /// Expected arrival at the final destination.
+    pub expected_arrival: NaiveDateTime,
+    /// The worst risk among the changes (`SAFE` without changes).
+    pub risk: ConnectionRisk,
+}
+
+/// Expected times of one leg, from its live journey when known.
+#[derive(Debug, Clone, Copy)]
+struct LegTimes {
+    departure: NaiveDateTime,
+    arrival: NaiveDateTime,
+    arrival_confidence: Option<PredictionConfidence>,
+    cancelled: bool,
+}
+
+impl LegTimes {
+    fn estimate(leg: &TripLeg, journey: Option<&TrainJourney>) -> Self {
+        let scheduled = Self {
+            departure: leg.departure,
+            arrival: leg.arrival,
+            arrival_confidence: None,
+            cancelled: false,
+        };
+        let Some(journey) = journey else {
+            return scheduled;
+        };
+
+        let find = |station: &Station| {
+            let code = normalize_station_id(&station.code);
+            journey
+                .stops
+                .iter()
+                .find(|s| normalize_station_id(&s.station.code) == code)
+        };
+        let (Some(boards), Some(alights)) = (find(&leg.from), find(&leg.to)) else {
+            return scheduled;
+        };
+
+        let delay = Duration::minutes(journey.delay_minutes.unwrap_or(0).into());
+        let cancelled = journey.status == JourneyStatus::Cancelled
+            || boards.status == StopStatus::Cancelled
+            || alights.status == StopStatus::Cancelled;
+
+        let departure = boards
+            .actual_departure
+            .as_deref()
+            .or(boards.predicted_time.as_deref())
+            .and_then(|t| datetime_near(leg.departure, t))
+            .unwrap_or(leg.departure + delay)
+            .max(leg.departure);
+        let (arrival, arrival_confidence) = expected_arrival(leg.arrival, alights, delay);
+
+        Self {
+            departure,
+            arrival,
+            arrival_confidence,
+            cancelled,
+        }
+    }
+}
+
+/// Arrival at `stop`: the actual time, then the prediction (with its
+/// confidence), then the schedule shifted by the train's delay.
+fn expected_arrival(
+    scheduled: NaiveDateTime,
+    stop: &JourneyStop,
+    delay: Duration,
+) -> (NaiveDateTime, Option<PredictionConfidence>) {
+    if let Some(actual) = stop
+        .actual_arrival
+        .as_deref()
+        .and_then(|t| datetime_near(scheduled, t))
+    {
+        return (actual, Some(PredictionConfidence::High));
+    }
+    if let Some(predicted) = stop
+        .predicted_time
+        .as_deref()
+        .and_then(|t| datetime_near(scheduled, t))
+    {
+        return (predicted, stop.prediction_confidence);
+    }
+    (scheduled + delay, None)
+}
+
+/// Assess the changes in `itinerary` against the live journeys of its legs.
+///
+/// `journeys` holds the journey of each leg's train, in leg order; `None`
+/// (or a journey not calling at the leg's stations) means the schedule is
+/// assumed. Minimum transfer times come from `config`. Alternatives are not
+/// looked up here; see [`crate::Comboios::assess_connections`].
+#[must_use]
+pub fn assess(
+    itinerary: &Itinerary,
+    journeys: &[Option<TrainJourney>],
+    config: &PlannerConfig,
+) -> ItineraryAssessment {
+    let times: Vec<LegTimes> = itinerary
+        .legs
+        .iter()
+        .enumerate()
+        .map(|(i, leg)| LegTimes::estimate(leg, journeys.get(i).and_then(Option::as_ref)))
+        .collect();
+
+    let transfers: Vec<TransferAssessment> = itinerary
+        .legs
+        .windows(2)
+        .zip(times.windows(2))
+        .map(|(legs, times)| {
+            let (arriving, departing) = (&legs[0], &legs[1]);
+            let min_transfer =
+                i64::try_from(config.get_transfer_time(&departing.from.code).as_secs() / 60)
+                    .unwrap_or(i64::MAX);
+            let slack = (times[1].departure - times[0].arrival).num_minutes() - min_transfer;
+
+            let margin = if times[0].arrival_confidence == Some(PredictionConfidence::Low) {
+                2 * AT_RISK_SLACK_MINUTES
+            } else {
+                AT_RISK_SLACK_MINUTES
+            };
+            let risk = if slack < 0 || times[0].cancelled || times[1].cancelled {
+                ConnectionRisk::Missed
+            } else if slack < margin {
+                ConnectionRisk::AtRisk
+            } else {
+                ConnectionRisk::Safe
+            };
+
+            TransferAssessment {
+                station: departing.from.clone(),
+                arriving_train: arriving.train_number.clone(),
+                departing_train: departing.train_number.clone(),
+                scheduled_arrival: arriving.arrival,
+                scheduled_departure: departing.departure,
+                expected_arrival: times[0].arrival,
+                expected_departure: times[1].departure,
+                min_transfer_minutes: min_transfer,
+                slack_minutes: slack,
+                risk,
+                alternatives: Vec::new(),
+            }
+        })
+        .collect();
+
+    ItineraryAssessment {
+        expected_arrival: times.last().map_or(itinerary.arrival, |t| t.arrival),
+        risk: transfers
+            .iter()
+            .map(|t| t.risk)
+            .max()
+            .unwrap_or(ConnectionRisk::Safe),
+        transfers,
+    }
+}
@@ -0,0 +1,143 @@
+//! Scheduled stop times of a journey laid out on a single minute axis.
+//!
+//! Journey stops carry bare `HH:MM` strings. [`Timeline`] places them in
+//! minutes since midnight of the service day, rolling over at midnight, so
+//! durations and delays can be computed with plain subtraction.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::domain::journey::{JourneyStop, StopStatus};
+
+pub(crate) const MINUTES_PER_DAY: i32 = 24 * 60;
+
+/// Scheduled arrival and departure of every stop in minutes since midnight of
+/// the service day, increasing monotonically across midnight.
+pub(crate) struct Timeline {
+    arrivals: Vec<i32>,
+    departures: Vec<i32>,
+}
+
+impl Timeline {
+    pub(crate) fn new(stops: &[JourneyStop]) -> Self {
+        let mut arrivals = Vec::with_capacity(stops.len());
+        let mut departures = Vec::with_capacity(stops.len());
+        let mut day = 0;
+        let mut previous = 0;
+
+        let mut place = |time: &str, fallback: i32| {
+            let Some(mut t) = minutes(time) else {
+                return fallback;
+            };
+            t += day;
+            if t < previous {
+                day += MINUTES_PER_DAY;
+                t += MINUTES_PER_DAY;
+            }
+            previous = t;
+            t
+        };
+
+        for stop in stops {
+            let fallback = departures.last().copied().unwrap_or(0);
+            let arrival = place(&stop.scheduled_arrival, fallback);
+            let departure = place(&stop.scheduled_departure, arrival);
+            arrivals.push(arrival);
+            departures.push(departure);
+        }
+
+        Self {
+            arrivals,
+            departures,
+        }
+    }
+
+    pub(crate) fn arrival(&self, i: usize) -> i32 {
+        self.arrivals[i]
+    }
+
+    pub(crate) fn departure(&self, i: usize) -> i32 {
+        self.departures[i]
+    }
+
+    /// Actual arrival at stop `i`, placed on the same axis as its schedule.
+    pub(crate) fn actual_arrival(&self, stop: &JourneyStop, i: usize) -> Option<i32> {
+        place_near(self.arrival(i), stop.actual_arrival.as_deref()?)
+    }
+
+    /// Actual departure from stop `i`, placed on the same axis as its schedule.
+    pub(crate) fn actual_departure(&self, stop: &JourneyStop, i: usize) -> Option<i32> {
+        place_near(self.departure(i), stop.actual_departure.as_deref()?)
+    }
+}
+
+/// Whether the train has reached `stop`: it is there now, or has left or
+/// passed it.
+pub(crate) fn has_reached(stop: &JourneyStop) -> bool {
+    stop.has_passed == Some(true)
+        || matches!(
+            stop.status,
+            StopStatus::AtStop | StopStatus::Departed | StopStatus::Passed
+        )
+}
+
+/// Dates a run of clock times, such as the stops of a journey or the rows of
+/// a board, on a service day. A time more than twelve hours earlier than the
+/// one before it has passed midnight and moves to the next day; a smaller
+/// step back (rows slightly out of order) stays on the same day.
+pub(crate) struct ServiceDay {
+    day: NaiveDate,
+    previous: Option<NaiveTime>,
+}
+
+impl ServiceDay {
+    pub(crate) fn new(date: NaiveDate) -> Self {
+        Self {
+            day: date,
+            previous: None,
+        }
+    }
+
+    /// Date `time`, the next in the run.
+    pub(crate) fn place(&mut self, time: NaiveTime) -> NaiveDateTime {
+        if let Some(previous) = self.previous
+            && previous - time > chrono::Duration::hours(12)
+        {
+            self.day = self.day.succ_opt().unwrap_or(self.day);
+        }
+        self.previous = Some(time);
+        self.day.and_time(time)
+    }
+}
+
+/// Place an `HH:MM` time within twelve hours of `scheduled`.
+fn place_near(scheduled: i32, time: &str) -> Option<i32> {
+    Some(scheduled + wrap(minutes(time)? - scheduled))
+}
+
+/// Place an `HH:MM` time on the calendar within twelve hours of `scheduled`.
+pub(crate) fn datetime_near(scheduled: NaiveDateTime, time: &str) -> Option<NaiveDateTime> {
+    let midnight = scheduled.date().and_time(NaiveTime::MIN);
+    let at = i32::try_from((scheduled - midnight).num_minutes()).ok()?;
+    let shift = wrap(minutes(time)? - at);
+    Some(scheduled + chrono::Duration::minutes(shift.into()))
+}
+
+/// Parse `HH:MM` (seconds ignored) into minutes since midnight.
+pub(crate) fn minutes(time: &str) -> Option<i32> {
+    let mut parts = time.split(':');
+    let hours: i32 = parts.next()?.trim().parse().ok()?;
+    let mins: i32 = parts.next()?.trim().parse().ok()?;
+    ((0..24).contains(&hours) && (0..60).contains(&mins)).then_some(hours * 60 + mins)
+}
+
+/// Bring a difference between two clock times into `-12h..12h`, so a time
+/// just past midnight compared with one just before it reads as minutes late
+/// rather than a day early. Either operand may already include whole days.
+pub(crate) fn wrap(diff: i32) -> i32 {
+    (diff + MINUTES_PER_DAY / 2).rem_euclid(MINUTES_PER_DAY) - MINUTES_PER_DAY / 2
+}
+
+pub(crate) fn format_minutes(total: i32) -> String {
+    let total = total.rem_euclid(MINUTES_PER_DAY);
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
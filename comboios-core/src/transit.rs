@@ -0,0 +1,738 @@
+//! Onward connections on other operators' GTFS feeds.
+//!
+//! Many trips start or end on the metro or on Fertagus rather than on CP.
+//! [`TransitFeed`] loads a GTFS static feed (Metro de Lisboa, Metro do Porto,
+//! Fertagus, ...) from a local zip into memory, and [`TransitTimetable`]
+//! links its stops to CP stations: stops within [`LINK_RADIUS_METERS`] of a
+//! station, or within [`NAME_LINK_RADIUS_METERS`] when their names match.
+//! [`TransitTimetable::connections`] then answers "what leaves after the
+//! train gets to Oriente" for any stop of a [`TrainJourney`].
+//!
+//! Everything works offline; feeds are read from files and never fetched.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use comboios_core::Comboios;
+//! use comboios_core::transit::{TransitFeed, TransitTimetable};
+//!
+//! # async fn run(client: Comboios) -> Result<(), comboios_core::Error> {
+//! let metro = TransitFeed::open("gtfs/metro-lisboa.zip")?;
+//! let timetable = TransitTimetable::new(vec![metro], &client.station_index().await);
+//!
+//! let today = client.clock().today();
+//! let journey = client.get_train_journey("520", &client.today()).await?;
+//! for stop in 0..journey.stops.len() {
+//!     for connection in timetable.connections(&journey, stop, today) {
+//!         let departure = &connection.departure;
+//!         println!("{} {} to {}", departure.departure, departure.route, departure.headsign);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::normalize_station_id;
+use crate::domain::journey::TrainJourney;
+use crate::domain::position::Coordinates;
+use crate::domain::station::{StationIndex, normalize_name};
+use crate::error::CoreError;
+use crate::gtfs::parse_gtfs_time;
+use crate::timeline::{Timeline, datetime_near, minutes};
+
+/// Stops this close to a CP station are linked to it whatever their name.
+pub const LINK_RADIUS_METERS: f64 = 250.0;
+
+/// Stops this close to a CP station are linked to it when their names match,
+/// e.g. the metro's `"Oriente"` and CP's `"Lisboa - Oriente"`.
+pub const NAME_LINK_RADIUS_METERS: f64 = 1000.0;
+
+/// Walking pace used to time the change from the train.
+pub const WALKING_METERS_PER_MINUTE: f64 = 80.0;
+
+/// Shortest change from a train to a linked stop.
+pub const MIN_TRANSFER_MINUTES: i64 = 3;
+
+/// How far past the earliest possible departure connections are looked for.
+pub const CONNECTION_HORIZON_MINUTES: i64 = 60;
+
+/// Names shorter than this must match exactly, not as part of a longer name.
+const MIN_PARTIAL_NAME_LEN: usize = 4;
+
+/// A stop of a [`TransitFeed`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitStop {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<Coordinates>,
+}
+
+/// A departure from a [`TransitFeed`] stop.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitDeparture {
+    /// The feed's agency, e.g. `"Metro de Lisboa"`.
+    pub agency: String,
+    /// Route short name, or long name when it has none (e.g. `"Vermelha"`).
+    pub route: String,
+    /// GTFS `route_type`: `1` for metro, `2` for rail, `3` for bus, ...
+    pub route_type: u16,
+    pub headsign: String,
+    pub stop_id: String,
+    pub stop_name: String,
+    /// Local date and time of the departure.
+    pub departure: NaiveDateTime,
+}
+
+/// A departure reachable from a train: the next one of each route and
+/// direction at the stops linked to the station.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    #[serde(flatten)]
+    pub departure: TransitDeparture,
+    /// Minutes allowed to walk from the train to the stop.
+    pub walk_minutes: i64,
+}
+
+/// A link between a CP station and a stop of one of the feeds of a
+/// [`TransitTimetable`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StopLink {
+    /// Index of the feed in the timetable.
+    pub feed: usize,
+    pub stop_id: String,
+    /// Straight-line distance from the station.
+    pub distance_meters: f64,
+    /// Whether the names matched, as opposed to proximity alone.
+    pub by_name: bool,
+}
+
+/// A GTFS static feed loaded into memory, indexed by stop.
+#[derive(Debug, Clone, Default)]
+pub struct TransitFeed {
+    agency: String,
+    stops: Vec<TransitStop>,
+    stop_index: HashMap<String, usize>,
+    routes: HashMap<String, RouteInfo>,
+    trips: Vec<TripInfo>,
+    /// Departures of every stop, by stop index.
+    departures: HashMap<usize, Vec<StopDeparture>>,
+    calendar: HashMap<String, ServiceCalendar>,
+    exceptions: HashMap<(String, NaiveDate), bool>,
+}
+
+#[derive(Debug, Clone)]
+struct RouteInfo {
+    name: String,
+    route_type: u16,
+}
+
+#[derive(Debug, Clone)]
+struct TripInfo {
+    route_id: String,
+    service_id: String,
+    headsign: String,
+    /// `frequencies.txt` windows the trip is run in; empty for trips run
+    /// once, at their listed times.
+    frequencies: Vec<Frequency>,
+    /// Listed time of the first departure, which frequency-based trips are
+    /// timed from.
+    first_departure: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frequency {
+    start: u32,
+    end: u32,
+    headway: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StopDeparture {
+    trip: usize,
+    /// Seconds since the start of the service day; may pass 24 hours.
+    time: u32,
+}
+
+#[derive(Debug, Clone)]
+struct ServiceCalendar {
+    /// Monday first.
+    days: [bool; 7],
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl TransitFeed {
+    /// Load the GTFS zip at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoreError::GtfsError`] if the file cannot be read or a
+    /// required table (`stops.txt`, `routes.txt`, `trips.txt`,
+    /// `stop_times.txt`) is missing or malformed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| CoreError::GtfsError(format!("opening {}: {e}", path.display())))?;
+        let mut feed = Self::from_zip(BufReader::new(file))?;
+        if feed.agency.is_empty() {
+            feed.agency = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(feed)
+    }
+
+    /// Load a GTFS zip from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`open`](Self::open).
+    pub fn from_zip<R: Read + Seek>(reader: R) -> Result<Self, CoreError> {
+        let mut archive = zip::ZipArchive::new(reader)
+            .map_err(|e| CoreError::GtfsError(format!("reading zip: {e}")))?;
+
+        let agencies: Vec<AgencyRow> = read_table(&mut archive, "agency.txt", false)?;
+        let stops: Vec<StopRow> = read_table(&mut archive, "stops.txt", true)?;
+        let routes: Vec<RouteRow> = read_table(&mut archive, "routes.txt", true)?;
+        let trips: Vec<TripRow> = read_table(&mut archive, "trips.txt", true)?;
+        let stop_times: Vec<StopTimeRow> = read_table(&mut archive, "stop_times.txt", true)?;
+        let calendar: Vec<CalendarRow> = read_table(&mut archive, "calendar.txt", false)?;
+        let calendar_dates: Vec<CalendarDateRow> =
+            read_table(&mut archive, "calendar_dates.txt", false)?;
+        let frequencies: Vec<FrequencyRow> = read_table(&mut archive, "frequencies.txt", false)?;
+
+        let mut feed = Self {
+            agency: agencies
+                .into_iter()
+                .next()
+                .map(|a| a.agency_name)
+                .unwrap_or_default(),
+            ..Self::default()
+        };
+
+        for stop in stops {
+            let coordinates = stop
+                .stop_lat
+                .zip(stop.stop_lon)
+                .map(|(lat, lon)| Coordinates::new(lat, lon));
+            feed.stop_index
+                .insert(stop.stop_id.clone(), feed.stops.len());
+            feed.stops.push(TransitStop {
+                id: stop.stop_id,
+                name: stop.stop_name,
+                coordinates,
+            });
+        }
+
+        for route in routes {
+            let name = if route.route_short_name.is_empty() {
+                route.route_long_name
+            } else {
+                route.route_short_name
+            };
+            feed.routes.insert(
+                route.route_id,
+                RouteInfo {
+                    name,
+                    route_type: route.route_type,
+                },
+            );
+        }
+
+        let mut trip_index = HashMap::with_capacity(trips.len());
+        for trip in trips {
+            trip_index.insert(trip.trip_id, feed.trips.len());
+            feed.trips.push(TripInfo {
+                route_id: trip.route_id,
+                service_id: trip.service_id,
+                headsign: trip.trip_headsign,
+                frequencies: Vec::new(),
+                first_departure: u32::MAX,
+            });
+        }
+
+        // A trip's last stop is where it terminates, not a departure.
+        let mut last_stops: HashMap<String, u32> = HashMap::new();
+        for row in &stop_times {
+            let last = last_stops.entry(row.trip_id.clone()).or_default();
+            *last = (*last).max(row.stop_sequence);
+        }
+
+        for row in stop_times {
+            if last_stops.get(&row.trip_id) == Some(&row.stop_sequence) {
+                continue;
+            }
+            let (Some(&trip), Some(&stop)) = (
+                trip_index.get(&row.trip_id),
+                feed.stop_index.get(&row.stop_id),
+            ) else {
+                continue;
+            };
+            let Some(time) =
+                parse_gtfs_time(&row.departure_time).or_else(|| parse_gtfs_time(&row.arrival_time))
+            else {
+                continue;
+            };
+            let first = &mut feed.trips[trip].first_departure;
+            *first = (*first).min(time);
+            feed.departures
+                .entry(stop)
+                .or_default()
+                .push(StopDeparture { trip, time });
+        }
+        for departures in feed.departures.values_mut() {
+            departures.sort_by_key(|d| d.time);
+        }
+
+        for row in frequencies {
+            let (Some(&trip), Some(start), Some(end)) = (
+                trip_index.get(&row.trip_id),
+                parse_gtfs_time(&row.start_time),
+                parse_gtfs_time(&row.end_time),
+            ) else {
+                continue;
+            };
+            if row.headway_secs > 0 {
+                feed.trips[trip].frequencies.push(Frequency {
+                    start,
+                    end,
+                    headway: row.headway_secs,
+                });
+            }
+        }
+
+        for row in calendar {
+            let (Some(start), Some(end)) = (parse_date(&row.start_date), parse_date(&row.end_date))
+            else {
+                continue;
+            };
+            let days = [
+                row.monday,
+                row.tuesday,
+                row.wednesday,
+                row.thursday,
+                row.friday,
+                row.saturday,
+                row.sunday,
+            ]
+            .map(|d| d == 1);
+            feed.calendar
+                .insert(row.service_id, ServiceCalendar { days, start, end });
+        }
+        for row in calendar_dates {
+            if let Some(date) = parse_date(&row.date) {
+                feed.exceptions
+                    .insert((row.service_id, date), row.exception_type == 1);
+            }
+        }
+
+        Ok(feed)
+    }
+
+    /// The feed's agency name, or the file name when it has no `agency.txt`.
+    #[must_use]
+    pub fn agency(&self) -> &str {
+        &self.agency
+    }
+
+    /// Every stop of the feed.
+    #[must_use]
+    pub fn stops(&self) -> &[TransitStop] {
+        &self.stops
+    }
+
+    /// Look up a stop by its GTFS id.
+    #[must_use]
+    pub fn stop(&self, stop_id: &str) -> Option<&TransitStop> {
+        self.stop_index.get(stop_id).map(|&i| &self.stops[i])
+    }
+
+    /// Departures from `stop_id` at or after `from` and before `until`,
+    /// earliest first.
+    #[must_use]
+    pub fn departures(
+        &self,
+        stop_id: &str,
+        from: NaiveDateTime,
+        until: NaiveDateTime,
+    ) -> Vec<TransitDeparture> {
+        let Some(&stop) = self.stop_index.get(stop_id) else {
+            return Vec::new();
+        };
+        let Some(departures) = self.departures.get(&stop) else {
+            return Vec::new();
+        };
+
+        let mut found = Vec::new();
+        // Trips of the previous service day can run past midnight.
+        let mut day = from.date() - Duration::days(1);
+        while day <= until.date() {
+            let midnight = day.and_time(NaiveTime::MIN);
+            for departure in departures {
+                let trip = &self.trips[departure.trip];
+                if !self.runs(&trip.service_id, day) {
+                    continue;
+                }
+                for time in trip_times(trip, departure.time) {
+                    let at = midnight + Duration::seconds(time.into());
+                    if at >= from && at < until {
+                        found.push(self.departure(stop, trip, at));
+                    }
+                }
+            }
+            day += Duration::days(1);
+        }
+
+        found.sort_by_key(|d| d.departure);
+        found
+    }
+
+    fn departure(&self, stop: usize, trip: &TripInfo, at: NaiveDateTime) -> TransitDeparture {
+        let route = self.routes.get(&trip.route_id);
+        TransitDeparture {
+            agency: self.agency.clone(),
+            route: route.map(|r| r.name.clone()).unwrap_or_default(),
+            route_type: route.map_or(0, |r| r.route_type),
+            headsign: trip.headsign.clone(),
+            stop_id: self.stops[stop].id.clone(),
+            stop_name: self.stops[stop].name.clone(),
+            departure: at,
+        }
+    }
+
+    /// Whether `service_id` runs on `date`: `calendar_dates.txt` first, then
+    /// the weekly `calendar.txt` pattern.
+    fn runs(&self, service_id: &str, date: NaiveDate) -> bool {
+        if let Some(&added) = self.exceptions.get(&(service_id.to_string(), date)) {
+            return added;
+        }
+        self.calendar.get(service_id).is_some_and(|c| {
+            (c.start..=c.end).contains(&date)
+                && c.days[date.weekday().num_days_from_monday() as usize]
+        })
+    }
+}
+
+/// Times a trip leaves a stop it reaches `time` seconds into the day:
+/// once, or once per vehicle of each `frequencies.txt` window.
+fn trip_times(trip: &TripInfo, time: u32) -> Vec<u32> {
+    if trip.frequencies.is_empty() {
+        return vec![time];
+    }
+    let offset = time.saturating_sub(trip.first_departure);
+    trip.frequencies
+        .iter()
+        .flat_map(|f| {
+            (f.start..f.end)
+                .step_by(f.headway as usize)
+                .map(move |start| start + offset)
+        })
+        .collect()
+}
+
+/// Several [`TransitFeed`]s with their stops linked to CP stations.
+#[derive(Debug, Clone, Default)]
+pub struct TransitTimetable {
+    feeds: Vec<TransitFeed>,
+    /// Links by IP-format station id.
+    links: HashMap<String, Vec<StopLink>>,
+}
+
+impl TransitTimetable {
+    /// Link the stops of `feeds` to the stations of `stations` that have
+    /// coordinates. Only stops with departures are linked, so a station's
+    /// platforms are linked rather than its parent entry.
+    #[must_use]
+    pub fn new(feeds: Vec<TransitFeed>, stations: &StationIndex) -> Self {
+        let mut links: HashMap<String, Vec<StopLink>> = HashMap::new();
+
+        for station in stations.iter() {
+            let Some(at) = stations.coordinates(&station.code) else {
+                continue;
+            };
+            let station_name = normalize_name(&station.designation);
+
+            for (f, feed) in feeds.iter().enumerate() {
+                for (s, stop) in feed.stops.iter().enumerate() {
+                    let Some(stop_at) = stop.coordinates else {
+                        continue;
+                    };
+                    if !feed.departures.contains_key(&s) {
+                        continue;
+                    }
+                    let distance_meters = at.distance_km(&stop_at) * 1000.0;
+                    if distance_meters > NAME_LINK_RADIUS_METERS {
+                        continue;
+                    }
+                    let by_name = names_match(&station_name, &normalize_name(&stop.name));
+                    if by_name || distance_meters <= LINK_RADIUS_METERS {
+                        links
+                            .entry(normalize_station_id(&station.code))
+                            .or_default()
+                            .push(StopLink {
+                                feed: f,
+                                stop_id: stop.id.clone(),
+                                distance_meters,
+                                by_name,
+                            });
+                    }
+                }
+            }
+        }
+        for station_links in links.values_mut() {
+            station_links.sort_by(|a, b| a.distance_meters.total_cmp(&b.distance_meters));
+        }
+
+        Self { feeds, links }
+    }
+
+    /// The loaded feeds, in the order given.
+    #[must_use]
+    pub fn feeds(&self) -> &[TransitFeed] {
+        &self.feeds
+    }
+
+    /// Stops linked to the CP station with id `station_code` (CP or IP
+    /// format), nearest first.
+    #[must_use]
+    pub fn links(&self, station_code: &str) -> &[StopLink] {
+        self.links
+            .get(&normalize_station_id(station_code))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Onward connections from stop `stop` of `journey`, the train running
+    /// on service day `date`.
+    ///
+    /// The train's arrival is the actual one when it has arrived, else the
+    /// predicted one, else the scheduled one, and rolls over to the next day
+    /// for stops reached after midnight. For every linked stop, the walk
+    /// there is allowed for (at least [`MIN_TRANSFER_MINUTES`]) and the next
+    /// departure of each route and headsign within
+    /// [`CONNECTION_HORIZON_MINUTES`] is returned, earliest first.
+    #[must_use]
+    pub fn connections(
+        &self,
+        journey: &TrainJourney,
+        stop: usize,
+        date: NaiveDate,
+    ) -> Vec<Connection> {
+        let Some(arrival) = arrival(journey, stop, date) else {
+            return Vec::new();
+        };
+        let stop = &journey.stops[stop];
+
+        let mut seen = HashSet::new();
+        let mut connections = Vec::new();
+        for link in self.links(&stop.station.code) {
+            let walk_minutes = walk_minutes(link.distance_meters);
+            let from = arrival + Duration::minutes(walk_minutes);
+            let until = from + Duration::minutes(CONNECTION_HORIZON_MINUTES);
+
+            for departure in self.feeds[link.feed].departures(&link.stop_id, from, until) {
+                let key = (
+                    link.feed,
+                    departure.route.clone(),
+                    departure.headsign.clone(),
+                );
+                if seen.insert(key) {
+                    connections.push(Connection {
+                        departure,
+                        walk_minutes,
+                    });
+                }
+            }
+        }
+
+        connections.sort_by_key(|c| c.departure.departure);
+        connections
+    }
+}
+
+/// When the train reaches stop `i` of `journey` on service day `date`.
+fn arrival(journey: &TrainJourney, i: usize, date: NaiveDate) -> Option<NaiveDateTime> {
+    let stop = journey.stops.get(i)?;
+    let timeline = Timeline::new(&journey.stops);
+    let scheduled = if minutes(&stop.scheduled_arrival).is_some() {
+        timeline.arrival(i)
+    } else {
+        minutes(&stop.scheduled_departure)?;
+        timeline.departure(i)
+    };
+    let scheduled = date.and_time(NaiveTime::MIN) + Duration::minutes(scheduled.into());
+    Some(
+        [
+            stop.actual_arrival.as_deref(),
+            stop.predicted_time.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|time| datetime_near(scheduled, time))
+        .unwrap_or(scheduled),
+    )
+}
+
+fn walk_minutes(distance_meters: f64) -> i64 {
+    #[allow(clippy::cast_possible_truncation)]
+    let walk = (distance_meters / WALKING_METERS_PER_MINUTE).ceil() as i64;
+    walk.max(MIN_TRANSFER_MINUTES)
+}
+
+/// Whether two normalized names are the same, or one contains the other,
+/// as `"oriente"` does `"lisboaoriente"`.
+fn names_match(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    !short.is_empty()
+        && (short == long || (short.len() >= MIN_PARTIAL_NAME_LEN && long.contains(short)))
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y%m%d").ok()
+}
+
+/// Read the CSV table `name` of a GTFS zip, also found in a subdirectory.
+/// A missing optional table reads as empty.
+fn read_table<T: DeserializeOwned, R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    required: bool,
+) -> Result<Vec<T>, CoreError> {
+    let Some(path) = archive
+        .file_names()
+        .find(|file| *file == name || file.ends_with(&format!("/{name}")))
+        .map(str::to_string)
+    else {
+        return if required {
+            Err(CoreError::GtfsError(format!("{name} is missing")))
+        } else {
+            Ok(Vec::new())
+        };
+    };
+
+    let file = archive
+        .by_name(&path)
+        .map_err(|e| CoreError::GtfsError(format!("reading {name}: {e}")))?;
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(file)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(|e| CoreError::GtfsError(format!("reading {name}: {e}")))
+}
+
+#[derive(Deserialize)]
+struct AgencyRow {
+    #[serde(default)]
+    agency_name: String,
+}
+
+#[derive(Deserialize)]
+struct StopRow {
+    stop_id: String,
+    #[serde(default)]
+    stop_name: String,
+    #[serde(default)]
+    stop_lat: Option<f64>,
+    #[serde(default)]
+    stop_lon: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct RouteRow {
+    route_id: String,
+    #[serde(default)]
+    route_short_name: String,
+    #[serde(default)]
+    route_long_name: String,
+    #[serde(default)]
+    route_type: u16,
+}
+
+#[derive(Deserialize)]
+struct TripRow {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+    #[serde(default)]
+    trip_headsign: String,
+}
+
+#[derive(Deserialize)]
+struct StopTimeRow {
+    trip_id: String,
+    #[serde(default)]
+    arrival_time: String,
+    #[serde(default)]
+    departure_time: String,
+    stop_id: String,
+    stop_sequence: u32,
+}
+
+#[derive(Deserialize)]
+struct CalendarRow {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Deserialize)]
+struct CalendarDateRow {
+    service_id: String,
+    date: String,
+    exception_type: u8,
+}
+
+#[derive(Deserialize)]
+struct FrequencyRow {
+    trip_id: String,
+    start_time: String,
+    end_time: String,
+    headway_secs: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_whole_or_contained() {
+        assert!(names_match("lisboaoriente", "oriente"));
+        assert!(names_match("campanha", "campanha"));
+        assert!(!names_match("lisboaoriente", "ss"));
+        assert!(!names_match("", "oriente"));
+        assert!(!names_match("entrecampos", "campolide"));
+    }
+
+    #[test]
+    fn gtfs_times_pass_midnight() {
+        assert_eq!(parse_gtfs_time("6:30:00"), Some(6 * 3600 + 30 * 60));
+        assert_eq!(parse_gtfs_time(" 25:05:10 "), Some(25 * 3600 + 5 * 60 + 10));
+        assert_eq!(parse_gtfs_time("10:61:00"), None);
+        assert_eq!(parse_gtfs_time(""), None);
+    }
+
+    #[test]
+    fn walks_take_at_least_the_minimum_transfer() {
+        assert_eq!(walk_minutes(0.0), MIN_TRANSFER_MINUTES);
+        assert_eq!(walk_minutes(500.0), 7);
+    }
+}
@@ -0,0 +1,750 @@
+//! Push-style updates from repeated polling.
+//!
+//! CP and IP only answer requests, so following a train means fetching its
+//! journey again and again. [`JourneyTracker`] compares each new snapshot with
+//! the previous one and reports what changed as [`JourneyEvent`]s;
+//! [`Comboios::watch_train`] drives it as a stream. [`BoardTracker`] and
+//! [`Comboios::watch_board`] do the same for a station's departure board.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use comboios_core::Comboios;
+//!
+//! # async fn run(client: Comboios) {
+//! let events = client.watch_train("120", &client.today(), Duration::from_secs(30));
+//! futures::pin_mut!(events);
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         Ok(event) => println!("{event:?}"),
+//!         Err(e) => eprintln!("poll failed: {e}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::Comboios;
+use crate::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use crate::domain::station::Station;
+use crate::domain::station_timetable::StationTimetable;
+use crate::error::CoreError;
+use crate::query_builder::BoardWindow;
+use crate::timeline::datetime_near;
+
+/// Smallest change in a train's delay, in minutes, reported as
+/// [`JourneyEvent::DelayChanged`].
+pub const DELAY_CHANGE_MINUTES: i32 = 3;
+
+/// Longest wait between polls of a train that has not left yet.
+pub const MAX_IDLE_POLL: Duration = Duration::from_mins(15);
+
+/// Polls in a row answered with `404` after which a watched train is taken
+/// not to exist on that date.
+pub const MAX_NOT_FOUND_POLLS: u32 = 3;
+
+/// Something that happened to a train between two polls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JourneyEvent {
+    /// The train got to `station`; `time` is the actual arrival when known.
+    ArrivedAtStop {
+        station: Station,
+        time: Option<String>,
+    },
+    /// The train left `station`; `time` is the actual departure when known.
+    Departed {
+        station: Station,
+        time: Option<String>,
+    },
+    /// The delay moved by at least [`DELAY_CHANGE_MINUTES`] since it was
+    /// last reported.
+    DelayChanged {
+        previous: Option<i32>,
+        current: Option<i32>,
+    },
+    /// The platform at a stop still to come was assigned or changed.
+    PlatformChanged {
+        station: Station,
+        previous: Option<String>,
+        current: String,
+    },
+    /// The whole journey (`station` is `None`) or one stop was cancelled.
+    Cancelled { station: Option<Station> },
+    /// The train reached its destination.
+    Completed,
+}
+
+/// Turns successive snapshots of one journey into [`JourneyEvent`]s.
+#[derive(Debug, Clone, Default)]
+pub struct JourneyTracker {
+    previous: Option<TrainJourney>,
+    reported_delay: Option<i32>,
+}
+
+impl JourneyTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The last snapshot passed to [`update`](Self::update).
+    #[must_use]
+    pub fn journey(&self) -> Option<&TrainJourney> {
+        self.previous.as_ref()
+    }
+
+    /// Record `journey` and return what changed since the previous snapshot.
+    ///
+    /// The first snapshot is the baseline: it only yields
+    /// [`Cancelled`](JourneyEvent::Cancelled) or
+    /// [`Completed`](JourneyEvent::Completed) when the journey is already
+    /// over. Stop events come in stop order, then platform, delay and
+    /// cancellation events, and `Completed` last.
+    pub fn update(&mut self, journey: TrainJourney) -> Vec<JourneyEvent> {
+        let mut events = Vec::new();
+        let Some(previous) = self.previous.as_ref() else {
+            self.reported_delay = journey.delay_minutes;
+            match journey.status {
+                JourneyStatus::Cancelled => events.push(JourneyEvent::Cancelled { station: None }),
+                JourneyStatus::Completed => events.push(JourneyEvent::Completed),
+                _ => {}
+            }
+            self.previous = Some(journey);
+            return events;
+        };
+
+        // Stops are matched by position; a journey whose stop list changed
+        // shape is compared on the common prefix only.
+        let pairs = previous.stops.iter().zip(&journey.stops);
+        let last = journey.stops.len().saturating_sub(1);
+        for (i, (before, after)) in pairs.clone().enumerate() {
+            if i > 0 && !arrived(before) && arrived(after) {
+                events.push(JourneyEvent::ArrivedAtStop {
+                    station: after.station.clone(),
+                    time: after.actual_arrival.clone(),
+                });
+            }
+            if i < last && !departed(before) && departed(after) {
+                events.push(JourneyEvent::Departed {
+                    station: after.station.clone(),
+                    time: after.actual_departure.clone(),
+                });
+            }
+        }
+
+        for (before, after) in pairs.clone() {
+            if let Some(platform) = &after.platform
+                && before.platform.as_ref() != Some(platform)
+                && !departed(after)
+            {
+                events.push(JourneyEvent::PlatformChanged {
+                    station: after.station.clone(),
+                    previous: before.platform.clone(),
+                    current: platform.clone(),
+                });
+            }
+        }
+
+        let delay_change = journey.delay_minutes.unwrap_or(0) - self.reported_delay.unwrap_or(0);
+        if delay_change.abs() >= DELAY_CHANGE_MINUTES {
+            events.push(JourneyEvent::DelayChanged {
+                previous: self.reported_delay,
+                current: journey.delay_minutes,
+            });
+            self.reported_delay = journey.delay_minutes;
+        }
+
+        if journey.status == JourneyStatus::Cancelled {
+            if previous.status != JourneyStatus::Cancelled {
+                events.push(JourneyEvent::Cancelled { station: None });
+            }
+        } else {
+            for (before, after) in pairs {
+                if before.status != StopStatus::Cancelled && after.status == StopStatus::Cancelled {
+                    events.push(JourneyEvent::Cancelled {
+                        station: Some(after.station.clone()),
+                    });
+                }
+            }
+        }
+
+        if journey.status == JourneyStatus::Completed && previous.status != JourneyStatus::Completed
+        {
+            events.push(JourneyEvent::Completed);
+        }
+
+        self.previous = Some(journey);
+        events
+    }
+}
+
+/// CP marks every stop behind the train as passed, whether or not it called.
+fn arrived(stop: &JourneyStop) -> bool {
+    stop.has_arrived() || stop.status == StopStatus::Passed
+}
+
+fn departed(stop: &JourneyStop) -> bool {
+    stop.has_departed() || stop.status == StopStatus::Passed
+}
+
+/// How long to wait before polling `journey` again, or `None` once it is
+/// completed or cancelled.
+///
+/// A running train is polled every `interval`. Before departure the wait is
+/// half the time left until the train leaves its origin, between `interval`
+/// and [`MAX_IDLE_POLL`].
+#[must_use]
+pub fn next_poll(
+    journey: &TrainJourney,
+    now: NaiveDateTime,
+    interval: Duration,
+) -> Option<Duration> {
+    match journey.status {
+        JourneyStatus::Completed | JourneyStatus::Cancelled => None,
+        JourneyStatus::Scheduled => {
+            let departure = journey.origin_departure.or_else(|| {
+                let first = journey.stops.first()?;
+                datetime_near(now, &first.scheduled_departure)
+            });
+            let wait = departure
+                .and_then(|at| (at - now).to_std().ok())
+                .map_or(interval, |left| {
+                    (left / 2).clamp(interval, MAX_IDLE_POLL.max(interval))
+                });
+            Some(wait)
+        }
+        _ => Some(interval),
+    }
+}
+
+struct TrainWatch {
+    api: Comboios,
+    train_number: String,
+    date: String,
+    interval: Duration,
+    tracker: JourneyTracker,
+    pending: VecDeque<JourneyEvent>,
+    /// Wait before the next poll; `None` once the journey is over.
+    wait: Option<Duration>,
+    /// Polls in a row answered with `404`.
+    not_found: u32,
+}
+
+/// See [`Comboios::watch_train`].
+pub(crate) fn watch_train(
+    api: Comboios,
+    train_number: &str,
+    date: &str,
+    interval: Duration,
+) -> impl Stream<Item = Result<JourneyEvent, CoreError>> + Send + 'static {
+    let watch = TrainWatch {
+        api,
+        train_number: train_number.to_string(),
+        date: date.to_string(),
+        interval,
+        tracker: JourneyTracker::new(),
+        pending: VecDeque::new(),
+        wait: Some(Duration::ZERO),
+        not_found: 0,
+    };
+
+    stream::unfold(watch, |mut watch| async move {
+        loop {
+            if let Some(event) = watch.pending.pop_front() {
+                return Some((Ok(event), watch));
+            }
+            tokio::time::sleep(watch.wait?).await;
+
+            match watch
+                .api
+                .get_train_journey(&watch.train_number, &watch.date)
+                .await
+            {
+                Ok(journey) => {
+                    let now = watch.api.clock().now().naive_local();
+                    watch.not_found = 0;
+                    watch.wait = next_poll(&journey, now, watch.interval);
+                    watch.pending.extend(watch.tracker.update(journey));
+                }
+                Err(e) => {
+                    if matches!(e, CoreError::ApiError { status: 404, .. }) {
+                        watch.not_found += 1;
+                    } else {
+                        watch.not_found = 0;
+                    }
+                    watch.wait = (watch.not_found < MAX_NOT_FOUND_POLLS).then_some(watch.interval);
+                    return Some((Err(e), watch));
+                }
+            }
+        }
+    })
+}
+
+/// Suggested wait between polls of a watched station board.
+pub const BOARD_POLL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// How far before the start of a watched window the board is fetched, so a
+/// late train stays on it until it actually leaves.
+pub const BOARD_LOOKBACK: Duration = Duration::from_mins(60);
+
+/// Something that changed on a departure board between two polls. Trains are
+/// identified by their number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BoardEvent {
+    /// A train appeared on the board.
+    Added { train: Box<StationTimetable> },
+    /// A train dropped off the board before leaving, e.g. because it moved
+    /// past the result limit.
+    Removed { train_number: u64 },
+    /// A platform was given to a train that had none.
+    PlatformAssigned { train_number: u64, platform: String },
+    /// A train's platform changed.
+    PlatformChanged {
+        train_number: u64,
+        previous: String,
+        current: String,
+    },
+    /// A train's delay changed.
+    DelayChanged {
+        train_number: u64,
+        previous: Option<i32>,
+        current: Option<i32>,
+    },
+    /// CP flagged a train as suppressed.
+    Cancelled { train_number: u64 },
+    /// A train left; `time` is its estimated or actual departure when known.
+    Departed {
+        train_number: u64,
+        time: Option<String>,
+    },
+}
+
+/// Turns successive polls of a departure board into [`BoardEvent`]s.
+///
+/// Rows are keyed by train number and come paired with their scheduled
+/// date-time. A train counts as departed once its estimated departure, or
+/// its scheduled one plus the delay, is not after "now". Unlike
+/// [`JourneyTracker`] there is no silent baseline: the first poll adds every
+/// train, so a display can be built from the events alone.
+#[derive(Debug, Clone, Default)]
+pub struct BoardTracker {
+    trains: HashMap<u64, (NaiveDateTime, StationTimetable)>,
+    max_results: Option<usize>,
+}
+
+impl BoardTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show at most `max_results` trains still to leave, earliest first.
+    #[must_use]
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Whether `train_number` is on the board.
+    #[must_use]
+    pub fn contains(&self, train_number: u64) -> bool {
+        self.trains.contains_key(&train_number)
+    }
+
+    /// The trains on the board, earliest first.
+    #[must_use]
+    pub fn trains(&self) -> Vec<&StationTimetable> {
+        let mut trains: Vec<_> = self.trains.values().collect();
+        trains.sort_by_key(|(at, row)| (*at, row.train_number));
+        trains.into_iter().map(|(_, row)| row).collect()
+    }
+
+    /// Record a poll of the board at `now` and return what changed.
+    ///
+    /// Departures and removals come first, in time order, then additions
+    /// and changes in board order.
+    pub fn update(
+        &mut self,
+        rows: Vec<(NaiveDateTime, StationTimetable)>,
+        now: NaiveDateTime,
+    ) -> Vec<BoardEvent> {
+        let mut seen = HashSet::new();
+        let (mut upcoming, left): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .filter(|(_, row)| seen.insert(row.train_number))
+            .partition(|(at, row)| expected_departure(*at, row) > now);
+        if let Some(max) = self.max_results {
+            upcoming.truncate(max);
+        }
+
+        let mut gone: Vec<_> = self
+            .trains
+            .iter()
+            .filter(|(number, _)| !upcoming.iter().any(|(_, r)| r.train_number == **number))
+            .map(|(number, stored)| {
+                let latest = left.iter().find(|(_, r)| r.train_number == *number);
+                latest.unwrap_or(stored).clone()
+            })
+            .collect();
+        gone.sort_by_key(|(at, row)| (*at, row.train_number));
+
+        let mut events: Vec<BoardEvent> = gone
+            .iter()
+            .map(|(at, row)| {
+                let train_number = row.train_number;
+                // A cancelled train never leaves; it was reported as
+                // cancelled while on the board.
+                if !row.is_cancelled() && expected_departure(*at, row) <= now {
+                    BoardEvent::Departed {
+                        train_number,
+                        time: row
+                            .estimated_departure
+                            .clone()
+                            .or_else(|| row.departure_time.clone()),
+                    }
+                } else {
+                    BoardEvent::Removed { train_number }
+                }
+            })
+            .collect();
+
+        for (_, row) in &upcoming {
+            let train_number = row.train_number;
+            let Some((_, before)) = self.trains.get(&train_number) else {
+                events.push(BoardEvent::Added {
+                    train: Box::new(row.clone()),
+                });
+                continue;
+            };
+            if !before.is_cancelled() && row.is_cancelled() {
+                events.push(BoardEvent::Cancelled { train_number });
+            }
+            match (&before.platform, &row.platform) {
+                (None, Some(platform)) => events.push(BoardEvent::PlatformAssigned {
+                    train_number,
+                    platform: platform.clone(),
+                }),
+                (Some(previous), Some(current)) if previous != current => {
+                    events.push(BoardEvent::PlatformChanged {
+                        train_number,
+                        previous: previous.clone(),
+                        current: current.clone(),
+                    });
+                }
+                _ => {}
+            }
+            if before.delay.unwrap_or(0) != row.delay.unwrap_or(0) {
+                events.push(BoardEvent::DelayChanged {
+                    train_number,
+                    previous: before.delay,
+                    current: row.delay,
+                });
+            }
+        }
+
+        self.trains = upcoming
+            .into_iter()
+            .map(|(at, row)| (row.train_number, (at, row)))
+            .collect();
+        events
+    }
+}
+
+/// When the train on `row`, scheduled at `scheduled`, is expected to leave.
+fn expected_departure(scheduled: NaiveDateTime, row: &StationTimetable) -> NaiveDateTime {
+    row.estimated_departure
+        .as_deref()
+        .and_then(|t| datetime_near(scheduled, t))
+        .unwrap_or(scheduled + chrono::Duration::minutes(row.delay.unwrap_or(0).into()))
+}
+
+struct BoardWatch {
+    api: Comboios,
+    /// The station as given, until the first poll resolves it to an id.
+    station: String,
+    resolved: Option<String>,
+    window: BoardWindow,
+    interval: Duration,
+    /// Clock time of the first poll; the window moves on by the time since.
+    started: Option<NaiveDateTime>,
+    tracker: BoardTracker,
+    pending: VecDeque<BoardEvent>,
+    first: bool,
+}
+
+impl BoardWatch {
+    async fn poll(&mut self) -> Result<Vec<BoardEvent>, CoreError> {
+        let now = self.api.clock().now().naive_local();
+        let started = *self.started.get_or_insert(now);
+        let lookback = chrono::Duration::from_std(BOARD_LOOKBACK).unwrap_or_default();
+        let start = self.window.start() + (now - started);
+        let end = self.window.end() + (now - started);
+
+        let station = match &self.resolved {
+            Some(station) => station.clone(),
+            None => {
+                let station = self.api.resolve_station(&self.station).await?;
+                self.resolved.insert(station).clone()
+            }
+        };
+        let fetch_start = start - lookback;
+        let rows = self
+            .api
+            .dated_departures(&station, BoardWindow::new(fetch_start..end))
+            .await?;
+
+        // The lookback only keeps late trains that were already shown.
+        let rows = rows
+            .into_iter()
+            .filter(|(at, row)| *at >= start || self.tracker.contains(row.train_number))
+            .collect();
+        Ok(self.tracker.update(rows, now))
+    }
+}
+
+/// See [`Comboios::watch_board`].
+pub(crate) fn watch_board(
+    api: Comboios,
+    station: &str,
+    window: BoardWindow,
+    interval: Duration,
+) -> impl Stream<Item = Result<BoardEvent, CoreError>> + Send + 'static {
+    let mut tracker = BoardTracker::new();
+    if let Some(max) = window.get_max_results() {
+        tracker = tracker.max_results(max);
+    }
+    let watch = BoardWatch {
+        api,
+        station: station.to_string(),
+        resolved: None,
+        window,
+        interval,
+        started: None,
+        tracker,
+        pending: VecDeque::new(),
+        first: true,
+    };
+
+    stream::unfold(watch, |mut watch| async move {
+        loop {
+            if let Some(event) = watch.pending.pop_front() {
+                return Some((Ok(event), watch));
+            }
+            if !std::mem::take(&mut watch.first) {
+                tokio::time::sleep(watch.interval).await;
+            }
+            match watch.poll().await {
+                Ok(events) => watch.pending.extend(events),
+                Err(e) => return Some((Err(e), watch)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use futures::StreamExt;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::adapters::{CpAdapter, IpAdapter};
+    use crate::clock::FixedClock;
+
+    fn timetable(status: &str, last: Option<&str>, delay: i32, passed: usize) -> serde_json::Value {
+        let stops: Vec<_> = [("94-1", "08:50"), ("94-2", "09:10"), ("94-3", "09:30")]
+            .iter()
+            .enumerate()
+            .map(|(i, (code, time))| {
+                let done = i < passed;
+                serde_json::json!({
+                    "station": {"code": code, "designation": format!("Station {code}")},
+                    "arrival": time,
+                    "departure": time,
+                    "platform": (i == 2 && passed > 0).then_some("2"),
+                    "ETA": done.then_some(time),
+                    "ETD": (done && i < 2).then_some(time),
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "trainNumber": 100,
+            "serviceCode": {"code": "R", "designation": "Regional"},
+            "lastStationCode": last,
+            "delay": delay,
+            "trainStops": stops,
+            "status": status,
+            "messages": []
+        })
+    }
+
+    #[tokio::test]
+    async fn watch_train_gives_up_on_an_unknown_train() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let client = Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()));
+
+        let errors: Vec<_> = client
+            .watch_train("99999", "2026-03-14", Duration::from_millis(10))
+            .collect()
+            .await;
+
+        assert_eq!(errors.len(), MAX_NOT_FOUND_POLLS as usize);
+        assert!(
+            errors
+                .iter()
+                .all(|e| matches!(e, Err(CoreError::ApiError { status: 404, .. })))
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_train_streams_changes_until_completed() {
+        let server = MockServer::start().await;
+        for body in [
+            timetable("SCHEDULED", None, 0, 0),
+            timetable("NEAR_NEXT", Some("94-2"), 4, 2),
+            timetable("ARRIVED", Some("94-3"), 4, 3),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/services/travel-api/trains/100/timetable/2026-03-14"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let now = NaiveDate::from_ymd_opt(2026, 3, 14)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let client = Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()))
+            .with_clock(FixedClock::at_local(now).unwrap());
+
+        let events: Vec<JourneyEvent> = client
+            .watch_train("100", "2026-03-14", Duration::from_millis(10))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let station = |code: &str| Station {
+            code: code.to_string(),
+            designation: format!("Station {code}"),
+        };
+        assert_eq!(
+            events,
+            vec![
+                JourneyEvent::Departed {
+                    station: station("94-1"),
+                    time: Some("08:50".to_string()),
+                },
+                JourneyEvent::ArrivedAtStop {
+                    station: station("94-2"),
+                    time: Some("09:10".to_string()),
+                },
+                JourneyEvent::Departed {
+                    station: station("94-2"),
+                    time: Some("09:10".to_string()),
+                },
+                JourneyEvent::PlatformChanged {
+                    station: station("94-3"),
+                    previous: None,
+                    current: "2".to_string(),
+                },
+                JourneyEvent::DelayChanged {
+                    previous: Some(0),
+                    current: Some(4),
+                },
+                JourneyEvent::ArrivedAtStop {
+                    station: station("94-3"),
+                    time: Some("09:30".to_string()),
+                },
+                JourneyEvent::Completed,
+            ]
+        );
+    }
+
+    fn board_row(train: u64, departure: &str, delay: i32) -> serde_json::Value {
+        serde_json::json!({
+            "trainNumber": train,
+            "trainService": {"code": "R", "designation": "Regional"},
+            "trainOrigin": {"code": "94-1", "designation": "Station 94-1"},
+            "trainDestination": {"code": "94-3", "designation": "Station 94-3"},
+            "departureTime": departure,
+            "delay": delay,
+        })
+    }
+
+    #[tokio::test]
+    async fn watch_board_first_poll_adds_trains_still_to_leave() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/services/travel-api/stations/94-2/timetable/2026-03-14",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "stationStops": [
+                    board_row(10, "08:40", 30), // before the window, never shown
+                    board_row(20, "08:55", 0),  // already left
+                    board_row(30, "09:00", 5),
+                    board_row(40, "09:30", 0),
+                ],
+                "messages": []
+            })))
+            .mount(&server)
+            .await;
+
+        let cp = CpAdapter::with_base_url(
+            &server.uri(),
+            "key".to_string(),
+            "id".to_string(),
+            "secret".to_string(),
+        );
+        let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        let now = day.and_hms_opt(9, 0, 0).unwrap();
+        let client = Comboios::from_adapters(cp, IpAdapter::with_url(&server.uri()))
+            .with_clock(FixedClock::at_local(now).unwrap());
+
+        let window = BoardWindow::new(
+            day.and_hms_opt(8, 50, 0).unwrap()..day.and_hms_opt(10, 0, 0).unwrap(),
+        );
+        let added: Vec<u64> = client
+            .watch_board("94-2", window, BOARD_POLL_INTERVAL)
+            .take(2)
+            .map(|event| match event.unwrap() {
+                BoardEvent::Added { train } => train.train_number,
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect()
+            .await;
+
+        assert_eq!(added, vec![30, 40]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Tests for the iCalendar export of journeys.
+
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use comboios_core::domain::calendar::Calendar;
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, TrainJourney};
+
+mod common;
+
+/// Stop `num` at a station called `name`.
+fn stop(num: usize, code: &str, name: &str, arrival: &str, departure: &str) -> JourneyStop {
+    let mut stop = common::stop(num, code, arrival, departure);
+    stop.station.designation = name.to_string();
+    stop
+}
+
+/// Lisboa - Santa Apolónia (23:00) → Coimbra-B (00:40) → Porto - Campanhã
+/// (01:45), past midnight.
+fn ic_529() -> TrainJourney {
+    common::journey(
+        "529",
+        vec![
+            stop(1, "94-30007", "Lisboa - Santa Apolónia", "", "23:00"),
+            stop(2, "94-36004", "Coimbra-B", "00:40", "00:42"),
+            stop(3, "94-2006", "Porto - Campanhã", "01:45", ""),
+        ],
+    )
+}
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+}
+
+fn stamp(minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 14, 21, minute, 0).unwrap()
+}
+
+fn unfolded(ics: &str) -> String {
+    ics.replace("\r\n ", "")
+}
+
+#[test]
+fn journey_is_one_event_in_lisbon_time() {
+    let ics = ic_529().to_ics(day(14), stamp(0));
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(!ics.replace("\r\n", "").contains('\n'));
+    assert!(ics.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Lisbon\r\n"));
+
+    let ics = unfolded(&ics);
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains("UID:529_20260314@comboios\r\n"));
+    assert!(ics.contains("DTSTART;TZID=Europe/Lisbon:20260314T230000\r\n"));
+    assert!(ics.contains("DTEND;TZID=Europe/Lisbon:20260315T014500\r\n"));
+    assert!(ics.contains("SUMMARY:IC 529 Lisboa - Santa Apolónia → Porto - Campanhã\r\n"));
+    assert!(ics.contains("LOCATION:Lisboa - Santa Apolónia → Porto - Campanhã\r\n"));
+    assert!(ics.contains("STATUS:CONFIRMED\r\n"));
+    assert!(ics.contains("DTSTAMP:20260314T210000Z\r\n"));
+}
+
+#[test]
+fn delay_and_platforms_are_described() {
+    let mut journey = ic_529();
+    journey.status = JourneyStatus::Delayed;
+    journey.delay_minutes = Some(12);
+    journey.predicted_arrival = Some("01:57".to_string());
+    journey.stops[0].platform = Some("3".to_string());
+    journey.stops[2].platform = Some("5".to_string());
+    journey.observations = Some("Circula com atraso, por avaria.".to_string());
+
+    let ics = unfolded(&journey.to_ics(day(14), stamp(0)));
+
+    assert!(
+        ics.contains("SUMMARY:IC 529 Lisboa - Santa Apolónia → Porto - Campanhã (+12 min)\r\n")
+    );
+    assert!(ics.contains(
+        "DESCRIPTION:Running 12 min late.\\nExpected arrival 01:57.\\n\
+         Departs from platform 3.\\nArrives at platform 5.\\n\
+         Circula com atraso\\, por avaria.\\nCP train 529.\r\n"
+    ));
+    // The schedule stays put; the delay is only described.
+    assert!(ics.contains("DTSTART;TZID=Europe/Lisbon:20260314T230000\r\n"));
+}
+
+#[test]
+fn cancelled_journeys_are_cancelled_events() {
+    let mut journey = ic_529();
+    journey.status = JourneyStatus::Cancelled;
+
+    let ics = unfolded(&journey.to_ics(day(14), stamp(0)));
+
+    assert!(ics.contains("STATUS:CANCELLED\r\n"));
+    assert!(ics.contains("SUMMARY:Cancelled: IC 529"));
+    assert!(ics.contains("DESCRIPTION:Cancelled.\\n"));
+}
+
+#[test]
+fn calendar_keeps_uids_stable_across_refreshes() {
+    let mut journey = ic_529();
+    let before = Calendar::new(stamp(0))
+        .name("Porto")
+        .refresh_interval(Duration::from_secs(300))
+        .journey(&journey, day(14))
+        .journey(&journey, day(15))
+        .to_string();
+    journey.delay_minutes = Some(5);
+    let after = Calendar::new(stamp(5))
+        .journey(&journey, day(14))
+        .to_string();
+
+    assert!(before.contains("X-WR-CALNAME:Porto\r\n"));
+    assert!(before.contains("REFRESH-INTERVAL;VALUE=DURATION:PT5M\r\n"));
+    assert!(before.contains("X-PUBLISHED-TTL:PT5M\r\n"));
+    assert!(before.contains("DTSTAMP:20260314T210000Z\r\n"));
+    assert!(before.contains("UID:529_20260315@comboios\r\n"));
+    assert!(before.contains("UID:529_20260314@comboios\r\n"));
+    assert!(after.contains("UID:529_20260314@comboios\r\n"));
+    assert!(after.contains("DTSTAMP:20260314T210500Z\r\n"));
+}
+
+#[test]
+fn journeys_without_stops_have_no_event() {
+    let mut journey = ic_529();
+    journey.stops.clear();
+
+    assert!(journey.scheduled_times(day(14)).is_none());
+    assert!(!journey.to_ics(day(14), stamp(0)).contains("BEGIN:VEVENT"));
+}
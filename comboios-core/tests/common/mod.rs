@@ -0,0 +1,103 @@
+//! Journey, stop and board-row builders shared by the integration tests.
+//!
+//! Every field gets a neutral default; tests change what they care about by
+//! mutating the result or with struct update syntax.
+
+#![allow(dead_code)]
+
+use chrono::{NaiveDate, NaiveDateTime};
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::occupancy::Occupancy;
+use comboios_core::domain::station::Station;
+use comboios_core::domain::station_timetable::StationTimetable;
+
+/// Station `code`, named `Station <code>`.
+pub fn station(code: &str) -> Station {
+    Station {
+        code: code.to_string(),
+        designation: format!("Station {code}"),
+    }
+}
+
+/// Stop `num` at `code`, scheduled and not yet reached.
+pub fn stop(num: usize, code: &str, arrival: &str, departure: &str) -> JourneyStop {
+    JourneyStop {
+        station: station(code),
+        scheduled_arrival: arrival.to_string(),
+        actual_arrival: None,
+        scheduled_departure: departure.to_string(),
+        actual_departure: None,
+        platform: None,
+        status: StopStatus::Scheduled,
+        delay_minutes: None,
+        stop_number: num,
+        has_passed: Some(false),
+        predicted_time: None,
+        prediction_confidence: None,
+    }
+}
+
+/// CP Intercidades `train` calling at `stops`, not yet departed.
+pub fn journey(train: &str, stops: Vec<JourneyStop>) -> TrainJourney {
+    let origin = stops
+        .first()
+        .map_or_else(|| station("94-1"), |s| s.station.clone());
+    let destination = stops
+        .last()
+        .map_or_else(|| origin.clone(), |s| s.station.clone());
+    TrainJourney {
+        train_number: train.to_string(),
+        service_type: "IC|Intercidades".to_string(),
+        origin,
+        destination,
+        stops,
+        status: JourneyStatus::Scheduled,
+        delay_minutes: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        observations: None,
+        duration: None,
+        origin_departure: None,
+        destination_arrival: None,
+        predicted_arrival: None,
+        prediction_confidence: None,
+        live_position: None,
+    }
+}
+
+/// Board row of CP Intercidades `train_number` from `origin` to
+/// `destination`, with no times, platform or delay.
+pub fn row(train_number: u64, origin: &str, destination: &str) -> StationTimetable {
+    StationTimetable {
+        train_number,
+        service_type: "IC|Intercidades".to_string(),
+        origin_station_name: format!("Station {origin}"),
+        origin_station_id: origin.to_string(),
+        destination_station_name: format!("Station {destination}"),
+        destination_station_id: destination.to_string(),
+        departure_time: None,
+        arrival_time: None,
+        platform: None,
+        delay: None,
+        estimated_departure: None,
+        estimated_arrival: None,
+        observations: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        has_passed: false,
+        is_departure: true,
+    }
+}
+
+/// Saturday 14 March 2026.
+pub fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 14).unwrap()
+}
+
+/// `HH:MM` on [`day`].
+pub fn at(time: &str) -> NaiveDateTime {
+    let (h, m) = time.split_once(':').unwrap();
+    day()
+        .and_hms_opt(h.parse().unwrap(), m.parse().unwrap(), 0)
+        .unwrap()
+}
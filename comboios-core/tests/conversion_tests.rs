@@ -1,10 +1,13 @@
 //! Conversion tests for raw API types to domain types.
 //! These are pure unit tests - no network calls.
 
+use chrono::NaiveDate;
+use comboios_core::clock::{Clock, FixedClock};
 use comboios_core::domain::cp_types::{
     CpServiceCode, CpStationSimple, CpTrainStop, CpTrainTimetable,
 };
 use comboios_core::domain::journey::{JourneyStatus, StopStatus};
+use comboios_core::domain::occupancy::Occupancy;
 use comboios_core::domain::station_timetable::TrainEntry;
 use comboios_core::domain::train_journey::{IpTrainJourneyResponse, TrainPassage};
 
@@ -50,6 +53,8 @@ fn make_timetable(status: &str, stops: Vec<CpTrainStop>) -> CpTrainTimetable {
         has_disruptions: None,
         duration: None,
         messages: vec![],
+        latitude: None,
+        longitude: None,
     }
 }
 
@@ -65,6 +70,7 @@ fn make_ip_passage(
         node_id,
         station_name: station_name.to_string(),
         observations: String::new(),
+        platform: None,
     }
 }
 
@@ -297,6 +303,52 @@ fn test_cp_train_no_delay_is_none() {
     assert_eq!(journey.stops[0].delay_minutes, None);
 }
 
+// ---------------------------------------------------------------------------
+// CpTrainTimetable::to_train_journey - occupancy
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cp_occupancy_mapped_to_level() {
+    let mut timetable = make_timetable("SCHEDULED", vec![make_stop("94-001", "Lisboa")]);
+    timetable.occupancy = Some(3);
+
+    let journey = timetable.to_train_journey();
+    assert_eq!(journey.occupancy, Occupancy::High);
+}
+
+#[test]
+fn test_cp_missing_occupancy_is_unknown() {
+    let timetable = make_timetable("SCHEDULED", vec![make_stop("94-001", "Lisboa")]);
+    let journey = timetable.to_train_journey();
+    assert_eq!(journey.occupancy, Occupancy::Unknown);
+}
+
+#[test]
+fn test_cp_out_of_range_occupancy_is_unknown() {
+    let mut timetable = make_timetable("SCHEDULED", vec![make_stop("94-001", "Lisboa")]);
+    timetable.occupancy = Some(0);
+
+    let journey = timetable.to_train_journey();
+    assert_eq!(journey.occupancy, Occupancy::Unknown);
+}
+
+#[test]
+fn test_cp_live_position_parsed_from_coordinates() {
+    let mut timetable = make_timetable("IN_TRANSIT", vec![make_stop("94-1", "A")]);
+    timetable.latitude = Some("38.7139".to_string());
+    timetable.longitude = Some("-9.1223".to_string());
+
+    let position = timetable.to_train_journey().live_position.unwrap();
+    assert!((position.latitude - 38.7139).abs() < 1e-9);
+    assert!((position.longitude + 9.1223).abs() < 1e-9);
+}
+
+#[test]
+fn test_cp_missing_coordinates_have_no_live_position() {
+    let timetable = make_timetable("SCHEDULED", vec![make_stop("94-1", "A")]);
+    assert!(timetable.to_train_journey().live_position.is_none());
+}
+
 // ---------------------------------------------------------------------------
 // CpTrainTimetable::to_train_journey - stop numbering and fields
 // ---------------------------------------------------------------------------
@@ -390,7 +442,9 @@ fn test_ip_station_name_and_node_id_mapped() {
         journey.stops[0].station.designation,
         "Lisboa Santa Apolonia"
     );
-    assert_eq!(journey.stops[0].station.code, "94001");
+    // Stops use CP ids, like the origin and destination.
+    assert_eq!(journey.stops[0].station.code, "94-001");
+    assert_eq!(journey.origin.code, "94-001");
 }
 
 #[test]
@@ -418,6 +472,235 @@ fn test_ip_operator_propagated() {
     assert_eq!(journey.operator, "CP");
 }
 
+#[test]
+fn test_ip_origin_and_destination_codes_from_first_and_last_passage() {
+    let response = make_ip_response(vec![
+        make_ip_passage(9430007, "Lisboa", "10:00", false),
+        make_ip_passage(9402006, "Porto", "12:00", false),
+    ]);
+    let journey = response.to_train_journey("720");
+    assert_eq!(journey.origin.code, "94-30007");
+    assert_eq!(journey.destination.code, "94-02006");
+}
+
+#[test]
+fn test_ip_full_datetimes_and_duration_mapped() {
+    let mut response = make_ip_response(vec![make_ip_passage(94001, "Lisboa", "10:00", false)]);
+    response.origin_time = "2024-01-01 22:30".to_string();
+    response.destination_time = "02-01-2024 01:15".to_string();
+    response.duration = "02:45".to_string();
+
+    let journey = response.to_train_journey("720");
+
+    let day = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    assert_eq!(journey.origin_departure, day.and_hms_opt(22, 30, 0));
+    assert_eq!(
+        journey.destination_arrival,
+        day.succ_opt().unwrap().and_hms_opt(1, 15, 0)
+    );
+    assert_eq!(journey.duration.as_deref(), Some("02:45:00"));
+}
+
+#[test]
+fn test_ip_status_scheduled_when_nothing_passed() {
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "23:58", false),
+        make_ip_passage(94002, "Porto", "23:59", false),
+    ]);
+    let journey = response.to_train_journey("720");
+    assert_eq!(journey.status, JourneyStatus::Scheduled);
+}
+
+#[test]
+fn test_ip_status_in_progress_when_some_passed() {
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "10:00", true),
+        make_ip_passage(94002, "Porto", "12:00", false),
+    ]);
+    let journey = response.to_train_journey("720");
+    assert_eq!(journey.status, JourneyStatus::InProgress);
+}
+
+#[test]
+fn test_ip_status_completed_when_last_passed() {
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "10:00", true),
+        make_ip_passage(94002, "Porto", "12:00", true),
+    ]);
+    let journey = response.to_train_journey("720");
+    assert_eq!(journey.status, JourneyStatus::Completed);
+}
+
+#[test]
+fn test_ip_suppressed_train_is_cancelled() {
+    let mut response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "10:00", false),
+        make_ip_passage(94002, "Porto", "12:00", false),
+    ]);
+    response.status = "SUPRIMIDO".to_string();
+
+    let journey = response.to_train_journey("720");
+
+    assert_eq!(journey.status, JourneyStatus::Cancelled);
+    assert!(
+        journey
+            .stops
+            .iter()
+            .all(|s| s.status == StopStatus::Cancelled)
+    );
+}
+
+#[test]
+fn test_ip_passed_stop_actual_time_from_observations() {
+    let mut passed = make_ip_passage(94001, "Lisboa", "10:00", true);
+    passed.observations = "Hora Prevista:10:04".to_string();
+    let mut upcoming = make_ip_passage(94002, "Porto", "12:00", false);
+    upcoming.observations = "Hora Prevista:12:06".to_string();
+
+    let journey = make_ip_response(vec![passed, upcoming]).to_train_journey("720");
+
+    assert_eq!(journey.stops[0].actual_arrival.as_deref(), Some("10:04"));
+    assert_eq!(journey.stops[0].actual_departure.as_deref(), Some("10:04"));
+    assert_eq!(journey.stops[1].actual_arrival, None);
+    assert_eq!(journey.stops[1].predicted_time.as_deref(), Some("12:06"));
+    assert_eq!(journey.stops[1].delay_minutes, Some(6));
+}
+
+#[test]
+fn test_ip_platform_mapped_when_present() {
+    let mut passage = make_ip_passage(94001, "Lisboa", "10:00", false);
+    passage.platform = Some("3".to_string());
+
+    let journey = make_ip_response(vec![passage]).to_train_journey("720");
+    assert_eq!(journey.stops[0].platform.as_deref(), Some("3"));
+}
+
+// ---------------------------------------------------------------------------
+// IpTrainJourneyResponse::to_train_journey_at - fixed clock
+// ---------------------------------------------------------------------------
+
+fn lisbon(day: u32, hour: u32, minute: u32) -> chrono::DateTime<chrono_tz::Tz> {
+    let local = NaiveDate::from_ymd_opt(2026, 3, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap();
+    FixedClock::at_local(local).unwrap().now()
+}
+
+fn three_stop_ip_response() -> IpTrainJourneyResponse {
+    make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "10:00", true),
+        make_ip_passage(94002, "Coimbra", "11:00", false),
+        make_ip_passage(94003, "Porto", "12:00", false),
+    ])
+}
+
+#[test]
+fn test_ip_at_stop_when_next_stop_time_reached() {
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+    let journey =
+        three_stop_ip_response().to_train_journey_at("720", service_date, lisbon(14, 11, 30));
+
+    assert_eq!(journey.stops[1].status, StopStatus::AtStop);
+    assert_eq!(journey.stops[2].status, StopStatus::Scheduled);
+}
+
+#[test]
+fn test_ip_next_stop_scheduled_before_its_time() {
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+    let journey =
+        three_stop_ip_response().to_train_journey_at("720", service_date, lisbon(14, 10, 30));
+
+    assert_eq!(journey.stops[1].status, StopStatus::Scheduled);
+}
+
+#[test]
+fn test_ip_future_service_date_is_not_evaluated_against_today() {
+    // Late in the evening of the 13th, times on the 14th are still ahead.
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+    let journey =
+        three_stop_ip_response().to_train_journey_at("720", service_date, lisbon(13, 23, 0));
+
+    assert!(journey.stops.iter().all(|s| s.status != StopStatus::AtStop));
+}
+
+#[test]
+fn test_ip_past_service_date_times_are_in_the_past() {
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 13);
+    let journey =
+        three_stop_ip_response().to_train_journey_at("720", service_date, lisbon(14, 9, 0));
+
+    assert_eq!(journey.stops[1].status, StopStatus::AtStop);
+}
+
+#[test]
+fn test_ip_times_after_midnight_roll_over_to_next_day() {
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "23:30", true),
+        make_ip_passage(94002, "Coimbra", "00:40", false),
+    ]);
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+
+    // 23:50 on the service date: 00:40 belongs to the 15th and is still ahead.
+    let journey = response.to_train_journey_at("720", service_date, lisbon(14, 23, 50));
+    assert_eq!(journey.stops[1].status, StopStatus::Scheduled);
+
+    let journey = response.to_train_journey_at("720", service_date, lisbon(15, 0, 45));
+    assert_eq!(journey.stops[1].status, StopStatus::AtStop);
+}
+
+#[test]
+fn test_ip_call_at_midnight_is_a_real_time() {
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "23:30", true),
+        make_ip_passage(94002, "Coimbra", "00:00", false),
+        make_ip_passage(94003, "Porto", "00:40", false),
+    ]);
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+
+    let journey = response.to_train_journey_at("720", service_date, lisbon(15, 0, 10));
+    assert_eq!(journey.stops[1].status, StopStatus::AtStop);
+}
+
+#[test]
+fn test_ip_small_step_back_stays_on_the_same_day() {
+    // A stop listed a few minutes before the previous one is out of order,
+    // not past midnight.
+    let response = make_ip_response(vec![
+        make_ip_passage(94001, "Lisboa", "10:00", true),
+        make_ip_passage(94002, "Coimbra", "09:55", false),
+        make_ip_passage(94003, "Porto", "12:00", false),
+    ]);
+    let service_date = NaiveDate::from_ymd_opt(2026, 3, 14);
+
+    let journey = response.to_train_journey_at("720", service_date, lisbon(14, 10, 30));
+    assert_eq!(journey.stops[1].status, StopStatus::AtStop);
+}
+
+// ---------------------------------------------------------------------------
+// CpTrainTimetable::to_train_journey_on - dated origin and destination
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cp_journey_on_fills_origin_and_destination_datetimes() {
+    let mut origin = make_stop("94-30007", "Lisboa");
+    origin.arrival = None;
+    origin.departure = Some("22:30".to_string());
+    let mut destination = make_stop("94-2006", "Porto");
+    destination.arrival = Some("01:15".to_string());
+    destination.departure = None;
+    let timetable = make_timetable("SCHEDULED", vec![origin, destination]);
+
+    let day = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+    let journey = timetable.to_train_journey_on(day);
+
+    assert_eq!(journey.origin_departure, day.and_hms_opt(22, 30, 0));
+    assert_eq!(
+        journey.destination_arrival,
+        day.succ_opt().unwrap().and_hms_opt(1, 15, 0)
+    );
+}
+
 // ---------------------------------------------------------------------------
 // TrainEntry::delay_minutes - parsing from observations string
 // ---------------------------------------------------------------------------
@@ -1,6 +1,7 @@
 //! Tests for domain models
 
 use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::occupancy::Occupancy;
 use comboios_core::domain::station::Station;
 
 fn make_stop(num: usize, status: StopStatus) -> JourneyStop {
@@ -27,6 +28,7 @@ fn make_stop(num: usize, status: StopStatus) -> JourneyStop {
         stop_number: num,
         has_passed: Some(status == StopStatus::Departed || status == StopStatus::Passed),
         predicted_time: None,
+        prediction_confidence: None,
     }
 }
 
@@ -48,9 +50,15 @@ fn make_journey(stops: Vec<JourneyStop>) -> TrainJourney {
         stops,
         status: JourneyStatus::InProgress,
         delay_minutes: Some(5),
+        occupancy: Occupancy::Unknown,
         operator: "CP".to_string(),
         observations: None,
         duration: Some("02:30".to_string()),
+        origin_departure: None,
+        destination_arrival: None,
+        predicted_arrival: None,
+        prediction_confidence: None,
+        live_position: None,
     }
 }
 
@@ -189,3 +197,104 @@ fn test_stop_status_serialization() {
     let json = serde_json::to_string(&status).unwrap();
     assert_eq!(json, "\"AT_STOP\"");
 }
+
+#[test]
+fn test_occupancy_serialization() {
+    let json = serde_json::to_string(&Occupancy::Medium).unwrap();
+    assert_eq!(json, "\"MEDIUM\"");
+
+    let deserialized: Occupancy = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, Occupancy::Medium);
+}
+
+#[test]
+fn test_journey_without_occupancy_deserializes_as_unknown() {
+    let mut value = serde_json::to_value(make_journey(vec![])).unwrap();
+    value.as_object_mut().unwrap().remove("occupancy");
+
+    let journey: TrainJourney = serde_json::from_value(value).unwrap();
+    assert_eq!(journey.occupancy, Occupancy::Unknown);
+}
+
+fn timed_stop(num: usize, times: [&str; 4]) -> JourneyStop {
+    let mut stop = make_stop(num, StopStatus::Departed);
+    let [
+        scheduled_arrival,
+        actual_arrival,
+        scheduled_departure,
+        actual_departure,
+    ] = times;
+    stop.scheduled_arrival = scheduled_arrival.to_string();
+    stop.actual_arrival = (!actual_arrival.is_empty()).then(|| actual_arrival.to_string());
+    stop.scheduled_departure = scheduled_departure.to_string();
+    stop.actual_departure = (!actual_departure.is_empty()).then(|| actual_departure.to_string());
+    stop
+}
+
+fn segment_journey() -> TrainJourney {
+    make_journey(vec![
+        timed_stop(1, ["10:00", "", "10:00", "10:02"]),
+        // Lost 6 minutes running, then 2 more overstaying the stop.
+        timed_stop(2, ["10:30", "10:38", "10:32", "10:42"]),
+        // Made up 4 minutes running.
+        timed_stop(3, ["11:00", "11:06", "11:00", ""]),
+        timed_stop(4, ["11:30", "", "11:30", ""]),
+    ])
+}
+
+#[test]
+fn test_segments_between_consecutive_stops() {
+    let segments = segment_journey().segments();
+
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments[0].from.code, "ST1");
+    assert_eq!(segments[0].to.code, "ST2");
+    assert_eq!(segments[0].stop_number, 2);
+    assert_eq!(segments[0].scheduled_running_minutes, 30);
+    assert_eq!(segments[0].actual_running_minutes, Some(36));
+    assert_eq!(segments[0].delay_gained_minutes, Some(6));
+    assert_eq!(segments[0].scheduled_dwell_minutes, 2);
+    assert_eq!(segments[0].actual_dwell_minutes, Some(4));
+    assert_eq!(segments[0].dwell_overrun_minutes(), Some(2));
+
+    assert_eq!(segments[1].delay_gained_minutes, Some(-4));
+}
+
+#[test]
+fn test_segments_not_yet_run_have_no_actuals() {
+    let segments = segment_journey().segments();
+
+    assert_eq!(segments[2].scheduled_running_minutes, 30);
+    assert_eq!(segments[2].actual_running_minutes, None);
+    assert_eq!(segments[2].delay_gained_minutes, None);
+    assert_eq!(segments[2].actual_dwell_minutes, None);
+}
+
+#[test]
+fn test_worst_and_best_segment() {
+    let journey = segment_journey();
+
+    assert_eq!(journey.worst_segment().unwrap().to.code, "ST2");
+    assert_eq!(journey.best_segment().unwrap().to.code, "ST3");
+    assert_eq!(journey.delay_gained_minutes(), 4);
+}
+
+#[test]
+fn test_segments_across_midnight() {
+    let journey = make_journey(vec![
+        timed_stop(1, ["23:50", "", "23:50", "23:55"]),
+        timed_stop(2, ["00:10", "00:20", "00:10", ""]),
+    ]);
+    let segments = journey.segments();
+
+    assert_eq!(segments[0].scheduled_running_minutes, 20);
+    assert_eq!(segments[0].actual_running_minutes, Some(25));
+    assert_eq!(segments[0].delay_gained_minutes, Some(5));
+}
+
+#[test]
+fn test_segments_of_single_stop_journey_is_empty() {
+    let journey = make_journey(vec![make_stop(1, StopStatus::Scheduled)]);
+    assert!(journey.segments().is_empty());
+    assert!(journey.worst_segment().is_none());
+}
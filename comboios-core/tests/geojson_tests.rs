@@ -0,0 +1,140 @@
+//! Tests for the `GeoJSON` export of journeys and boards.
+
+use comboios_core::domain::journey::{JourneyStatus, StopStatus, TrainJourney};
+use comboios_core::domain::position::Coordinates;
+use comboios_core::domain::station::StationIndex;
+use comboios_core::domain::station_timetable::{StationBoard, StationTimetable};
+use serde_json::{Value, json};
+
+mod common;
+use common::{station, stop};
+
+/// A (10:00) → B (10:20–10:22) → X (no coordinates) → C (10:40), left A
+/// and running late.
+fn ic_520() -> TrainJourney {
+    let mut stops = vec![
+        stop(1, "94-1", "", "10:00"),
+        stop(2, "94-2", "10:20", "10:22"),
+        stop(3, "94-9", "10:30", "10:30"),
+        stop(4, "94-3", "10:40", ""),
+    ];
+    stops[0].status = StopStatus::Departed;
+    stops[0].actual_departure = Some("10:04".to_string());
+    stops[0].platform = Some("2".to_string());
+    stops[1].delay_minutes = Some(4);
+    TrainJourney {
+        status: JourneyStatus::InProgress,
+        delay_minutes: Some(4),
+        ..common::journey("520", stops)
+    }
+}
+
+fn index() -> StationIndex {
+    let mut index = StationIndex::new(["94-1", "94-2", "94-3", "94-9"].map(station));
+    index.set_coordinates("94-1", Coordinates::new(38.0, -9.0));
+    index.set_coordinates("94-2", Coordinates::new(39.0, -9.0));
+    index.set_coordinates("94-3", Coordinates::new(39.0, -8.0));
+    index
+}
+
+fn kinds(collection: &Value) -> Vec<&str> {
+    collection["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["properties"]["kind"].as_str().unwrap())
+        .collect()
+}
+
+/// Arriving 10:20 and leaving 10:22 from platform 1, 4 minutes late.
+fn row(train_number: u64, origin: &str, destination: &str) -> StationTimetable {
+    StationTimetable {
+        departure_time: Some("10:22".to_string()),
+        arrival_time: Some("10:20".to_string()),
+        platform: Some("1".to_string()),
+        delay: Some(4),
+        ..common::row(train_number, origin, destination)
+    }
+}
+
+#[test]
+fn journey_is_a_route_and_its_stops() {
+    let collection = ic_520().to_geojson(&index());
+
+    assert_eq!(collection["type"], "FeatureCollection");
+    // The stop without coordinates is left out of both.
+    assert_eq!(kinds(&collection), ["route", "stop", "stop", "stop"]);
+
+    let route = &collection["features"][0];
+    assert_eq!(route["geometry"]["type"], "LineString");
+    assert_eq!(
+        route["geometry"]["coordinates"],
+        json!([[-9.0, 38.0], [-9.0, 39.0], [-8.0, 39.0]])
+    );
+    assert_eq!(route["properties"]["status"], "IN_PROGRESS");
+    assert_eq!(route["properties"]["delay_minutes"], 4);
+
+    let origin = &collection["features"][1];
+    assert_eq!(
+        origin["geometry"],
+        json!({"type": "Point", "coordinates": [-9.0, 38.0]})
+    );
+    assert_eq!(origin["properties"]["status"], "DEPARTED");
+    assert_eq!(origin["properties"]["platform"], "2");
+    assert_eq!(origin["properties"]["scheduled_arrival"], Value::Null);
+    assert_eq!(origin["properties"]["actual_departure"], "10:04");
+    assert_eq!(collection["features"][2]["properties"]["delay_minutes"], 4);
+    assert_eq!(collection["features"][3]["properties"]["stop_number"], 4);
+}
+
+#[test]
+fn live_position_is_a_train_point() {
+    let mut journey = ic_520();
+    journey.live_position = Some(Coordinates::new(38.5, -9.0));
+
+    let collection = journey.to_geojson(&index());
+
+    assert_eq!(kinds(&collection).last(), Some(&"train"));
+    let train = collection["features"].as_array().unwrap().last().unwrap();
+    assert_eq!(train["geometry"]["coordinates"], json!([-9.0, 38.5]));
+}
+
+#[test]
+fn journey_without_coordinates_has_no_features() {
+    let collection = ic_520().to_geojson(&StationIndex::default());
+
+    assert_eq!(collection["features"], json!([]));
+}
+
+#[test]
+fn board_rows_run_through_the_station() {
+    let board = StationBoard {
+        station_id: "94-2".to_string(),
+        station_name: "Station 94-2".to_string(),
+        trains: vec![
+            row(520, "94-1", "943"),
+            row(521, "94-2", "94-3"),
+            row(522, "94-7", "94-8"),
+        ],
+    };
+
+    let collection = board.to_geojson(&index());
+
+    assert_eq!(kinds(&collection), ["station", "train", "train", "train"]);
+    let features = collection["features"].as_array().unwrap();
+    assert_eq!(features[0]["geometry"]["coordinates"], json!([-9.0, 39.0]));
+    assert_eq!(
+        features[1]["geometry"]["coordinates"],
+        json!([[-9.0, 38.0], [-9.0, 39.0], [-8.0, 39.0]])
+    );
+    assert_eq!(features[1]["properties"]["train_number"], 520);
+    assert_eq!(features[1]["properties"]["platform"], "1");
+    assert_eq!(features[1]["properties"]["delay"], 4);
+    // Starting here, the line is not doubled back on itself.
+    assert_eq!(
+        features[2]["geometry"]["coordinates"],
+        json!([[-9.0, 39.0], [-8.0, 39.0]])
+    );
+    // Neither end is known: the row stays, off the map.
+    assert_eq!(features[3]["geometry"], Value::Null);
+}
@@ -0,0 +1,259 @@
+//! Tests for the GTFS static export.
+
+use std::io::{Cursor, Read};
+
+use chrono::NaiveDate;
+use comboios_core::Error;
+use comboios_core::domain::journey::{StopStatus, TrainJourney};
+use comboios_core::domain::position::Coordinates;
+use comboios_core::domain::station::StationIndex;
+use comboios_core::gtfs::{self, GtfsFeed};
+
+mod common;
+use common::{journey, station, stop};
+
+/// A (10:00) → B (10:20–10:22) → C (10:40).
+fn ic_520() -> TrainJourney {
+    journey(
+        "520",
+        vec![
+            stop(1, "94-1", "", "10:00"),
+            stop(2, "94-2", "10:20", "10:22"),
+            stop(3, "94-3", "10:40", ""),
+        ],
+    )
+}
+
+fn index() -> StationIndex {
+    let mut index = StationIndex::new(["94-1", "94-2", "94-3"].map(station));
+    index.set_coordinates("94-1", Coordinates::new(38.0, -9.0));
+    index.set_coordinates("94-2", Coordinates::new(39.0, -9.0));
+    index.set_coordinates("94-3", Coordinates::new(39.0, -8.0));
+    index
+}
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+}
+
+#[test]
+fn feed_has_one_trip_per_train_and_date() {
+    let feed = GtfsFeed::from_journeys(&index(), &[(day(14), ic_520()), (day(15), ic_520())]);
+
+    assert_eq!(feed.agencies.len(), 1);
+    assert_eq!(feed.agencies[0].agency_id, "CP");
+    assert_eq!(feed.stops.len(), 3);
+    assert_eq!(feed.stops[0].stop_id, "941");
+    assert_eq!(feed.stops[0].stop_lat, Some(38.0));
+    assert_eq!(feed.routes.len(), 1);
+    assert_eq!(feed.routes[0].route_short_name, "IC");
+    assert_eq!(feed.routes[0].route_long_name, "Intercidades");
+    assert_eq!(feed.routes[0].route_type, gtfs::ROUTE_TYPE_RAIL);
+
+    let trips: Vec<_> = feed.trips.iter().map(|t| t.trip_id.as_str()).collect();
+    assert_eq!(trips, ["520_20260314", "520_20260315"]);
+    assert_eq!(feed.trips[0].service_id, "20260314");
+    assert_eq!(feed.trips[0].trip_headsign, "Station 94-3");
+    assert_eq!(feed.calendar_dates.len(), 2);
+    assert_eq!(feed.stop_times.len(), 6);
+
+    feed.validate().unwrap();
+}
+
+#[test]
+fn origin_and_destination_borrow_their_only_time() {
+    let feed = GtfsFeed::from_journeys(&index(), &[(day(14), ic_520())]);
+    let times: Vec<_> = feed
+        .stop_times
+        .iter()
+        .map(|s| (s.arrival_time.as_str(), s.departure_time.as_str()))
+        .collect();
+
+    assert_eq!(
+        times,
+        [
+            ("10:00:00", "10:00:00"),
+            ("10:20:00", "10:22:00"),
+            ("10:40:00", "10:40:00"),
+        ]
+    );
+}
+
+#[test]
+fn trips_past_midnight_keep_counting_hours() {
+    let night = journey(
+        "16730",
+        vec![
+            stop(1, "94-1", "", "23:30"),
+            stop(2, "94-2", "23:55", "00:02"),
+            stop(3, "94-3", "00:25", ""),
+        ],
+    );
+    let feed = GtfsFeed::from_journeys(&index(), &[(day(14), night)]);
+
+    let times: Vec<_> = feed
+        .stop_times
+        .iter()
+        .map(|s| s.departure_time.as_str())
+        .collect();
+    assert_eq!(times, ["23:30:00", "24:02:00", "24:25:00"]);
+    feed.validate().unwrap();
+}
+
+#[test]
+fn stops_without_coordinates_fail_validation() {
+    let index = StationIndex::new(["94-1", "94-2", "94-3"].map(station));
+    let feed = GtfsFeed::from_journeys(&index, &[(day(14), ic_520())]);
+
+    let err = feed.validate().unwrap_err();
+    assert!(
+        matches!(&err, Error::GtfsError(msg) if msg.contains("stop 941 has no coordinates")),
+        "got {err:?}"
+    );
+    assert!(feed.write_zip(Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn dangling_references_fail_validation() {
+    let mut feed = GtfsFeed::from_journeys(&index(), &[(day(14), ic_520())]);
+    feed.calendar_dates.clear();
+    feed.stops.pop();
+
+    let Err(Error::GtfsError(msg)) = feed.validate() else {
+        panic!("expected a GTFS error");
+    };
+    assert!(msg.contains("unknown service 20260314"), "{msg}");
+    assert!(msg.contains("unknown stop 943"), "{msg}");
+}
+
+#[test]
+fn short_and_duplicate_journeys_are_left_out() {
+    let short = journey("999", vec![stop(1, "94-1", "", "10:00")]);
+    let feed = GtfsFeed::from_journeys(
+        &index(),
+        &[(day(14), ic_520()), (day(14), ic_520()), (day(14), short)],
+    );
+
+    assert_eq!(feed.trips.len(), 1);
+    assert_eq!(feed.stop_times.len(), 3);
+}
+
+#[test]
+fn zip_holds_every_table() {
+    let feed = GtfsFeed::from_journeys(&index(), &[(day(14), ic_520())]);
+    let mut buffer = Cursor::new(Vec::new());
+    feed.write_zip(&mut buffer).unwrap();
+
+    let mut archive = zip::ZipArchive::new(buffer).unwrap();
+    let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "agency.txt",
+            "calendar_dates.txt",
+            "routes.txt",
+            "stop_times.txt",
+            "stops.txt",
+            "trips.txt",
+        ]
+    );
+
+    let mut trips = String::new();
+    archive
+        .by_name("trips.txt")
+        .unwrap()
+        .read_to_string(&mut trips)
+        .unwrap();
+    assert_eq!(
+        trips,
+        "route_id,service_id,trip_id,trip_short_name,trip_headsign\n\
+         CP-IC,20260314,520_20260314,520,Station 94-3\n"
+    );
+
+    let mut agency = String::new();
+    archive
+        .by_name("agency.txt")
+        .unwrap()
+        .read_to_string(&mut agency)
+        .unwrap();
+    assert!(agency.contains("CP,CP - Comboios de Portugal,https://www.cp.pt,Europe/Lisbon"));
+}
+
+#[test]
+fn fields_with_commas_are_quoted() {
+    let mut porto = station("94-3");
+    porto.designation = "Porto, \"Campanhã\"".to_string();
+    let mut index = StationIndex::new([station("94-1"), station("94-2"), porto]);
+    index.set_coordinates("94-1", Coordinates::new(38.0, -9.0));
+    index.set_coordinates("94-2", Coordinates::new(39.0, -9.0));
+    index.set_coordinates("94-3", Coordinates::new(39.0, -8.0));
+    let feed = GtfsFeed::from_journeys(&index, &[(day(14), ic_520())]);
+
+    let stops = feed
+        .files()
+        .into_iter()
+        .find(|(name, _)| *name == "stops.txt")
+        .unwrap()
+        .1;
+    assert!(
+        stops.contains("943,\"Porto, \"\"Campanhã\"\"\",39.000000,-8.000000"),
+        "{stops}"
+    );
+}
+
+#[test]
+fn ids_are_stable() {
+    assert_eq!(gtfs::stop_id("94-31039"), "9431039");
+    assert_eq!(gtfs::trip_id(" 520 ", day(14)), "520_20260314");
+}
+
+#[test]
+fn stop_updates_follow_actual_and_predicted_times() {
+    let mut journey = ic_520();
+    journey.stops[0].status = StopStatus::Departed;
+    journey.stops[0].actual_departure = Some("10:03".to_string());
+    journey.stops[1].predicted_time = Some("10:24".to_string());
+    journey.stops[2].predicted_time = Some("10:43".to_string());
+    journey.stops[2].status = StopStatus::Cancelled;
+
+    let updates = gtfs::stop_updates(&journey, day(14));
+    let delays: Vec<_> = updates
+        .iter()
+        .map(|u| (u.arrival.delay_minutes, u.departure.delay_minutes))
+        .collect();
+    assert_eq!(
+        delays,
+        [(None, Some(3)), (Some(4), Some(2)), (Some(3), Some(3))]
+    );
+
+    let sequences: Vec<_> = updates
+        .iter()
+        .map(|u| (u.stop_sequence, u.stop_id.as_str()))
+        .collect();
+    assert_eq!(sequences, [(1, "941"), (2, "942"), (3, "943")]);
+    assert_eq!(
+        updates[1].arrival.expected(),
+        day(14).and_hms_opt(10, 24, 0)
+    );
+    assert!(!updates[1].skipped);
+    assert!(updates[2].skipped);
+}
+
+#[test]
+fn stop_updates_share_the_static_route_id_and_times() {
+    let journey = ic_520();
+    let feed = GtfsFeed::from_journeys(&index(), &[(day(14), journey.clone())]);
+    let updates = gtfs::stop_updates(&journey, day(14));
+
+    assert_eq!(
+        gtfs::route_id(&journey.operator, &journey.service_type),
+        feed.trips[0].route_id
+    );
+    assert_eq!(updates.len(), feed.stop_times.len());
+    assert_eq!(
+        updates[2].arrival.scheduled,
+        day(14).and_hms_opt(10, 40, 0).unwrap()
+    );
+    assert!(updates.iter().all(|u| u.arrival.delay_minutes.is_none()));
+}
@@ -0,0 +1,183 @@
+//! Tests for the connection-scan journey planner.
+
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::planner::{Connection, PlannerConfig, search};
+
+mod common;
+use common::{at, day, station};
+
+/// A train calling at `calls` (station, time) with no dwell.
+fn train(number: &str, calls: &[(&str, &str)]) -> Vec<Connection> {
+    calls
+        .windows(2)
+        .map(|pair| Connection {
+            train_number: number.to_string(),
+            service_type: "R|Regional".to_string(),
+            operator: "CP".to_string(),
+            from: station(pair[0].0),
+            to: station(pair[1].0),
+            departure: at(pair[0].1),
+            arrival: at(pair[1].1),
+            departure_platform: None,
+            arrival_platform: None,
+        })
+        .collect()
+}
+
+/// A slow direct train A→D, a faster change at B, and an even faster
+/// route changing at B and C that needs a quick change at B.
+fn network() -> Vec<Connection> {
+    [
+        train("10", &[("94-1", "08:00"), ("94-4", "12:00")]),
+        train("20", &[("94-1", "08:10"), ("94-2", "09:00")]),
+        train("30", &[("94-2", "09:10"), ("94-4", "10:00")]),
+        train("40", &[("94-2", "09:03"), ("94-3", "09:30")]),
+        train("50", &[("94-3", "09:40"), ("94-4", "09:50")]),
+    ]
+    .concat()
+}
+
+#[test]
+fn returns_pareto_set_by_transfers() {
+    let trips = search(
+        &network(),
+        "94-1",
+        "94-4",
+        at("07:30"),
+        &PlannerConfig::new(),
+    );
+
+    let summary: Vec<(usize, NaiveDateTime)> =
+        trips.iter().map(|t| (t.transfers, t.arrival)).collect();
+    assert_eq!(summary, vec![(0, at("12:00")), (1, at("10:00"))]);
+
+    let change = &trips[1];
+    assert_eq!(change.legs.len(), 2);
+    assert_eq!(change.legs[0].train_number, "20");
+    assert_eq!(change.legs[0].to.code, "94-2");
+    assert_eq!(change.legs[1].train_number, "30");
+    assert_eq!(change.departure, at("08:10"));
+    assert_eq!(change.duration_minutes(), 110);
+}
+
+#[test]
+fn per_station_transfer_time_enables_quick_changes() {
+    let config = PlannerConfig::new().transfer_time("942", Duration::from_mins(2));
+    let trips = search(&network(), "94-1", "94-4", at("07:30"), &config);
+
+    let last = trips.last().unwrap();
+    assert_eq!(last.transfers, 2);
+    assert_eq!(last.arrival, at("09:50"));
+    let numbers: Vec<&str> = last.legs.iter().map(|l| l.train_number.as_str()).collect();
+    assert_eq!(numbers, vec!["20", "40", "50"]);
+}
+
+#[test]
+fn max_transfers_limits_the_search() {
+    let config = PlannerConfig::new()
+        .transfer_time("94-2", Duration::from_mins(2))
+        .max_transfers(1);
+    let trips = search(&network(), "94-1", "94-4", at("07:30"), &config);
+
+    assert!(trips.iter().all(|t| t.transfers <= 1));
+    assert_eq!(trips.last().unwrap().arrival, at("10:00"));
+}
+
+#[test]
+fn trains_before_the_start_time_are_ignored() {
+    let trips = search(
+        &network(),
+        "94-1",
+        "94-4",
+        at("08:05"),
+        &PlannerConfig::new(),
+    );
+
+    assert_eq!(trips.len(), 1);
+    assert_eq!(trips[0].legs[0].train_number, "20");
+}
+
+#[test]
+fn staying_on_board_needs_no_transfer_time() {
+    let connections = train(
+        "60",
+        &[("94-1", "08:00"), ("94-2", "08:30"), ("94-3", "08:31")],
+    );
+    let trips = search(
+        &connections,
+        "94-1",
+        "94-3",
+        at("08:00"),
+        &PlannerConfig::new(),
+    );
+
+    assert_eq!(trips.len(), 1);
+    assert_eq!(trips[0].transfers, 0);
+    assert_eq!(trips[0].legs[0].from.code, "94-1");
+    assert_eq!(trips[0].legs[0].to.code, "94-3");
+}
+
+#[test]
+fn unreachable_destination_yields_nothing() {
+    let trips = search(
+        &network(),
+        "94-4",
+        "94-1",
+        at("07:00"),
+        &PlannerConfig::new(),
+    );
+    assert!(trips.is_empty());
+}
+
+/// Stop `num` at platform `num`.
+fn stop(num: usize, code: &str, arrival: &str, departure: &str) -> JourneyStop {
+    JourneyStop {
+        platform: Some(num.to_string()),
+        ..common::stop(num, code, arrival, departure)
+    }
+}
+
+fn journey(stops: Vec<JourneyStop>) -> TrainJourney {
+    common::journey("520", stops)
+}
+
+#[test]
+fn connections_from_journey_roll_over_midnight() {
+    let journey = journey(vec![
+        stop(1, "94-1", "23:30", "23:30"),
+        stop(2, "94-2", "23:55", "23:57"),
+        stop(3, "94-3", "00:20", "00:20"),
+    ]);
+    let connections = Connection::from_journey(&journey, day());
+
+    assert_eq!(connections.len(), 2);
+    assert_eq!(connections[0].departure, at("23:30"));
+    assert_eq!(connections[0].arrival, at("23:55"));
+    assert_eq!(connections[1].departure, at("23:57"));
+    assert_eq!(
+        connections[1].arrival,
+        day().succ_opt().unwrap().and_hms_opt(0, 20, 0).unwrap()
+    );
+    assert_eq!(connections[1].departure_platform.as_deref(), Some("2"));
+    assert_eq!(connections[1].arrival_platform.as_deref(), Some("3"));
+}
+
+#[test]
+fn cancelled_stops_and_journeys_have_no_connections() {
+    let mut skipping = journey(vec![
+        stop(1, "94-1", "10:00", "10:00"),
+        stop(2, "94-2", "10:20", "10:21"),
+        stop(3, "94-3", "10:40", "10:40"),
+    ]);
+    skipping.stops[1].status = StopStatus::Cancelled;
+    let connections = Connection::from_journey(&skipping, day());
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0].from.code, "94-1");
+    assert_eq!(connections[0].to.code, "94-3");
+
+    skipping.status = JourneyStatus::Cancelled;
+    assert!(Connection::from_journey(&skipping, day()).is_empty());
+}
@@ -0,0 +1,182 @@
+//! Tests for train position estimation.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::position::{Coordinates, PositionSource, TrainPosition};
+use comboios_core::domain::station::StationIndex;
+
+mod common;
+use common::station;
+
+fn stop(num: usize, code: &str, time: &str, status: StopStatus) -> JourneyStop {
+    JourneyStop {
+        has_passed: Some(matches!(status, StopStatus::Departed | StopStatus::Passed)),
+        status,
+        ..common::stop(num, code, time, time)
+    }
+}
+
+/// Due north from A (10:00) to B (10:20), then due east to C (10:40).
+fn journey(statuses: [StopStatus; 3]) -> TrainJourney {
+    let [a, b, c] = statuses;
+    let stops = vec![
+        stop(1, "94-1", "10:00", a),
+        stop(2, "94-2", "10:20", b),
+        stop(3, "94-3", "10:40", c),
+    ];
+    TrainJourney {
+        service_type: "R|Regional".to_string(),
+        status: JourneyStatus::InProgress,
+        ..common::journey("520", stops)
+    }
+}
+
+fn index() -> StationIndex {
+    let mut index = StationIndex::new(["94-1", "94-2", "94-3"].map(station));
+    index.set_coordinates("94-1", Coordinates::new(38.0, -9.0));
+    index.set_coordinates("94-2", Coordinates::new(39.0, -9.0));
+    index.set_coordinates("94-3", Coordinates::new(39.0, -8.0));
+    index
+}
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 14).unwrap()
+}
+
+fn at(hour: u32, minute: u32) -> NaiveDateTime {
+    day().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn between_a_and_b() -> TrainJourney {
+    journey([
+        StopStatus::Departed,
+        StopStatus::Scheduled,
+        StopStatus::Scheduled,
+    ])
+}
+
+#[test]
+fn interpolates_between_last_departed_and_next_stop() {
+    let position = TrainPosition::estimate(&between_a_and_b(), &index(), day(), at(10, 5)).unwrap();
+
+    assert_eq!(position.source, PositionSource::Interpolated);
+    assert!((position.coordinates.latitude - 38.25).abs() < 1e-9);
+    assert!((position.coordinates.longitude + 9.0).abs() < 1e-9);
+
+    let segment = position.segment.unwrap();
+    assert_eq!(segment.from.code, "94-1");
+    assert_eq!(segment.to.code, "94-2");
+    assert!((segment.progress - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn heading_points_to_next_stop() {
+    let position = TrainPosition::estimate(&between_a_and_b(), &index(), day(), at(10, 5)).unwrap();
+    assert!(position.heading.unwrap().abs() < 1e-6);
+}
+
+#[test]
+fn delay_shifts_the_interpolation() {
+    let mut journey = between_a_and_b();
+    journey.delay_minutes = Some(10);
+
+    let position = TrainPosition::estimate(&journey, &index(), day(), at(10, 15)).unwrap();
+    assert!((position.segment.unwrap().progress - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn progress_is_clamped_when_overdue() {
+    let position =
+        TrainPosition::estimate(&between_a_and_b(), &index(), day(), at(10, 50)).unwrap();
+    assert!((position.segment.unwrap().progress - 1.0).abs() < 1e-9);
+    assert!((position.coordinates.latitude - 39.0).abs() < 1e-9);
+}
+
+#[test]
+fn train_at_stop_is_placed_at_station() {
+    let journey = journey([
+        StopStatus::Departed,
+        StopStatus::AtStop,
+        StopStatus::Scheduled,
+    ]);
+    let position = TrainPosition::estimate(&journey, &index(), day(), at(10, 21)).unwrap();
+
+    assert_eq!(position.source, PositionSource::Station);
+    assert_eq!(position.coordinates, Coordinates::new(39.0, -9.0));
+    assert!(position.segment.is_none());
+    assert!(position.heading.is_none());
+}
+
+#[test]
+fn scheduled_and_completed_trains_are_at_their_terminus() {
+    let scheduled = journey([
+        StopStatus::Scheduled,
+        StopStatus::Scheduled,
+        StopStatus::Scheduled,
+    ]);
+    let position = TrainPosition::estimate(&scheduled, &index(), day(), at(9, 0)).unwrap();
+    assert_eq!(position.coordinates, Coordinates::new(38.0, -9.0));
+
+    let mut completed = journey([StopStatus::Passed, StopStatus::Passed, StopStatus::Passed]);
+    completed.status = JourneyStatus::Completed;
+    let position = TrainPosition::estimate(&completed, &index(), day(), at(11, 0)).unwrap();
+    assert_eq!(position.coordinates, Coordinates::new(39.0, -8.0));
+}
+
+#[test]
+fn live_coordinates_take_precedence() {
+    let mut journey = between_a_and_b();
+    journey.live_position = Some(Coordinates::new(38.5, -9.0));
+
+    let position = TrainPosition::estimate(&journey, &index(), day(), at(10, 5)).unwrap();
+    assert_eq!(position.source, PositionSource::Live);
+    assert_eq!(position.coordinates, Coordinates::new(38.5, -9.0));
+    assert_eq!(position.segment.unwrap().to.code, "94-2");
+}
+
+#[test]
+fn unknown_station_coordinates_yield_none() {
+    let position = TrainPosition::estimate(
+        &between_a_and_b(),
+        &StationIndex::default(),
+        day(),
+        at(10, 5),
+    );
+    assert!(position.is_none());
+}
+
+#[test]
+fn cancelled_train_has_no_position() {
+    let mut journey = between_a_and_b();
+    journey.status = JourneyStatus::Cancelled;
+    assert!(TrainPosition::estimate(&journey, &index(), day(), at(10, 5)).is_none());
+}
+
+#[test]
+fn coordinates_parse_rejects_placeholders() {
+    assert_eq!(
+        Coordinates::parse(Some("38.7"), Some("-9.1")),
+        Some(Coordinates::new(38.7, -9.1))
+    );
+    assert_eq!(Coordinates::parse(Some("0"), Some("0")), None);
+    assert_eq!(Coordinates::parse(Some("abc"), Some("-9.1")), None);
+    assert_eq!(Coordinates::parse(None, Some("-9.1")), None);
+}
+
+#[test]
+fn bearing_and_distance() {
+    let a = Coordinates::new(39.0, -9.0);
+    let b = Coordinates::new(39.0, -8.0);
+    assert!((a.bearing_to(&b) - 90.0).abs() < 1.0);
+    assert!((a.distance_km(&b) - 86.4).abs() < 1.0);
+}
+
+#[test]
+fn station_index_coordinates_accept_ip_ids() {
+    let mut index = StationIndex::default();
+    index.set_coordinates("94-31039", Coordinates::new(38.77, -9.1));
+    assert_eq!(
+        index.coordinates("9431039"),
+        Some(Coordinates::new(38.77, -9.1))
+    );
+}
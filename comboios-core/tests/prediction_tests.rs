@@ -0,0 +1,232 @@
+//! Tests for delay propagation and arrival-time prediction.
+
+use comboios_core::domain::journey::{
+    JourneyStatus, JourneyStop, PredictionConfidence, StopStatus, TrainJourney,
+};
+use comboios_core::prediction::predict;
+
+mod common;
+
+fn stop(num: usize, arrival: &str, departure: &str, status: StopStatus) -> JourneyStop {
+    JourneyStop {
+        has_passed: Some(matches!(
+            status,
+            StopStatus::Departed | StopStatus::Passed | StopStatus::AtStop
+        )),
+        status,
+        ..common::stop(num, &format!("94-{num}"), arrival, departure)
+    }
+}
+
+fn departed(num: usize, departure: &str, actual: &str) -> JourneyStop {
+    let mut s = stop(num, departure, departure, StopStatus::Departed);
+    s.actual_departure = Some(actual.to_string());
+    s
+}
+
+fn journey(status: JourneyStatus, stops: Vec<JourneyStop>) -> TrainJourney {
+    TrainJourney {
+        status,
+        ..common::journey("520", stops)
+    }
+}
+
+/// Departed 10 minutes late; 40 and 59 minute runs with a 1 minute stop.
+fn late_in_progress() -> TrainJourney {
+    journey(
+        JourneyStatus::InProgress,
+        vec![
+            departed(1, "10:00", "10:10"),
+            stop(2, "10:40", "10:41", StopStatus::Scheduled),
+            stop(3, "11:40", "11:40", StopStatus::Scheduled),
+        ],
+    )
+}
+
+#[test]
+fn delay_propagates_with_running_recovery() {
+    let mut j = late_in_progress();
+    predict(&mut j);
+
+    assert_eq!(j.stops[1].predicted_time.as_deref(), Some("10:48"));
+    assert_eq!(j.stops[2].predicted_time.as_deref(), Some("11:46"));
+    assert_eq!(j.predicted_arrival.as_deref(), Some("11:46"));
+}
+
+#[test]
+fn passed_stops_are_not_predicted() {
+    let mut j = late_in_progress();
+    predict(&mut j);
+    assert_eq!(j.stops[0].predicted_time, None);
+    assert_eq!(j.stops[0].prediction_confidence, None);
+}
+
+#[test]
+fn confidence_decreases_with_horizon() {
+    let mut j = late_in_progress();
+    predict(&mut j);
+
+    assert_eq!(
+        j.stops[1].prediction_confidence,
+        Some(PredictionConfidence::Medium)
+    );
+    assert_eq!(
+        j.stops[2].prediction_confidence,
+        Some(PredictionConfidence::Low)
+    );
+    assert_eq!(j.prediction_confidence, Some(PredictionConfidence::Low));
+}
+
+#[test]
+fn long_dwell_absorbs_delay() {
+    let mut j = journey(
+        JourneyStatus::InProgress,
+        vec![
+            departed(1, "10:00", "10:10"),
+            stop(2, "10:40", "10:50", StopStatus::Scheduled),
+            stop(3, "11:20", "11:20", StopStatus::Scheduled),
+        ],
+    );
+    predict(&mut j);
+
+    assert_eq!(j.stops[1].predicted_time.as_deref(), Some("10:48"));
+    assert_eq!(j.stops[2].predicted_time.as_deref(), Some("11:20"));
+}
+
+#[test]
+fn train_at_stop_predicts_from_actual_arrival() {
+    let mut at_stop = stop(2, "10:40", "10:42", StopStatus::AtStop);
+    at_stop.actual_arrival = Some("10:50".to_string());
+    let mut j = journey(
+        JourneyStatus::InProgress,
+        vec![
+            departed(1, "10:00", "10:08"),
+            at_stop,
+            stop(3, "11:00", "11:00", StopStatus::Scheduled),
+        ],
+    );
+    predict(&mut j);
+
+    // 10 late on arrival, 1 minute of dwell slack, 18 minute run: 9 late.
+    assert_eq!(j.stops[2].predicted_time.as_deref(), Some("11:09"));
+    assert_eq!(
+        j.stops[2].prediction_confidence,
+        Some(PredictionConfidence::High)
+    );
+}
+
+#[test]
+fn scheduled_train_without_delay_is_not_predicted() {
+    let mut j = journey(
+        JourneyStatus::Scheduled,
+        vec![
+            stop(1, "10:00", "10:00", StopStatus::Scheduled),
+            stop(2, "10:40", "10:40", StopStatus::Scheduled),
+        ],
+    );
+    predict(&mut j);
+
+    assert!(j.stops.iter().all(|s| s.predicted_time.is_none()));
+    assert_eq!(j.predicted_arrival, None);
+}
+
+#[test]
+fn train_held_at_origin_uses_reported_delay() {
+    let mut j = journey(
+        JourneyStatus::Scheduled,
+        vec![
+            stop(1, "10:00", "10:00", StopStatus::Scheduled),
+            stop(2, "10:20", "10:20", StopStatus::Scheduled),
+        ],
+    );
+    j.delay_minutes = Some(15);
+    predict(&mut j);
+
+    assert_eq!(j.stops[0].predicted_time.as_deref(), Some("10:15"));
+    assert_eq!(j.stops[1].predicted_time.as_deref(), Some("10:34"));
+    // A bare delay figure is less trustworthy than observed times.
+    assert_eq!(
+        j.stops[1].prediction_confidence,
+        Some(PredictionConfidence::Medium)
+    );
+}
+
+#[test]
+fn source_forecast_is_kept_and_reanchors() {
+    let mut j = late_in_progress();
+    j.stops[1].predicted_time = Some("10:55".to_string());
+    predict(&mut j);
+
+    assert_eq!(j.stops[1].predicted_time.as_deref(), Some("10:55"));
+    assert_eq!(
+        j.stops[1].prediction_confidence,
+        Some(PredictionConfidence::High)
+    );
+    // 15 late, 59 minute run recovers 2.
+    assert_eq!(j.stops[2].predicted_time.as_deref(), Some("11:53"));
+    assert_eq!(
+        j.stops[2].prediction_confidence,
+        Some(PredictionConfidence::Medium)
+    );
+}
+
+#[test]
+fn prediction_rolls_over_midnight() {
+    let mut j = journey(
+        JourneyStatus::InProgress,
+        vec![
+            departed(1, "23:50", "00:05"),
+            stop(2, "00:20", "00:20", StopStatus::Scheduled),
+        ],
+    );
+    predict(&mut j);
+
+    // 15 late, 30 minute run recovers 1.
+    assert_eq!(j.predicted_arrival.as_deref(), Some("00:34"));
+}
+
+#[test]
+fn cancelled_stops_are_skipped() {
+    let mut j = late_in_progress();
+    j.stops[1].status = StopStatus::Cancelled;
+    predict(&mut j);
+
+    assert_eq!(j.stops[1].predicted_time, None);
+    assert!(j.stops[2].predicted_time.is_some());
+}
+
+#[test]
+fn completed_and_cancelled_journeys_are_untouched() {
+    for status in [JourneyStatus::Completed, JourneyStatus::Cancelled] {
+        let mut j = late_in_progress();
+        j.status = status;
+        predict(&mut j);
+        assert!(j.stops.iter().all(|s| s.predicted_time.is_none()));
+        assert_eq!(j.predicted_arrival, None);
+    }
+}
+
+#[test]
+fn estimated_arrival_falls_back_to_prediction() {
+    let mut j = late_in_progress();
+    assert_eq!(j.estimated_arrival(), None);
+
+    predict(&mut j);
+    assert_eq!(j.estimated_arrival(), Some("11:46"));
+
+    j.stops[2].actual_arrival = Some("11:44".to_string());
+    assert_eq!(j.estimated_arrival(), Some("11:44"));
+}
+
+#[test]
+fn confidence_serialization() {
+    assert_eq!(
+        serde_json::to_string(&PredictionConfidence::Medium).unwrap(),
+        "\"MEDIUM\""
+    );
+    assert!(PredictionConfidence::High > PredictionConfidence::Low);
+    assert_eq!(
+        PredictionConfidence::Medium.lower(),
+        PredictionConfidence::Low
+    );
+}
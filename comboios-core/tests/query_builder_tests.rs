@@ -1,5 +1,9 @@
-use comboios_core::domain::station_timetable::TrainEntry;
-use comboios_core::query_builder::{StationQuery, TrainEntryFilter};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use comboios_core::domain::occupancy::Occupancy;
+use comboios_core::domain::station_timetable::{StationTimetable, TrainEntry};
+use comboios_core::query_builder::{
+    BoardFilter, BoardStatus, BoardWindow, StationQuery, TrainEntryFilter,
+};
 
 fn make_entry(service_type: &str, origin: &str, destination: &str) -> TrainEntry {
     TrainEntry {
@@ -128,3 +132,175 @@ fn multiple_filters_all_must_match() {
     let wrong_origin = make_entry("IC", "Braga", "Lisboa Oriente");
     assert!(!filter.matches(&wrong_origin));
 }
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 3, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+#[test]
+fn board_window_same_day_has_one_service_date() {
+    let window = BoardWindow::new(at(14, 8, 0)..at(14, 12, 0));
+    assert_eq!(window.service_dates(), vec![at(14, 0, 0).date()]);
+}
+
+#[test]
+fn board_window_across_midnight_has_two_service_dates() {
+    let window = BoardWindow::new(at(14, 22, 0)..at(15, 2, 0));
+    assert_eq!(
+        window.service_dates(),
+        vec![at(14, 0, 0).date(), at(15, 0, 0).date()]
+    );
+}
+
+#[test]
+fn board_window_ending_at_midnight_skips_next_day() {
+    let window = BoardWindow::new(at(14, 22, 0)..at(15, 0, 0));
+    assert_eq!(window.service_dates(), vec![at(14, 0, 0).date()]);
+}
+
+#[test]
+fn board_window_end_is_exclusive() {
+    let window = BoardWindow::new(at(14, 8, 0)..at(14, 9, 0));
+    assert!(window.contains(at(14, 8, 0)));
+    assert!(!window.contains(at(14, 9, 0)));
+}
+
+#[test]
+fn board_window_for_date_covers_whole_day() {
+    let window = BoardWindow::for_date(at(14, 0, 0).date());
+    assert_eq!(window.start(), at(14, 0, 0));
+    assert_eq!(window.end(), at(15, 0, 0));
+    assert_eq!(window.service_dates().len(), 1);
+}
+
+#[test]
+fn board_window_max_results_chain() {
+    let window = BoardWindow::new(at(14, 8, 0)..at(14, 9, 0)).max_results(3);
+    assert_eq!(window.get_max_results(), Some(3));
+}
+
+#[test]
+fn board_window_validate_rejects_inverted_and_oversized() {
+    assert!(
+        BoardWindow::new(at(14, 9, 0)..at(14, 8, 0))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        BoardWindow::new(at(1, 0, 0)..at(14, 0, 0))
+            .validate()
+            .is_err()
+    );
+    assert!(
+        BoardWindow::new(at(14, 8, 0)..at(14, 9, 0))
+            .validate()
+            .is_ok()
+    );
+}
+
+// --- BoardFilter ---
+
+fn make_row(service_type: &str, departure: &str, delay: Option<i32>) -> StationTimetable {
+    StationTimetable {
+        train_number: 520,
+        service_type: service_type.to_string(),
+        origin_station_name: "Lisboa - Santa Apolónia".to_string(),
+        origin_station_id: "94-30007".to_string(),
+        destination_station_name: "Porto - Campanhã".to_string(),
+        destination_station_id: "94-2006".to_string(),
+        departure_time: Some(departure.to_string()),
+        arrival_time: None,
+        platform: Some("4".to_string()),
+        delay,
+        estimated_departure: None,
+        estimated_arrival: None,
+        observations: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        has_passed: false,
+        is_departure: true,
+    }
+}
+
+fn hm(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn board_filter_empty_matches_everything() {
+    assert!(BoardFilter::new().matches(&make_row("IC|Intercidades", "10:00", None)));
+}
+
+#[test]
+fn board_filter_service_type_matches_code_or_designation() {
+    let row = make_row("IC|Intercidades", "10:00", None);
+    assert!(BoardFilter::new().service_type("ic").matches(&row));
+    assert!(
+        BoardFilter::new()
+            .service_type("intercidades")
+            .matches(&row)
+    );
+    assert!(!BoardFilter::new().service_type("AP").matches(&row));
+}
+
+#[test]
+fn board_filter_station_by_id_or_name() {
+    let row = make_row("IC|Intercidades", "10:00", None);
+    assert!(BoardFilter::new().origin("94-30007").matches(&row));
+    assert!(BoardFilter::new().origin("9430007").matches(&row));
+    assert!(BoardFilter::new().destination("campanhã").matches(&row));
+    assert!(!BoardFilter::new().destination("94-30007").matches(&row));
+}
+
+#[test]
+fn board_filter_platform_and_operator() {
+    let row = make_row("IC|Intercidades", "10:00", None);
+    assert!(
+        BoardFilter::new()
+            .platform("4")
+            .operator("cp")
+            .matches(&row)
+    );
+    assert!(!BoardFilter::new().platform("5").matches(&row));
+    assert!(!BoardFilter::new().operator("Fertagus").matches(&row));
+}
+
+#[test]
+fn board_filter_delayed_only_and_min_delay() {
+    let on_time = make_row("IC|Intercidades", "10:00", Some(0));
+    let late = make_row("IC|Intercidades", "10:00", Some(12));
+
+    assert!(!BoardFilter::new().delayed_only().matches(&on_time));
+    assert!(BoardFilter::new().delayed_only().matches(&late));
+    assert!(BoardFilter::new().min_delay(10).matches(&late));
+    assert!(!BoardFilter::new().min_delay(15).matches(&late));
+}
+
+#[test]
+fn board_filter_cancelled_only() {
+    let mut cancelled = make_row("R|Regional", "10:00", None);
+    cancelled.observations = Some("Supressão".to_string());
+
+    let filter = BoardFilter::new().status(BoardStatus::Cancelled);
+    assert!(filter.matches(&cancelled));
+    assert!(!filter.matches(&make_row("R|Regional", "10:00", None)));
+}
+
+#[test]
+fn board_filter_time_range_inclusive() {
+    let filter = BoardFilter::new().time_range(hm(9, 0), hm(10, 0));
+    assert!(filter.matches(&make_row("R|Regional", "09:00", None)));
+    assert!(filter.matches(&make_row("R|Regional", "10:00", None)));
+    assert!(!filter.matches(&make_row("R|Regional", "10:01", None)));
+}
+
+#[test]
+fn board_filter_time_range_wraps_midnight() {
+    let filter = BoardFilter::new().time_range(hm(23, 0), hm(1, 0));
+    assert!(filter.matches(&make_row("R|Regional", "23:30", None)));
+    assert!(filter.matches(&make_row("R|Regional", "00:30", None)));
+    assert!(!filter.matches(&make_row("R|Regional", "12:00", None)));
+}
@@ -0,0 +1,176 @@
+//! Tests for live connection-risk assessment.
+
+use comboios_core::domain::journey::{
+    JourneyStatus, JourneyStop, PredictionConfidence, StopStatus, TrainJourney,
+};
+use comboios_core::planner::{Itinerary, PlannerConfig, TripLeg};
+use comboios_core::risk::{ConnectionRisk, assess};
+
+mod common;
+use common::{at, station};
+
+fn leg(train: &str, from: (&str, &str), to: (&str, &str)) -> TripLeg {
+    TripLeg {
+        train_number: train.to_string(),
+        service_type: "R|Regional".to_string(),
+        operator: "CP".to_string(),
+        from: station(from.0),
+        to: station(to.0),
+        departure: at(from.1),
+        arrival: at(to.1),
+        departure_platform: None,
+        arrival_platform: None,
+    }
+}
+
+/// Train 100 reaches the change at 94-2 at 10:30; train 200 leaves at 10:45.
+fn itinerary() -> Itinerary {
+    Itinerary {
+        legs: vec![
+            leg("100", ("94-1", "10:00"), ("94-2", "10:30")),
+            leg("200", ("94-2", "10:45"), ("94-3", "11:30")),
+        ],
+        departure: at("10:00"),
+        arrival: at("11:30"),
+        transfers: 1,
+    }
+}
+
+fn stop(num: usize, code: &str, time: &str) -> JourneyStop {
+    common::stop(num, code, time, time)
+}
+
+fn journey(train: &str, stops: Vec<JourneyStop>) -> TrainJourney {
+    TrainJourney {
+        service_type: "R|Regional".to_string(),
+        status: JourneyStatus::InProgress,
+        ..common::journey(train, stops)
+    }
+}
+
+/// Train 100 with a prediction for its arrival at the change.
+fn arriving(predicted: &str, confidence: PredictionConfidence) -> TrainJourney {
+    let mut change = stop(2, "94-2", "10:30");
+    change.predicted_time = Some(predicted.to_string());
+    change.prediction_confidence = Some(confidence);
+    journey("100", vec![stop(1, "94-1", "10:00"), change])
+}
+
+fn departing() -> TrainJourney {
+    journey(
+        "200",
+        vec![stop(1, "94-2", "10:45"), stop(2, "94-3", "11:30")],
+    )
+}
+
+#[test]
+fn on_schedule_connection_is_safe() {
+    let result = assess(&itinerary(), &[None, None], &PlannerConfig::new());
+
+    let transfer = &result.transfers[0];
+    assert_eq!(transfer.station.code, "94-2");
+    assert_eq!(transfer.arriving_train, "100");
+    assert_eq!(transfer.departing_train, "200");
+    assert_eq!(transfer.min_transfer_minutes, 5);
+    assert_eq!(transfer.slack_minutes, 10);
+    assert_eq!(transfer.risk, ConnectionRisk::Safe);
+    assert_eq!(result.risk, ConnectionRisk::Safe);
+    assert_eq!(result.expected_arrival, at("11:30"));
+}
+
+#[test]
+fn predicted_delay_classifies_the_connection() {
+    let config = PlannerConfig::new();
+    let cases = [
+        ("10:38", ConnectionRisk::AtRisk, 2),
+        ("10:42", ConnectionRisk::Missed, -2),
+    ];
+
+    for (predicted, risk, slack) in cases {
+        let journeys = [
+            Some(arriving(predicted, PredictionConfidence::Medium)),
+            Some(departing()),
+        ];
+        let result = assess(&itinerary(), &journeys, &config);
+        assert_eq!(result.transfers[0].expected_arrival, at(predicted));
+        assert_eq!(result.transfers[0].slack_minutes, slack);
+        assert_eq!(result.transfers[0].risk, risk, "predicted {predicted}");
+        assert_eq!(result.risk, risk);
+    }
+}
+
+#[test]
+fn low_confidence_prediction_widens_the_margin() {
+    let config = PlannerConfig::new();
+
+    let medium = [Some(arriving("10:33", PredictionConfidence::Medium)), None];
+    assert_eq!(
+        assess(&itinerary(), &medium, &config).transfers[0].risk,
+        ConnectionRisk::Safe
+    );
+
+    let low = [Some(arriving("10:33", PredictionConfidence::Low)), None];
+    assert_eq!(
+        assess(&itinerary(), &low, &config).transfers[0].risk,
+        ConnectionRisk::AtRisk
+    );
+}
+
+#[test]
+fn actual_arrival_beats_prediction() {
+    let mut journey = arriving("10:50", PredictionConfidence::Low);
+    journey.stops[1].actual_arrival = Some("10:31".to_string());
+
+    let result = assess(&itinerary(), &[Some(journey), None], &PlannerConfig::new());
+    assert_eq!(result.transfers[0].expected_arrival, at("10:31"));
+    assert_eq!(result.transfers[0].risk, ConnectionRisk::Safe);
+}
+
+#[test]
+fn delayed_onward_train_can_save_the_connection() {
+    let mut onward = departing();
+    onward.delay_minutes = Some(10);
+
+    let journeys = [
+        Some(arriving("10:42", PredictionConfidence::Medium)),
+        Some(onward),
+    ];
+    let result = assess(&itinerary(), &journeys, &PlannerConfig::new());
+
+    assert_eq!(result.transfers[0].expected_departure, at("10:55"));
+    assert_eq!(result.transfers[0].slack_minutes, 8);
+    assert_eq!(result.transfers[0].risk, ConnectionRisk::Safe);
+    assert_eq!(result.expected_arrival, at("11:40"));
+}
+
+#[test]
+fn cancelled_onward_train_is_missed() {
+    let mut onward = departing();
+    onward.stops[0].status = StopStatus::Cancelled;
+
+    let result = assess(&itinerary(), &[None, Some(onward)], &PlannerConfig::new());
+    assert_eq!(result.transfers[0].risk, ConnectionRisk::Missed);
+}
+
+#[test]
+fn per_station_transfer_time_is_used() {
+    let config = PlannerConfig::new().transfer_time("94-2", std::time::Duration::from_mins(12));
+    let result = assess(&itinerary(), &[None, None], &config);
+
+    assert_eq!(result.transfers[0].min_transfer_minutes, 12);
+    assert_eq!(result.transfers[0].risk, ConnectionRisk::AtRisk);
+}
+
+#[test]
+fn direct_itinerary_has_no_transfers() {
+    let direct = Itinerary {
+        legs: vec![leg("100", ("94-1", "10:00"), ("94-2", "10:30"))],
+        departure: at("10:00"),
+        arrival: at("10:30"),
+        transfers: 0,
+    };
+    let result = assess(&direct, &[None], &PlannerConfig::new());
+
+    assert!(result.transfers.is_empty());
+    assert_eq!(result.risk, ConnectionRisk::Safe);
+}
@@ -1,6 +1,8 @@
 //! Tests for station and timetable domain models
 
-use comboios_core::domain::station::Station;
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::occupancy::Occupancy;
+use comboios_core::domain::station::{Station, StationIndex};
 use comboios_core::domain::station_timetable::{StationBoard, StationTimetable};
 
 #[test]
@@ -46,6 +48,7 @@ fn test_station_timetable_creation() {
         estimated_departure: None,
         estimated_arrival: None,
         observations: None,
+        occupancy: Occupancy::Unknown,
         operator: "CP".to_string(),
         has_passed: false,
         is_departure: true,
@@ -75,6 +78,7 @@ fn test_station_board_creation() {
             estimated_departure: None,
             estimated_arrival: None,
             observations: None,
+            occupancy: Occupancy::Unknown,
             operator: "CP".to_string(),
             has_passed: false,
             is_departure: true,
@@ -101,6 +105,7 @@ fn test_station_timetable_serialization() {
         estimated_departure: None,
         estimated_arrival: Some("15:40".to_string()),
         observations: None,
+        occupancy: Occupancy::Unknown,
         operator: "CP".to_string(),
         has_passed: true,
         is_departure: false,
@@ -129,3 +134,106 @@ fn test_station_board_serialization() {
     assert_eq!(deserialized.station_name, "Porto Campanha");
     assert!(deserialized.trains.is_empty());
 }
+
+// --- StationIndex ---
+
+fn station(code: &str, designation: &str) -> Station {
+    Station {
+        code: code.to_string(),
+        designation: designation.to_string(),
+    }
+}
+
+fn make_index() -> StationIndex {
+    StationIndex::new(vec![
+        station("94-31039", "Lisboa - Oriente"),
+        station("94-2006", "Porto - Campanhã"),
+    ])
+}
+
+fn make_journey_stop(code: &str, designation: &str) -> JourneyStop {
+    JourneyStop {
+        station: station(code, designation),
+        scheduled_arrival: "10:00".to_string(),
+        actual_arrival: None,
+        scheduled_departure: "10:00".to_string(),
+        actual_departure: None,
+        platform: None,
+        status: StopStatus::Scheduled,
+        delay_minutes: None,
+        stop_number: 1,
+        has_passed: None,
+        predicted_time: None,
+        prediction_confidence: None,
+    }
+}
+
+#[test]
+fn station_index_looks_up_cp_and_ip_ids() {
+    let index = make_index();
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.name_of("94-31039"), Some("Lisboa - Oriente"));
+    assert_eq!(index.name_of("9431039"), Some("Lisboa - Oriente"));
+    assert_eq!(index.name_of("94-99999"), None);
+}
+
+#[test]
+fn station_index_name_lookup_ignores_accents_and_punctuation() {
+    let index = make_index();
+    let found = index.find_by_name("Porto Campanha").unwrap();
+    assert_eq!(found.code, "94-2006");
+}
+
+#[test]
+fn station_index_fills_empty_board_name() {
+    let index = make_index();
+    let mut board = StationBoard {
+        station_id: "94-31039".to_string(),
+        station_name: String::new(),
+        trains: vec![],
+    };
+
+    index.fill_board(&mut board);
+    assert_eq!(board.station_name, "Lisboa - Oriente");
+}
+
+#[test]
+fn station_index_fills_journey_endpoints() {
+    let index = make_index();
+    let mut journey = TrainJourney {
+        train_number: "530".to_string(),
+        service_type: "IC".to_string(),
+        origin: station("", "Porto Campanha"),
+        destination: station("", ""),
+        stops: vec![
+            make_journey_stop("", "Porto Campanha"),
+            make_journey_stop("9431039", "Lisboa Oriente"),
+        ],
+        status: JourneyStatus::Scheduled,
+        delay_minutes: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        observations: None,
+        duration: None,
+        origin_departure: None,
+        destination_arrival: None,
+        predicted_arrival: None,
+        prediction_confidence: None,
+        live_position: None,
+    };
+
+    index.fill_journey(&mut journey);
+
+    assert_eq!(journey.origin.code, "94-2006");
+    assert_eq!(journey.origin.designation, "Porto Campanha");
+    assert_eq!(journey.destination.code, "94-31039");
+    assert_eq!(journey.destination.designation, "Lisboa Oriente");
+}
+
+#[test]
+fn station_index_leaves_unknown_station_unchanged() {
+    let index = make_index();
+    let mut unknown = station("", "Nowhere");
+    index.complete(&mut unknown);
+    assert_eq!(unknown.code, "");
+}
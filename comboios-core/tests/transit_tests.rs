@@ -0,0 +1,298 @@
+//! Tests for loading external GTFS feeds and finding onward connections.
+
+use std::io::{Cursor, Write};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use comboios_core::Error;
+use comboios_core::domain::journey::TrainJourney;
+use comboios_core::domain::position::Coordinates;
+use comboios_core::domain::station::{Station, StationIndex};
+use comboios_core::transit::{TransitFeed, TransitTimetable};
+
+mod common;
+
+/// A small metro: the red line between Oriente and Alameda, with one trip
+/// each way at listed times on weekdays and a frequency-based trip every
+/// ten minutes from 06:00 until 01:00, and one green line trip from Cais do
+/// Sodré.
+fn metro_files() -> Vec<(&'static str, &'static str)> {
+    vec![
+        (
+            "agency.txt",
+            "agency_id,agency_name,agency_url,agency_timezone\n\
+             ML,Metro de Lisboa,https://www.metrolisboa.pt,Europe/Lisbon\n",
+        ),
+        (
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+             OR-P,Oriente,38.76790,-9.09900,1,\n\
+             OR,Oriente,38.76790,-9.09900,0,OR-P\n\
+             ALM,Alameda,38.73700,-9.13380,0,\n\
+             CSO,\"Cais do Sodré\",38.70590,-9.14560,0,\n",
+        ),
+        (
+            "routes.txt",
+            "route_id,agency_id,route_short_name,route_long_name,route_type\n\
+             RED,ML,,Vermelha,1\n\
+             GRN,ML,,Verde,1\n",
+        ),
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id,trip_headsign\n\
+             RED,WK,T1,São Sebastião\n\
+             RED,WK,T2,Aeroporto\n\
+             RED,WK,T3,São Sebastião\n\
+             GRN,WK,T4,Telheiras\n",
+        ),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T1,10:05:00,10:05:00,OR,1\n\
+             T1,10:20:00,10:20:00,ALM,2\n\
+             T2,10:00:00,10:00:00,ALM,1\n\
+             T2,10:12:00,10:12:00,OR,2\n\
+             T3,06:00:00,06:00:00,OR,1\n\
+             T3,06:15:00,06:15:00,ALM,2\n\
+             T4,10:30:00,10:30:00,CSO,1\n\
+             T4,10:45:00,10:45:00,ALM,2\n",
+        ),
+        (
+            "calendar.txt",
+            "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+             WK,1,1,1,1,1,0,0,20260101,20261231\n",
+        ),
+        (
+            "calendar_dates.txt",
+            "service_id,date,exception_type\n\
+             WK,20260316,2\n",
+        ),
+        (
+            "frequencies.txt",
+            "trip_id,start_time,end_time,headway_secs\n\
+             T3,06:00:00,25:00:00,600\n",
+        ),
+    ]
+}
+
+fn zip(files: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    let mut buffer = zip.finish().unwrap();
+    buffer.set_position(0);
+    buffer
+}
+
+fn metro() -> TransitFeed {
+    TransitFeed::from_zip(zip(&metro_files())).unwrap()
+}
+
+fn stations() -> StationIndex {
+    let mut index = StationIndex::new([
+        Station {
+            code: "94-31039".to_string(),
+            designation: "Lisboa - Oriente".to_string(),
+        },
+        Station {
+            code: "94-69005".to_string(),
+            designation: "Cais do Sodré".to_string(),
+        },
+        Station {
+            code: "94-30007".to_string(),
+            designation: "Lisboa - Santa Apolónia".to_string(),
+        },
+    ]);
+    // CP's Oriente is a few hundred metres from the metro entrance; Cais do
+    // Sodré is next door; Santa Apolónia has no metro stop in this feed.
+    index.set_coordinates("94-31039", Coordinates::new(38.76790, -9.10200));
+    index.set_coordinates("94-69005", Coordinates::new(38.70600, -9.14580));
+    index.set_coordinates("94-30007", Coordinates::new(38.71370, -9.12270));
+    index
+}
+
+fn timetable() -> TransitTimetable {
+    TransitTimetable::new(vec![metro()], &stations())
+}
+
+/// A train from Santa Apolónia at `departure`, arriving at `code` at
+/// `arrival`.
+fn arriving_at(code: &str, departure: &str, arrival: &str) -> TrainJourney {
+    common::journey(
+        "520",
+        vec![
+            common::stop(1, "94-30007", "", departure),
+            common::stop(2, code, arrival, ""),
+        ],
+    )
+}
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 3, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+/// Friday.
+fn friday() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 13).unwrap()
+}
+
+#[test]
+fn feed_loads_agency_and_stops() {
+    let feed = metro();
+
+    assert_eq!(feed.agency(), "Metro de Lisboa");
+    assert_eq!(feed.stops().len(), 4);
+    assert_eq!(feed.stop("CSO").unwrap().name, "Cais do Sodré");
+}
+
+#[test]
+fn stations_link_to_stops_by_name_or_proximity() {
+    let timetable = timetable();
+
+    // ~260 m away, so only linked because the names match; the parent
+    // station has no departures and is not linked.
+    let oriente = timetable.links("94-31039");
+    assert_eq!(oriente.len(), 1);
+    assert_eq!(oriente[0].stop_id, "OR");
+    assert!(oriente[0].by_name);
+    assert!(oriente[0].distance_meters > 250.0 && oriente[0].distance_meters < 300.0);
+
+    assert_eq!(timetable.links("9469005")[0].stop_id, "CSO");
+    assert!(timetable.links("94-30007").is_empty());
+}
+
+#[test]
+fn connections_list_the_next_departure_of_each_direction() {
+    let connections =
+        timetable().connections(&arriving_at("94-31039", "09:50", "10:00"), 1, friday());
+
+    let found: Vec<_> = connections
+        .iter()
+        .map(|c| {
+            (
+                c.departure.route.as_str(),
+                c.departure.headsign.as_str(),
+                c.departure.departure,
+            )
+        })
+        .collect();
+    // T2 terminates at Oriente, so it is not a departure; T3 runs every ten
+    // minutes but only its next run is listed.
+    assert_eq!(found, [("Vermelha", "São Sebastião", at(13, 10, 5))]);
+    assert_eq!(connections[0].departure.agency, "Metro de Lisboa");
+    assert_eq!(connections[0].departure.stop_name, "Oriente");
+    assert_eq!(connections[0].walk_minutes, 4);
+}
+
+#[test]
+fn connections_allow_for_the_walk() {
+    let connections =
+        timetable().connections(&arriving_at("94-31039", "09:50", "10:03"), 1, friday());
+
+    // T1 at 10:05 leaves before the walk is done; T3 runs at 10:10.
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0].departure.departure, at(13, 10, 10));
+}
+
+#[test]
+fn connections_start_from_actual_then_predicted_arrival() {
+    let timetable = timetable();
+    let mut journey = arriving_at("94-31039", "08:50", "09:00");
+    journey.stops[1].predicted_time = Some("09:30".to_string());
+
+    let predicted = timetable.connections(&journey, 1, friday());
+    assert_eq!(predicted[0].departure.departure, at(13, 9, 40));
+
+    journey.stops[1].actual_arrival = Some("10:00".to_string());
+    let actual = timetable.connections(&journey, 1, friday());
+    assert_eq!(actual[0].departure.departure, at(13, 10, 5));
+}
+
+#[test]
+fn connections_roll_over_midnight() {
+    let timetable = timetable();
+    let mut journey = arriving_at("94-31039", "23:50", "00:20");
+
+    // Friday's train reaches Oriente early on Saturday, in time for the last
+    // runs of Friday's metro service.
+    let connections = timetable.connections(&journey, 1, friday());
+    assert_eq!(connections[0].departure.departure, at(14, 0, 30));
+
+    // Running late, it still arrives on Saturday.
+    journey.stops[1].predicted_time = Some("00:35".to_string());
+    let late = timetable.connections(&journey, 1, friday());
+    assert_eq!(late[0].departure.departure, at(14, 0, 40));
+
+    assert!(timetable.connections(&journey, 2, friday()).is_empty());
+}
+
+#[test]
+fn departures_expand_frequencies() {
+    let departures = metro().departures("OR", at(13, 10, 0), at(13, 11, 0));
+
+    // T1 once, T3 every ten minutes.
+    assert_eq!(departures.len(), 7);
+    assert!(
+        departures
+            .windows(2)
+            .all(|w| w[0].departure <= w[1].departure)
+    );
+}
+
+#[test]
+fn services_follow_calendar_and_exceptions() {
+    let feed = metro();
+
+    // Saturday: no weekday service, except the Friday frequency trip
+    // running past midnight.
+    let saturday = feed.departures("OR", at(14, 0, 0), at(14, 12, 0));
+    let times: Vec<_> = saturday.iter().map(|d| d.departure).collect();
+    assert_eq!(
+        times,
+        [
+            at(14, 0, 0),
+            at(14, 0, 10),
+            at(14, 0, 20),
+            at(14, 0, 30),
+            at(14, 0, 40),
+            at(14, 0, 50)
+        ]
+    );
+
+    // Monday 16 March is removed by calendar_dates.txt.
+    assert!(
+        feed.departures("OR", at(16, 6, 0), at(16, 23, 0))
+            .is_empty()
+    );
+    assert!(!feed.departures("OR", at(17, 6, 0), at(17, 7, 0)).is_empty());
+}
+
+#[test]
+fn missing_tables_are_reported() {
+    let files: Vec<_> = metro_files()
+        .into_iter()
+        .filter(|(name, _)| *name != "stop_times.txt")
+        .collect();
+
+    let err = TransitFeed::from_zip(zip(&files)).unwrap_err();
+    assert!(
+        matches!(&err, Error::GtfsError(msg) if msg.contains("stop_times.txt")),
+        "got {err:?}"
+    );
+}
+
+#[test]
+fn tables_may_sit_in_a_folder() {
+    let files: Vec<_> = metro_files()
+        .into_iter()
+        .map(|(name, contents)| (format!("metro/{name}"), contents))
+        .collect();
+    let files: Vec<_> = files.iter().map(|(n, c)| (n.as_str(), *c)).collect();
+
+    assert_eq!(TransitFeed::from_zip(zip(&files)).unwrap().stops().len(), 4);
+}
@@ -0,0 +1,221 @@
+//! Tests for direct train matching between two station boards.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::occupancy::Occupancy;
+use comboios_core::domain::station::{Station, StationIndex};
+use comboios_core::domain::station_timetable::{StationBoard, StationTimetable};
+use comboios_core::domain::trip::DirectTrain;
+
+fn row(train: u64, arrival: Option<&str>, departure: Option<&str>) -> StationTimetable {
+    StationTimetable {
+        train_number: train,
+        service_type: "IC|Intercidades".to_string(),
+        origin_station_name: "Porto - Campanhã".to_string(),
+        origin_station_id: "94-2006".to_string(),
+        destination_station_name: "Lisboa - Santa Apolónia".to_string(),
+        destination_station_id: "94-30007".to_string(),
+        departure_time: departure.map(String::from),
+        arrival_time: arrival.map(String::from),
+        platform: Some("3".to_string()),
+        delay: None,
+        estimated_departure: None,
+        estimated_arrival: None,
+        observations: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        has_passed: false,
+        is_departure: departure.is_some(),
+    }
+}
+
+fn board(id: &str, name: &str, trains: Vec<StationTimetable>) -> StationBoard {
+    StationBoard {
+        station_id: id.to_string(),
+        station_name: name.to_string(),
+        trains,
+    }
+}
+
+fn day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, 14).unwrap()
+}
+
+fn at(date: NaiveDate, hour: u32, minute: u32) -> NaiveDateTime {
+    date.and_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn campanha(trains: Vec<StationTimetable>) -> StationBoard {
+    board("94-2006", "Porto - Campanhã", trains)
+}
+
+fn aveiro(trains: Vec<StationTimetable>) -> StationBoard {
+    board("94-39008", "Aveiro", trains)
+}
+
+#[test]
+fn pairs_trains_on_both_boards() {
+    let trains = DirectTrain::match_boards(
+        &campanha(vec![
+            row(521, None, Some("17:39")),
+            row(4021, Some("17:00"), Some("17:05")),
+            row(999, None, Some("17:10")),
+        ]),
+        &aveiro(vec![
+            row(4021, Some("18:10"), Some("18:12")),
+            row(521, Some("18:17"), Some("18:19")),
+            row(777, Some("18:30"), None),
+        ]),
+        day(),
+    );
+
+    let numbers: Vec<&str> = trains.iter().map(|t| t.train_number.as_str()).collect();
+    assert_eq!(numbers, vec!["4021", "521"]);
+
+    let ic = &trains[1];
+    assert_eq!(ic.from.designation, "Porto - Campanhã");
+    assert_eq!(ic.to.code, "94-39008");
+    assert_eq!(ic.origin.code, "94-2006");
+    assert_eq!(ic.departure, at(day(), 17, 39));
+    assert_eq!(ic.arrival, at(day(), 18, 17));
+    assert_eq!(ic.duration_minutes(), 38);
+    assert_eq!(ic.platform.as_deref(), Some("3"));
+}
+
+#[test]
+fn rows_without_the_needed_time_are_skipped() {
+    let trains = DirectTrain::match_boards(
+        // Terminates at Campanhã, so it cannot be boarded there.
+        &campanha(vec![row(100, Some("10:00"), None)]),
+        &aveiro(vec![row(100, Some("11:00"), None)]),
+        day(),
+    );
+    assert!(trains.is_empty());
+
+    let trains = DirectTrain::match_boards(
+        &campanha(vec![row(100, None, Some("10:00"))]),
+        // Starts at Aveiro, so it cannot be alighted from there.
+        &aveiro(vec![row(100, None, Some("11:00"))]),
+        day(),
+    );
+    assert!(trains.is_empty());
+}
+
+#[test]
+fn arrival_after_midnight_rolls_over() {
+    let trains = DirectTrain::match_boards(
+        &campanha(vec![row(600, None, Some("23:40"))]),
+        &aveiro(vec![row(600, Some("00:25"), None)]),
+        day(),
+    );
+
+    assert_eq!(trains[0].arrival, at(day().succ_opt().unwrap(), 0, 25));
+    assert_eq!(trains[0].duration_minutes(), 45);
+}
+
+#[test]
+fn implausibly_long_runs_are_dropped() {
+    // Arrives before it leaves: the train runs the other way.
+    let trains = DirectTrain::match_boards(
+        &campanha(vec![row(700, None, Some("18:00"))]),
+        &aveiro(vec![row(700, Some("17:20"), None)]),
+        day(),
+    );
+    assert!(trains.is_empty());
+}
+
+#[test]
+fn expected_times_use_estimates_then_delay() {
+    let mut dep = row(521, None, Some("17:39"));
+    dep.delay = Some(5);
+    let mut arr = row(521, Some("18:17"), None);
+    arr.estimated_arrival = Some("18:20".to_string());
+
+    let train = DirectTrain::match_boards(&campanha(vec![dep]), &aveiro(vec![arr]), day())
+        .pop()
+        .unwrap();
+
+    assert_eq!(train.delay_minutes, Some(5));
+    assert_eq!(train.expected_departure(), at(day(), 17, 44));
+    assert_eq!(train.expected_arrival(), at(day(), 18, 20));
+}
+
+fn journey(codes: &[&str]) -> TrainJourney {
+    let stops: Vec<JourneyStop> = codes
+        .iter()
+        .enumerate()
+        .map(|(i, code)| JourneyStop {
+            station: Station {
+                code: (*code).to_string(),
+                designation: String::new(),
+            },
+            scheduled_arrival: "10:00".to_string(),
+            actual_arrival: None,
+            scheduled_departure: "10:00".to_string(),
+            actual_departure: None,
+            platform: None,
+            status: StopStatus::Scheduled,
+            delay_minutes: None,
+            stop_number: i + 1,
+            has_passed: Some(false),
+            predicted_time: None,
+            prediction_confidence: None,
+        })
+        .collect();
+    TrainJourney {
+        train_number: "521".to_string(),
+        service_type: "IC|Intercidades".to_string(),
+        origin: stops[0].station.clone(),
+        destination: stops[stops.len() - 1].station.clone(),
+        stops,
+        status: JourneyStatus::Scheduled,
+        delay_minutes: None,
+        occupancy: Occupancy::Unknown,
+        operator: "CP".to_string(),
+        observations: None,
+        duration: None,
+        origin_departure: None,
+        destination_arrival: None,
+        predicted_arrival: None,
+        prediction_confidence: None,
+        live_position: None,
+    }
+}
+
+#[test]
+fn stop_order_is_confirmed_against_the_journey() {
+    let train = DirectTrain::match_boards(
+        &campanha(vec![row(521, None, Some("17:39"))]),
+        &aveiro(vec![row(521, Some("18:17"), None)]),
+        day(),
+    )
+    .pop()
+    .unwrap();
+
+    assert_eq!(
+        train.serves_in_order(&journey(&["942006", "94-39008", "94-30007"])),
+        Some(true)
+    );
+    assert_eq!(
+        train.serves_in_order(&journey(&["94-30007", "94-39008", "94-2006"])),
+        Some(false)
+    );
+    assert_eq!(
+        train.serves_in_order(&journey(&["94-30007", "94-2006"])),
+        None
+    );
+}
+
+#[test]
+fn station_index_resolves_ids_and_names() {
+    let index = StationIndex::new([Station {
+        code: "94-2006".to_string(),
+        designation: "Porto - Campanhã".to_string(),
+    }]);
+
+    assert_eq!(index.resolve("942006").as_deref(), Some("94-2006"));
+    assert_eq!(index.resolve("porto campanha").as_deref(), Some("94-2006"));
+    assert_eq!(index.resolve("94-39008").as_deref(), Some("94-39008"));
+    assert_eq!(index.resolve("Aveiro"), None);
+    assert_eq!(index.resolve(""), None);
+}
@@ -0,0 +1,342 @@
+//! Tests for turning journey snapshots into change events.
+
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use comboios_core::domain::journey::{JourneyStatus, JourneyStop, StopStatus, TrainJourney};
+use comboios_core::domain::station_timetable::StationTimetable;
+use comboios_core::watch::{
+    BoardEvent, BoardTracker, JourneyEvent, JourneyTracker, MAX_IDLE_POLL, next_poll,
+};
+
+mod common;
+use common::{at, station};
+
+fn stop(num: usize, code: &str, time: &str) -> JourneyStop {
+    common::stop(num, code, time, time)
+}
+
+/// Train 100 calling at 94-1 (10:00), 94-2 (10:30) and 94-3 (11:00).
+fn journey() -> TrainJourney {
+    let stops = vec![
+        stop(1, "94-1", "10:00"),
+        stop(2, "94-2", "10:30"),
+        stop(3, "94-3", "11:00"),
+    ];
+    TrainJourney {
+        service_type: "R|Regional".to_string(),
+        origin_departure: Some(at("10:00")),
+        ..common::journey("100", stops)
+    }
+}
+
+fn with_delay(delay: i32) -> TrainJourney {
+    let mut journey = journey();
+    journey.status = JourneyStatus::InProgress;
+    journey.delay_minutes = Some(delay);
+    journey
+}
+
+#[test]
+fn first_snapshot_is_the_baseline() {
+    let mut tracker = JourneyTracker::new();
+    let mut journey = with_delay(10);
+    journey.stops[0].status = StopStatus::Departed;
+
+    assert!(tracker.update(journey).is_empty());
+    assert!(tracker.journey().is_some());
+}
+
+#[test]
+fn first_snapshot_of_a_finished_journey_ends_it() {
+    let mut cancelled = journey();
+    cancelled.status = JourneyStatus::Cancelled;
+    assert_eq!(
+        JourneyTracker::new().update(cancelled),
+        vec![JourneyEvent::Cancelled { station: None }]
+    );
+
+    let mut completed = journey();
+    completed.status = JourneyStatus::Completed;
+    assert_eq!(
+        JourneyTracker::new().update(completed),
+        vec![JourneyEvent::Completed]
+    );
+}
+
+#[test]
+fn small_delay_changes_add_up_before_being_reported() {
+    let mut tracker = JourneyTracker::new();
+    tracker.update(with_delay(0));
+
+    assert!(tracker.update(with_delay(1)).is_empty());
+    assert!(tracker.update(with_delay(2)).is_empty());
+    assert_eq!(
+        tracker.update(with_delay(3)),
+        vec![JourneyEvent::DelayChanged {
+            previous: Some(0),
+            current: Some(3),
+        }]
+    );
+    assert!(tracker.update(with_delay(1)).is_empty());
+    assert_eq!(
+        tracker.update(with_delay(0)),
+        vec![JourneyEvent::DelayChanged {
+            previous: Some(3),
+            current: Some(0),
+        }]
+    );
+}
+
+#[test]
+fn platform_changes_are_reported_for_stops_ahead_only() {
+    let mut tracker = JourneyTracker::new();
+    let mut before = journey();
+    before.stops[0].status = StopStatus::Departed;
+    before.stops[2].platform = Some("1".to_string());
+    tracker.update(before.clone());
+
+    let mut after = before;
+    after.stops[0].platform = Some("4".to_string());
+    after.stops[2].platform = Some("3".to_string());
+
+    assert_eq!(
+        tracker.update(after),
+        vec![JourneyEvent::PlatformChanged {
+            station: station("94-3"),
+            previous: Some("1".to_string()),
+            current: "3".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn skipped_polls_still_report_every_stop() {
+    let mut tracker = JourneyTracker::new();
+    tracker.update(journey());
+
+    let mut at_destination = journey();
+    at_destination.status = JourneyStatus::Completed;
+    for stop in &mut at_destination.stops {
+        stop.status = StopStatus::Passed;
+        stop.has_passed = Some(true);
+    }
+
+    let events = tracker.update(at_destination);
+    let kinds: Vec<(&str, &str)> = events
+        .iter()
+        .map(|e| match e {
+            JourneyEvent::ArrivedAtStop { station, .. } => ("arrived", station.code.as_str()),
+            JourneyEvent::Departed { station, .. } => ("departed", station.code.as_str()),
+            JourneyEvent::Completed => ("completed", ""),
+            other => panic!("unexpected {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("departed", "94-1"),
+            ("arrived", "94-2"),
+            ("departed", "94-2"),
+            ("arrived", "94-3"),
+            ("completed", ""),
+        ]
+    );
+}
+
+#[test]
+fn cancellations_of_stops_and_journey() {
+    let mut tracker = JourneyTracker::new();
+    tracker.update(journey());
+
+    let mut skipping = journey();
+    skipping.stops[1].status = StopStatus::Cancelled;
+    assert_eq!(
+        tracker.update(skipping.clone()),
+        vec![JourneyEvent::Cancelled {
+            station: Some(station("94-2")),
+        }]
+    );
+    assert!(tracker.update(skipping.clone()).is_empty());
+
+    let mut cancelled = skipping;
+    cancelled.status = JourneyStatus::Cancelled;
+    for stop in &mut cancelled.stops {
+        stop.status = StopStatus::Cancelled;
+    }
+    assert_eq!(
+        tracker.update(cancelled),
+        vec![JourneyEvent::Cancelled { station: None }]
+    );
+}
+
+#[test]
+fn polling_slows_down_before_departure_and_stops_at_the_end() {
+    let interval = Duration::from_secs(30);
+    let scheduled = journey();
+
+    assert_eq!(
+        next_poll(&scheduled, at("07:00"), interval),
+        Some(MAX_IDLE_POLL)
+    );
+    assert_eq!(
+        next_poll(&scheduled, at("09:50"), interval),
+        Some(Duration::from_mins(5))
+    );
+    assert_eq!(next_poll(&scheduled, at("09:59"), interval), Some(interval));
+    assert_eq!(next_poll(&scheduled, at("10:05"), interval), Some(interval));
+
+    assert_eq!(
+        next_poll(&with_delay(5), at("10:20"), interval),
+        Some(interval)
+    );
+
+    let mut completed = journey();
+    completed.status = JourneyStatus::Completed;
+    assert_eq!(next_poll(&completed, at("11:00"), interval), None);
+}
+
+fn row(train_number: u64, departure: &str) -> (NaiveDateTime, StationTimetable) {
+    (
+        at(departure),
+        StationTimetable {
+            service_type: "R|Regional".to_string(),
+            departure_time: Some(departure.to_string()),
+            ..common::row(train_number, "94-1", "94-3")
+        },
+    )
+}
+
+fn added(events: &[BoardEvent]) -> Vec<u64> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            BoardEvent::Added { train } => Some(train.train_number),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn first_board_poll_adds_every_train() {
+    let mut tracker = BoardTracker::new();
+    let events = tracker.update(vec![row(1, "10:00"), row(2, "10:15")], at("09:00"));
+
+    assert_eq!(added(&events), vec![1, 2]);
+    assert_eq!(tracker.trains().len(), 2);
+    assert!(
+        tracker
+            .update(vec![row(1, "10:00"), row(2, "10:15")], at("09:01"))
+            .is_empty()
+    );
+}
+
+#[test]
+fn board_reports_platform_delay_and_cancellation_changes() {
+    let mut tracker = BoardTracker::new();
+    tracker.update(vec![row(1, "10:00"), row(2, "10:15")], at("09:00"));
+
+    let (at1, mut first) = row(1, "10:00");
+    first.platform = Some("2".to_string());
+    first.delay = Some(4);
+    let (at2, mut second) = row(2, "10:15");
+    second.observations = Some("Suprimido".to_string());
+    let events = tracker.update(vec![(at1, first.clone()), (at2, second)], at("09:05"));
+    assert_eq!(
+        events,
+        vec![
+            BoardEvent::PlatformAssigned {
+                train_number: 1,
+                platform: "2".to_string(),
+            },
+            BoardEvent::DelayChanged {
+                train_number: 1,
+                previous: None,
+                current: Some(4),
+            },
+            BoardEvent::Cancelled { train_number: 2 },
+        ]
+    );
+
+    first.platform = Some("5".to_string());
+    assert_eq!(
+        tracker.update(vec![(at1, first)], at("09:06"))[..2],
+        [
+            BoardEvent::Removed { train_number: 2 },
+            BoardEvent::PlatformChanged {
+                train_number: 1,
+                previous: "2".to_string(),
+                current: "5".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn board_departures_follow_the_expected_time() {
+    let mut tracker = BoardTracker::new();
+    let (scheduled, mut late) = row(1, "10:00");
+    late.delay = Some(5);
+    tracker.update(
+        vec![(scheduled, late.clone()), row(2, "10:15")],
+        at("09:55"),
+    );
+
+    // Still there at the scheduled time, thanks to the delay.
+    assert!(
+        tracker
+            .update(
+                vec![(scheduled, late.clone()), row(2, "10:15")],
+                at("10:02")
+            )
+            .is_empty()
+    );
+
+    late.estimated_departure = Some("10:04".to_string());
+    assert_eq!(
+        tracker.update(vec![(scheduled, late), row(2, "10:15")], at("10:04")),
+        vec![BoardEvent::Departed {
+            train_number: 1,
+            time: Some("10:04".to_string()),
+        }]
+    );
+
+    // A train that drops off the board after leaving time has departed too.
+    assert_eq!(
+        tracker.update(Vec::new(), at("10:20")),
+        vec![BoardEvent::Departed {
+            train_number: 2,
+            time: Some("10:15".to_string()),
+        }]
+    );
+}
+
+#[test]
+fn cancelled_trains_are_removed_rather_than_departed() {
+    let mut tracker = BoardTracker::new();
+    let (scheduled, mut cancelled) = row(1, "10:00");
+    cancelled.observations = Some("Suprimido".to_string());
+    tracker.update(vec![(scheduled, cancelled.clone())], at("09:55"));
+
+    assert_eq!(
+        tracker.update(vec![(scheduled, cancelled)], at("10:01")),
+        vec![BoardEvent::Removed { train_number: 1 }]
+    );
+}
+
+#[test]
+fn board_result_limit_removes_and_adds_trains() {
+    let mut tracker = BoardTracker::new().max_results(1);
+    let events = tracker.update(vec![row(1, "10:00"), row(2, "10:15")], at("09:00"));
+    assert_eq!(added(&events), vec![1]);
+
+    let events = tracker.update(vec![row(1, "10:00"), row(2, "10:15")], at("10:00"));
+    assert_eq!(
+        events[0],
+        BoardEvent::Departed {
+            train_number: 1,
+            time: Some("10:00".to_string())
+        }
+    );
+    assert_eq!(added(&events), vec![2]);
+}
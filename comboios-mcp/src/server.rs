@@ -1,5 +1,8 @@
-use chrono::Local;
-use comboios_core::{Comboios, domain::station_timetable::StationBoard};
+use comboios_core::{
+    Comboios,
+    domain::station_timetable::StationBoard,
+    planner::{MAX_TRANSFERS, PlannerConfig, TripPlanner},
+};
 use rmcp::{
     Error as McpError, ServerHandler,
     model::{
@@ -46,7 +49,9 @@ impl CpServer {
         }
     }
 
-    #[tool(description = "Get station timetable (departures/arrivals) for the next 12 hours")]
+    #[tool(
+        description = "Get station timetable (departures/arrivals) for the next 12 hours, including delay, platform and occupancy (LOW/MEDIUM/HIGH/UNKNOWN) per train"
+    )]
     async fn get_station_timetable(
         &self,
         #[tool(param)]
@@ -66,7 +71,7 @@ impl CpServer {
     }
 
     async fn fetch_station_timetable(&self, station_id: &str) -> Result<Vec<StationBoard>, String> {
-        let now = Local::now();
+        let now = self.api.clock().now();
         let date = now.format("%Y-%m-%d").to_string();
         let start_time = now.format("%H:%M").to_string();
 
@@ -77,14 +82,16 @@ impl CpServer {
             .map_err(|e| e.to_string())
     }
 
-    #[tool(description = "Get train journey details by train number")]
+    #[tool(
+        description = "Get train journey details by train number, including stop-by-stop status, delay, occupancy and predicted arrival times (with confidence) for upcoming stops"
+    )]
     async fn get_train_details(
         &self,
         #[tool(param)]
         #[schemars(description = "Train number (e.g., 18298)")]
         train_id: String,
     ) -> Result<CallToolResult, McpError> {
-        let date = Local::now().format("%Y-%m-%d").to_string();
+        let date = self.api.today();
         match self.api.get_train_journey(&train_id, &date).await {
             Ok(journey) => {
                 let message = serde_json::to_string(&journey)
@@ -96,6 +103,46 @@ impl CpServer {
             ))])),
         }
     }
+
+    #[tool(
+        description = "Plan a train trip between two stations for today, including trips with changes of train. Returns the fastest itinerary for each number of changes that arrives earlier than with fewer changes, with every leg's train, stations, times and platforms"
+    )]
+    async fn plan_trip(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Origin station ID or name (e.g., 94-2006 or Braga)")]
+        from: String,
+        #[tool(param)]
+        #[schemars(description = "Destination station ID or name (e.g., Faro)")]
+        to: String,
+        #[tool(param)]
+        #[schemars(description = "Earliest departure time in HH:MM (defaults to now)")]
+        after: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Maximum number of changes of train (defaults to 3)")]
+        max_transfers: Option<usize>,
+    ) -> Result<CallToolResult, McpError> {
+        let date = self.api.today();
+        let after = after.unwrap_or_else(|| self.api.clock().now().format("%H:%M").to_string());
+        let mut config = PlannerConfig::new();
+        if let Some(max) = max_transfers {
+            config = config.max_transfers(max.min(MAX_TRANSFERS));
+        }
+
+        match TripPlanner::new(self.api.clone(), config)
+            .plan(&from, &to, &date, Some(&after))
+            .await
+        {
+            Ok(itineraries) => {
+                let text = serde_json::to_string(&itineraries)
+                    .unwrap_or_else(|e| format!("Serialization error: {e}"));
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Error planning trip: {e}"
+            ))])),
+        }
+    }
 }
 
 #[tool(tool_box)]
@@ -110,7 +157,7 @@ impl ServerHandler for CpServer {
             instructions: Some(
                 "This server provides tools to retrieve station information and timetables from Comboios de Portugal \
                  using the CP API with IP as fallback. Use get_station_timetable with a station_id to get current \
-                 departures and arrivals."
+                 departures and arrivals, and plan_trip to find trains between two stations."
                     .to_string(),
             ),
         }
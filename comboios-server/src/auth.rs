@@ -0,0 +1,93 @@
+//! API keys for the routes that store state on behalf of a caller.
+//!
+//! Keys are configured with `API_KEYS` and sent as `Authorization: Bearer
+//! <key>`. A caller is known by the SHA-256 of its key, so the stores never
+//! hold the key itself and each caller only sees what it created.
+
+use std::sync::Arc;
+
+use axum::{extract::FromRequestParts, http::header, http::request::Parts};
+use sha2::{Digest, Sha256};
+
+use crate::domain::AppState;
+use crate::error::AppError;
+
+/// The caller of a request, authenticated by one of the configured API keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller(String);
+
+impl Caller {
+    /// The caller holding `key`.
+    #[must_use]
+    pub fn from_key(key: &str) -> Self {
+        Self(hex::encode(Sha256::digest(key.as_bytes())))
+    }
+
+    /// Hex SHA-256 of the caller's key, stored as the owner of what it
+    /// creates.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for Caller {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(
+            parts
+                .headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok()),
+            &state.settings.api_keys,
+        )
+    }
+}
+
+/// The caller sending `authorization` (`Bearer <key>`), if the key is one of
+/// `keys`.
+///
+/// Keys are compared by their hash, so the comparison takes the same time
+/// however much of a key matches.
+fn authenticate(authorization: Option<&str>, keys: &[String]) -> Result<Caller, AppError> {
+    if keys.is_empty() {
+        return Err(AppError::Unauthorized(
+            "no API keys are configured; set API_KEYS".to_string(),
+        ));
+    }
+    let key = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Unauthorized("missing bearer API key".to_string()))?;
+    let caller = Caller::from_key(key.trim());
+    if keys.iter().any(|k| Caller::from_key(k) == caller) {
+        Ok(caller)
+    } else {
+        Err(AppError::Unauthorized("unknown API key".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_configured_keys_are_accepted() {
+        let keys = vec!["alpha".to_string(), "beta".to_string()];
+
+        let caller = authenticate(Some("Bearer beta"), &keys).unwrap();
+        assert_eq!(caller, Caller::from_key("beta"));
+        assert_ne!(caller.id(), "beta");
+
+        for authorization in [None, Some("beta"), Some("Bearer gamma")] {
+            assert!(matches!(
+                authenticate(authorization, &keys),
+                Err(AppError::Unauthorized(_))
+            ));
+        }
+        assert!(authenticate(Some("Bearer alpha"), &[]).is_err());
+    }
+}
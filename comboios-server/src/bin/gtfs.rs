@@ -0,0 +1,104 @@
+//! Export a GTFS static feed of CP trains.
+//!
+//! ```text
+//! comboios-gtfs --trains 520,522,4101 --from 2026-03-14 --to 2026-03-20 --output cp-gtfs.zip
+//! ```
+//!
+//! `--to` defaults to `--from`, `--from` to today and `--output` to
+//! `gtfs.zip`.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::{Context, bail};
+use chrono::NaiveDate;
+use comboios_core::Comboios;
+use comboios_server::telemetry::{get_subscriber, init_subscriber};
+
+const USAGE: &str = "usage: comboios-gtfs --trains 520,522 [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--output gtfs.zip]";
+
+struct Args {
+    trains: Vec<String>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    output: String,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut parsed = Self {
+            trains: Vec::new(),
+            from: None,
+            to: None,
+            output: "gtfs.zip".to_string(),
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .with_context(|| format!("{flag} needs a value\n{USAGE}"))?;
+            match flag.as_str() {
+                "--trains" => parsed.trains.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_string),
+                ),
+                "--from" => parsed.from = Some(date(&value)?),
+                "--to" => parsed.to = Some(date(&value)?),
+                "--output" => parsed.output = value,
+                _ => bail!("unknown argument {flag}\n{USAGE}"),
+            }
+        }
+        if parsed.trains.is_empty() {
+            bail!("no trains given\n{USAGE}");
+        }
+        Ok(parsed)
+    }
+}
+
+fn date(value: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("invalid date {value}, expected YYYY-MM-DD"))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse(std::env::args().skip(1))?;
+    init_subscriber(get_subscriber(
+        "comboios-gtfs".into(),
+        "info".into(),
+        std::io::stderr,
+    ));
+
+    let api = Comboios::new().await?;
+    let from = args.from.unwrap_or_else(|| api.clock().today());
+    let to = args.to.unwrap_or(from);
+    let trains: Vec<&str> = args.trains.iter().map(String::as_str).collect();
+
+    let feed = api.gtfs_feed(&trains, from, to).await?;
+    // Checked before anything is written, and written aside, so a bad feed
+    // or a failed write never leaves a broken zip at `--output`.
+    feed.validate()?;
+    let tmp = format!("{}.tmp", args.output);
+    let written = File::create(&tmp)
+        .with_context(|| format!("creating {tmp}"))
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            feed.write_zip(&mut writer)?;
+            Ok(writer.flush()?)
+        });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    std::fs::rename(&tmp, &args.output).with_context(|| format!("writing {}", args.output))?;
+
+    tracing::info!(
+        "Wrote {} trips over {} stops to {}",
+        feed.trips.len(),
+        feed.stops.len(),
+        args.output
+    );
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Recurring commutes served as iCalendar subscriptions.
+//!
+//! A commute is a set of trains taken on some days of the week. Its
+//! calendar, `/commutes/{id}.ics`, lists those trains over the coming days
+//! with their live delay and platforms, so a calendar app subscribed to it
+//! picks up changes on every refresh. The journeys behind it are shared by
+//! all commutes through a [`JourneyCache`], so calendar apps refreshing
+//! together do not fetch them again. Commutes belong to the API key that
+//! saved them ([`Caller`]) and are stored in a JSON file ([`CommuteStore`])
+//! like webhooks.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use comboios_core::domain::journey::TrainJourney;
+use comboios_core::error::CoreError;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::Caller;
+use crate::error::AppError;
+use crate::store::{JsonFileStore, Record};
+
+/// Most trains in one commute; its calendar fetches each on every day.
+pub const MAX_TRAINS: usize = 10;
+
+/// How long a journey fetched for a commute calendar is reused.
+pub const JOURNEY_CACHE_TTL: Duration = Duration::from_mins(2);
+
+const WORKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// Trains taken on the same days every week.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Commute {
+    pub id: Uuid,
+    /// Calendar name.
+    pub name: String,
+    /// Train numbers, e.g. the morning and the evening train.
+    pub trains: Vec<String>,
+    pub days: Vec<Weekday>,
+    pub created_at: String,
+    /// [`Caller::id`] of the API key that saved the commute.
+    #[serde(default)]
+    pub owner: String,
+}
+
+impl Commute {
+    /// Service dates of the commute among the `days` days starting at
+    /// `from`.
+    #[must_use]
+    pub fn dates(&self, from: NaiveDate, days: u32) -> Vec<NaiveDate> {
+        (0..days)
+            .map(|offset| from + Days::new(offset.into()))
+            .filter(|date| self.days.contains(&date.weekday()))
+            .collect()
+    }
+}
+
+/// Body of `POST /commutes`. `days` defaults to Monday to Friday.
+#[derive(Debug, Deserialize)]
+pub struct NewCommute {
+    pub name: Option<String>,
+    pub trains: Vec<String>,
+    pub days: Option<Vec<Weekday>>,
+}
+
+impl NewCommute {
+    /// Check the request and build the commute it describes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError`] wrapping [`CoreError::InvalidInput`] when no
+    /// trains, more than [`MAX_TRAINS`] or no days are given.
+    pub fn into_commute(self, owner: &Caller, created_at: String) -> Result<Commute, AppError> {
+        let invalid = |message: &str| AppError::CoreError(CoreError::InvalidInput(message.into()));
+
+        let mut trains: Vec<String> = Vec::new();
+        for train in self
+            .trains
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+        {
+            if !trains.iter().any(|t| t == train) {
+                trains.push(train.to_string());
+            }
+        }
+        if trains.is_empty() {
+            return Err(invalid("trains must not be empty"));
+        }
+        if trains.len() > MAX_TRAINS {
+            return Err(invalid(&format!("at most {MAX_TRAINS} trains per commute")));
+        }
+        let mut days = self.days.unwrap_or_else(|| WORKDAYS.to_vec());
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        if days.is_empty() {
+            return Err(invalid("days must not be empty"));
+        }
+
+        Ok(Commute {
+            id: Uuid::new_v4(),
+            name: self
+                .name
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| format!("Trains {}", trains.join(", "))),
+            trains,
+            days,
+            created_at,
+            owner: owner.id().to_string(),
+        })
+    }
+}
+
+/// Commutes persisted as a JSON array in one file.
+pub type CommuteStore = JsonFileStore<Commute>;
+
+impl Record for Commute {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// A journey fetched for a commute calendar; `None` when the train does not
+/// run that day.
+type CachedJourney = (Instant, Option<TrainJourney>);
+
+/// Journeys fetched for commute calendars by train and service date, reused
+/// for [`JOURNEY_CACHE_TTL`].
+#[derive(Debug, Default)]
+pub struct JourneyCache {
+    journeys: Mutex<HashMap<(String, NaiveDate), CachedJourney>>,
+}
+
+impl JourneyCache {
+    /// The journey of `train` on `date` if fetched within the last
+    /// [`JOURNEY_CACHE_TTL`]: `Some(None)` when it did not run.
+    #[must_use]
+    pub fn get(&self, train: &str, date: NaiveDate) -> Option<Option<TrainJourney>> {
+        self.journeys()
+            .get(&(train.to_string(), date))
+            .filter(|(at, _)| at.elapsed() < JOURNEY_CACHE_TTL)
+            .map(|(_, journey)| journey.clone())
+    }
+
+    /// Keep `journey` as the one of `train` on `date`, dropping expired
+    /// entries.
+    pub fn insert(&self, train: String, date: NaiveDate, journey: Option<TrainJourney>) {
+        let mut journeys = self.journeys();
+        journeys.retain(|_, (at, _)| at.elapsed() < JOURNEY_CACHE_TTL);
+        journeys.insert((train, date), (Instant::now(), journey));
+    }
+
+    fn journeys(&self) -> std::sync::MutexGuard<'_, HashMap<(String, NaiveDate), CachedJourney>> {
+        self.journeys.lock().expect("journey cache lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{journey, stop};
+
+    fn request(trains: &[&str], days: Option<Vec<Weekday>>) -> NewCommute {
+        NewCommute {
+            name: None,
+            trains: trains.iter().map(|t| (*t).to_string()).collect(),
+            days,
+        }
+    }
+
+    fn owner() -> Caller {
+        Caller::from_key("alpha")
+    }
+
+    fn commute(trains: &[&str], days: Option<Vec<Weekday>>) -> Commute {
+        request(trains, days)
+            .into_commute(&owner(), "2026-03-14T10:00:00+00:00".to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn commutes_default_to_workdays() {
+        let commute = commute(&["520", " 529 ", "520"], None);
+
+        assert_eq!(commute.name, "Trains 520, 529");
+        assert_eq!(commute.trains, ["520", "529"]);
+        assert_eq!(commute.days, WORKDAYS);
+        assert_eq!(commute.owner, owner().id());
+    }
+
+    #[test]
+    fn commutes_need_trains_and_days() {
+        let created_at = String::new;
+        assert!(
+            request(&[" "], None)
+                .into_commute(&owner(), created_at())
+                .is_err()
+        );
+        assert!(
+            request(&["520"], Some(Vec::new()))
+                .into_commute(&owner(), created_at())
+                .is_err()
+        );
+        let trains: Vec<String> = (0..=MAX_TRAINS).map(|n| (520 + n).to_string()).collect();
+        let trains: Vec<&str> = trains.iter().map(String::as_str).collect();
+        assert!(
+            request(&trains, None)
+                .into_commute(&owner(), created_at())
+                .is_err()
+        );
+        assert!(
+            request(&trains[1..], None)
+                .into_commute(&owner(), created_at())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn dates_fall_on_commute_days() {
+        let commute = commute(&["520"], Some(vec![Weekday::Sat, Weekday::Mon]));
+        // Friday 13 March 2026.
+        let friday = NaiveDate::from_ymd_opt(2026, 3, 13).unwrap();
+
+        let dates: Vec<_> = commute
+            .dates(friday, 7)
+            .iter()
+            .map(|d| d.format("%a %d").to_string())
+            .collect();
+        assert_eq!(dates, ["Sat 14", "Mon 16"]);
+        assert_eq!(commute.days, [Weekday::Mon, Weekday::Sat]);
+    }
+
+    #[test]
+    fn cached_journeys_include_trains_that_did_not_run() {
+        let cache = JourneyCache::default();
+        let friday = NaiveDate::from_ymd_opt(2026, 3, 13).unwrap();
+        assert!(cache.get("520", friday).is_none());
+
+        cache.insert("520".to_string(), friday, None);
+        assert!(matches!(cache.get("520", friday), Some(None)));
+        assert!(cache.get("520", friday + Days::new(1)).is_none());
+
+        let journey = journey("529", vec![stop(1, "94-30007", "23:00", "23:00")]);
+        cache.insert("529".to_string(), friday, Some(journey));
+        assert!(matches!(
+            cache.get("529", friday),
+            Some(Some(journey)) if journey.train_number == "529"
+        ));
+    }
+}
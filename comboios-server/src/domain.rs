@@ -1,17 +1,28 @@
-use std::collections::HashMap;
-use std::sync::RwLock;
-
 use comboios_core::Comboios;
+use comboios_core::monitor::NetworkMonitor;
+use comboios_core::planner::TripPlanner;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
 
+use crate::commutes::{CommuteStore, JourneyCache};
 use crate::configuration::Settings;
+use crate::hub::LiveHub;
+use crate::webhooks::Webhooks;
 
 #[derive(Debug)]
 pub struct AppState {
     pub(crate) api: Comboios,
+    pub(crate) monitor: NetworkMonitor,
+    /// Shares its cache with the planner of every `/trips` request.
+    pub(crate) planner: TripPlanner,
+    /// One permit per `/trips` request allowed to run at once.
+    pub(crate) trip_permits: Semaphore,
+    pub(crate) hub: LiveHub,
+    pub(crate) webhooks: Webhooks,
+    pub(crate) commutes: CommuteStore,
+    /// Journeys behind commute calendars, shared by all commutes.
+    pub(crate) commute_journeys: JourneyCache,
     pub(crate) settings: Settings,
-    /// Cached station code → name mapping, populated at startup from CP.
-    pub(crate) station_names: RwLock<HashMap<String, String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -12,6 +12,14 @@ pub enum AppError {
     CoreError(#[from] CoreError),
     #[error("invalid train id")]
     InvalidTrainId,
+    #[error("not found")]
+    NotFound(String),
+    #[error("storage error")]
+    Storage(#[from] std::io::Error),
+    #[error("too many requests")]
+    TooManyRequests(String),
+    #[error("unauthorized")]
+    Unauthorized(String),
 }
 
 #[derive(Serialize)]
@@ -56,12 +64,41 @@ impl IntoResponse for AppError {
                     "InvalidInput".to_string(),
                     msg.clone(),
                 ),
+                CoreError::GtfsError(msg) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "GtfsError".to_string(),
+                    msg.clone(),
+                ),
             },
             AppError::InvalidTrainId => (
                 StatusCode::BAD_REQUEST,
                 "InvalidTrainId".to_string(),
                 "Invalid train ID format".to_string(),
             ),
+            AppError::NotFound(what) => (
+                StatusCode::NOT_FOUND,
+                "NotFound".to_string(),
+                format!("{what} not found"),
+            ),
+            AppError::Storage(e) => {
+                // The io error names server paths; keep it to the log.
+                tracing::error!("Storage error: {e}");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "StorageError".to_string(),
+                    "Failed to save the change".to_string(),
+                )
+            }
+            AppError::TooManyRequests(message) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "TooManyRequests".to_string(),
+                message.clone(),
+            ),
+            AppError::Unauthorized(message) => (
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized".to_string(),
+                message.clone(),
+            ),
         };
 
         tracing::error!("Error: {} ({})", message, error_type);
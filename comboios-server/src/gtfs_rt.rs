@@ -0,0 +1,595 @@
+//! GTFS-Realtime feeds of the trains the network monitor sees.
+//!
+//! [`trip_updates`] turns live [`TrainJourney`]s into `TripUpdate`s with
+//! per-stop delays, skipped stops and cancelled trips; [`vehicle_positions`]
+//! turns the monitor's [`LiveTrain`] positions into `VehiclePosition`s. Trip,
+//! route and stop ids are those of the static export in
+//! [`comboios_core::gtfs`], so a consumer loaded with that feed can match
+//! them.
+//!
+//! The messages below are the subset of `gtfs-realtime.proto` we fill,
+//! with the upstream field numbers. They encode to protobuf with
+//! [`prost::Message::encode_to_vec`] and serialize to JSON, enums by name,
+//! for the debug endpoints.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Europe::Lisbon;
+use comboios_core::domain::journey::{JourneyStatus, StopStatus, TrainJourney};
+use comboios_core::gtfs;
+use comboios_core::monitor::LiveTrain;
+use serde::{Serialize, Serializer};
+
+/// Version of the GTFS-Realtime specification the feeds follow.
+pub const GTFS_REALTIME_VERSION: &str = "2.0";
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct FeedMessage {
+    #[prost(message, required, tag = "1")]
+    pub header: FeedHeader,
+    #[prost(message, repeated, tag = "2")]
+    pub entity: Vec<FeedEntity>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct FeedHeader {
+    #[prost(string, required, tag = "1")]
+    pub gtfs_realtime_version: String,
+    #[prost(enumeration = "Incrementality", optional, tag = "2")]
+    #[serde(serialize_with = "enum_name::<Incrementality, _>")]
+    pub incrementality: Option<i32>,
+    /// POSIX time the data was fetched.
+    #[prost(uint64, optional, tag = "3")]
+    pub timestamp: Option<u64>,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration, Serialize,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Incrementality {
+    FullDataset = 0,
+    Differential = 1,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct FeedEntity {
+    #[prost(string, required, tag = "1")]
+    pub id: String,
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip_update: Option<TripUpdate>,
+    #[prost(message, optional, tag = "4")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vehicle: Option<VehiclePosition>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct TripUpdate {
+    #[prost(message, required, tag = "1")]
+    pub trip: TripDescriptor,
+    #[prost(message, repeated, tag = "2")]
+    pub stop_time_update: Vec<StopTimeUpdate>,
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vehicle: Option<VehicleDescriptor>,
+    #[prost(uint64, optional, tag = "4")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// Current delay of the trip in seconds.
+    #[prost(int32, optional, tag = "5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct StopTimeUpdate {
+    #[prost(uint32, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequence: Option<u32>,
+    #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival: Option<StopTimeEvent>,
+    #[prost(message, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub departure: Option<StopTimeEvent>,
+    #[prost(string, optional, tag = "4")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_id: Option<String>,
+    #[prost(enumeration = "StopScheduleRelationship", optional, tag = "5")]
+    #[serde(serialize_with = "enum_name::<StopScheduleRelationship, _>")]
+    pub schedule_relationship: Option<i32>,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration, Serialize,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StopScheduleRelationship {
+    Scheduled = 0,
+    Skipped = 1,
+    NoData = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct StopTimeEvent {
+    /// Seconds late (negative when early).
+    #[prost(int32, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay: Option<i32>,
+    /// Expected POSIX time.
+    #[prost(int64, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct TripDescriptor {
+    #[prost(string, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip_id: Option<String>,
+    /// Service date, `YYYYMMDD`.
+    #[prost(string, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    #[prost(enumeration = "TripScheduleRelationship", optional, tag = "4")]
+    #[serde(serialize_with = "enum_name::<TripScheduleRelationship, _>")]
+    pub schedule_relationship: Option<i32>,
+    #[prost(string, optional, tag = "5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route_id: Option<String>,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration, Serialize,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TripScheduleRelationship {
+    Scheduled = 0,
+    Added = 1,
+    Unscheduled = 2,
+    Canceled = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct VehicleDescriptor {
+    #[prost(string, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct VehiclePosition {
+    #[prost(message, optional, tag = "1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip: Option<TripDescriptor>,
+    #[prost(message, optional, tag = "2")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+    #[prost(uint32, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_stop_sequence: Option<u32>,
+    #[prost(enumeration = "VehicleStopStatus", optional, tag = "4")]
+    #[serde(serialize_with = "enum_name::<VehicleStopStatus, _>")]
+    pub current_status: Option<i32>,
+    #[prost(uint64, optional, tag = "5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[prost(string, optional, tag = "7")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_id: Option<String>,
+    #[prost(message, optional, tag = "8")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vehicle: Option<VehicleDescriptor>,
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration, Serialize,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VehicleStopStatus {
+    IncomingAt = 0,
+    StoppedAt = 1,
+    InTransitTo = 2,
+}
+
+#[derive(Clone, PartialEq, prost::Message, Serialize)]
+pub struct Position {
+    #[prost(float, required, tag = "1")]
+    pub latitude: f32,
+    #[prost(float, required, tag = "2")]
+    pub longitude: f32,
+    #[prost(float, optional, tag = "3")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearing: Option<f32>,
+}
+
+/// Serialize a protobuf enum field by its variant name, or as the number
+/// when it is not one of `E`'s values.
+fn enum_name<E, S>(value: &Option<i32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: TryFrom<i32> + Serialize,
+    S: Serializer,
+{
+    match value.map(|v| (v, E::try_from(v))) {
+        Some((_, Ok(name))) => name.serialize(serializer),
+        Some((v, Err(_))) => serializer.serialize_i32(v),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// `TripUpdate`s for `journeys`, each paired with its service date, as of
+/// `now` (Portugal local time).
+///
+/// Completed journeys are left out. Cancelled ones are marked `CANCELED`
+/// without stop times; the others list every stop with a known delay, and
+/// cancelled stops as `SKIPPED`.
+#[must_use]
+pub fn trip_updates(journeys: &[(NaiveDate, TrainJourney)], now: NaiveDateTime) -> FeedMessage {
+    let timestamp = unix(now).and_then(|t| u64::try_from(t).ok());
+    let entity = journeys
+        .iter()
+        .filter(|(_, journey)| journey.status != JourneyStatus::Completed)
+        .filter_map(|&(date, ref journey)| {
+            let cancelled = journey.status == JourneyStatus::Cancelled;
+            let stop_time_update = if cancelled {
+                Vec::new()
+            } else {
+                gtfs::stop_updates(journey, date)
+                    .into_iter()
+                    .filter_map(stop_time_update)
+                    .collect()
+            };
+            if !cancelled && stop_time_update.is_empty() && journey.delay_minutes.is_none() {
+                return None;
+            }
+
+            let trip = trip_descriptor(journey, date, cancelled);
+            Some(FeedEntity {
+                id: trip.trip_id.clone().unwrap_or_default(),
+                trip_update: Some(TripUpdate {
+                    trip,
+                    stop_time_update,
+                    vehicle: Some(vehicle_descriptor(&journey.train_number)),
+                    timestamp,
+                    delay: journey.delay_minutes.map(|d| d * 60),
+                }),
+                vehicle: None,
+            })
+        })
+        .collect();
+
+    feed(entity, timestamp)
+}
+
+/// `VehiclePosition`s for the `trains` that have a position, matched to
+/// their running journey in `journeys` (paired with its service date) for
+/// the trip and current stop. When a train runs on two service dates, the
+/// later run is the one placed, as in the monitor's table.
+#[must_use]
+pub fn vehicle_positions(
+    trains: &[LiveTrain],
+    journeys: &[(NaiveDate, TrainJourney)],
+    now: NaiveDateTime,
+) -> FeedMessage {
+    let journeys: HashMap<_, _> = journeys
+        .iter()
+        .filter(|(_, journey)| LiveTrain::is_running(&journey.status))
+        .map(|(date, journey)| (journey.train_number.as_str(), (*date, journey)))
+        .collect();
+
+    let entity = trains
+        .iter()
+        .filter_map(|train| {
+            let position = train.position.as_ref()?;
+            let (date, journey) = match journeys.get(train.train_number.as_str()) {
+                Some(&(date, journey)) => (date, Some(journey)),
+                None => (now.date(), None),
+            };
+            let current = journey.and_then(|journey| {
+                let stop = journey.current_stop()?;
+                let index = journey.stops.iter().position(|s| std::ptr::eq(s, stop))?;
+                Some((index, stop))
+            });
+
+            #[allow(clippy::cast_possible_truncation)]
+            let coordinates = Position {
+                latitude: position.coordinates.latitude as f32,
+                longitude: position.coordinates.longitude as f32,
+                bearing: position.heading.map(|h| h as f32),
+            };
+            let trip_id = gtfs::trip_id(&train.train_number, date);
+            Some(FeedEntity {
+                id: trip_id.clone(),
+                trip_update: None,
+                vehicle: Some(VehiclePosition {
+                    trip: Some(TripDescriptor {
+                        trip_id: Some(trip_id),
+                        start_date: Some(gtfs::service_id(date)),
+                        schedule_relationship: Some(TripScheduleRelationship::Scheduled as i32),
+                        route_id: Some(gtfs::route_id(&train.operator, &train.service_type)),
+                    }),
+                    position: Some(coordinates),
+                    current_stop_sequence: current
+                        .and_then(|(index, _)| u32::try_from(index + 1).ok()),
+                    current_status: current.map(|(_, stop)| {
+                        if stop.status == StopStatus::AtStop {
+                            VehicleStopStatus::StoppedAt as i32
+                        } else {
+                            VehicleStopStatus::InTransitTo as i32
+                        }
+                    }),
+                    timestamp: unix(position.as_of).and_then(|t| u64::try_from(t).ok()),
+                    stop_id: current.map(|(_, stop)| gtfs::stop_id(&stop.station.code)),
+                    vehicle: Some(vehicle_descriptor(&train.train_number)),
+                }),
+            })
+        })
+        .collect();
+
+    feed(entity, unix(now).and_then(|t| u64::try_from(t).ok()))
+}
+
+fn feed(entity: Vec<FeedEntity>, timestamp: Option<u64>) -> FeedMessage {
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: GTFS_REALTIME_VERSION.to_string(),
+            incrementality: Some(Incrementality::FullDataset as i32),
+            timestamp,
+        },
+        entity,
+    }
+}
+
+fn trip_descriptor(journey: &TrainJourney, date: NaiveDate, cancelled: bool) -> TripDescriptor {
+    let relationship = if cancelled {
+        TripScheduleRelationship::Canceled
+    } else {
+        TripScheduleRelationship::Scheduled
+    };
+    TripDescriptor {
+        trip_id: Some(gtfs::trip_id(&journey.train_number, date)),
+        start_date: Some(gtfs::service_id(date)),
+        schedule_relationship: Some(relationship as i32),
+        route_id: Some(gtfs::route_id(&journey.operator, &journey.service_type)),
+    }
+}
+
+fn vehicle_descriptor(train_number: &str) -> VehicleDescriptor {
+    VehicleDescriptor {
+        id: Some(train_number.to_string()),
+        label: Some(train_number.to_string()),
+    }
+}
+
+/// The update for one stop, or `None` when nothing is known about it.
+fn stop_time_update(update: gtfs::StopUpdate) -> Option<StopTimeUpdate> {
+    let event = |event: gtfs::StopEvent| {
+        let delay = event.delay_minutes?;
+        Some(StopTimeEvent {
+            delay: Some(delay * 60),
+            time: event.expected().and_then(unix),
+        })
+    };
+
+    let (arrival, departure, relationship) = if update.skipped {
+        (None, None, StopScheduleRelationship::Skipped)
+    } else {
+        let (arrival, departure) = (event(update.arrival), event(update.departure));
+        if arrival.is_none() && departure.is_none() {
+            return None;
+        }
+        (arrival, departure, StopScheduleRelationship::Scheduled)
+    };
+
+    Some(StopTimeUpdate {
+        stop_sequence: Some(update.stop_sequence),
+        arrival,
+        departure,
+        stop_id: Some(update.stop_id),
+        schedule_relationship: Some(relationship as i32),
+    })
+}
+
+/// POSIX time of a Portugal-local date-time.
+fn unix(local: NaiveDateTime) -> Option<i64> {
+    Lisbon
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use comboios_core::domain::position::{Coordinates, PositionSource, TrainPosition};
+    use prost::Message;
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{at, day as date, stop};
+
+    /// Regional train that left A at 10:03 (three late), due at B 10:20 and
+    /// C 10:40.
+    fn journey(train: &str, status: JourneyStatus) -> TrainJourney {
+        let mut stops = vec![
+            stop(1, "94-1", "10:00", "10:00"),
+            stop(2, "94-2", "10:20", "10:20"),
+            stop(3, "94-3", "10:40", "10:40"),
+        ];
+        stops[0].status = StopStatus::Departed;
+        stops[0].actual_departure = Some("10:03".to_string());
+        stops[1].predicted_time = Some("10:23".to_string());
+        stops[2].predicted_time = Some("10:42".to_string());
+        TrainJourney {
+            service_type: "R|Regional".to_string(),
+            status,
+            delay_minutes: Some(3),
+            ..crate::fixtures::journey(train, stops)
+        }
+    }
+
+    #[test]
+    fn trip_updates_carry_stop_delays_and_cancellations() {
+        let journeys = [
+            (date(), journey("520", JourneyStatus::InProgress)),
+            (date(), journey("521", JourneyStatus::Cancelled)),
+            (date(), journey("522", JourneyStatus::Completed)),
+        ];
+        let feed = trip_updates(&journeys, at("10:10"));
+
+        assert_eq!(feed.header.gtfs_realtime_version, "2.0");
+        assert_eq!(feed.entity.len(), 2);
+
+        let update = feed.entity[0].trip_update.as_ref().unwrap();
+        assert_eq!(feed.entity[0].id, "520_20260314");
+        assert_eq!(update.trip.route_id.as_deref(), Some("CP-R"));
+        assert_eq!(update.delay, Some(180));
+        let delays: Vec<_> = update
+            .stop_time_update
+            .iter()
+            .map(|s| {
+                (
+                    s.stop_sequence.unwrap(),
+                    s.arrival.as_ref().and_then(|e| e.delay),
+                    s.departure.as_ref().and_then(|e| e.delay),
+                )
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            [
+                (1, None, Some(180)),
+                (2, Some(180), Some(180)),
+                (3, Some(120), Some(120))
+            ]
+        );
+        assert_eq!(
+            update.stop_time_update[1].arrival.as_ref().unwrap().time,
+            unix(at("10:23"))
+        );
+
+        let cancelled = feed.entity[1].trip_update.as_ref().unwrap();
+        assert_eq!(
+            cancelled.trip.schedule_relationship,
+            Some(TripScheduleRelationship::Canceled as i32)
+        );
+        assert!(cancelled.stop_time_update.is_empty());
+    }
+
+    #[test]
+    fn feeds_round_trip_through_protobuf() {
+        let feed = trip_updates(
+            &[(date(), journey("520", JourneyStatus::InProgress))],
+            at("10:10"),
+        );
+
+        let decoded = FeedMessage::decode(feed.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, feed);
+    }
+
+    #[test]
+    fn json_names_enums() {
+        let feed = trip_updates(
+            &[(date(), journey("521", JourneyStatus::Cancelled))],
+            at("10:10"),
+        );
+        let value = serde_json::to_value(&feed).unwrap();
+
+        assert_eq!(value["header"]["incrementality"], "FULL_DATASET");
+        assert_eq!(
+            value["entity"][0]["trip_update"]["trip"],
+            json!({
+                "trip_id": "521_20260314",
+                "start_date": "20260314",
+                "schedule_relationship": "CANCELED",
+                "route_id": "CP-R"
+            })
+        );
+    }
+
+    #[test]
+    fn vehicle_positions_place_trains_with_a_position() {
+        let mut journey = journey("520", JourneyStatus::InProgress);
+        journey.stops[1].status = StopStatus::AtStop;
+        let train = |number: &str, position: bool| {
+            let mut train = LiveTrain::from_journey(&journey, None, at("10:20"));
+            train.train_number = number.to_string();
+            train.position = position.then(|| TrainPosition {
+                train_number: number.to_string(),
+                coordinates: Coordinates::new(39.0, -9.0),
+                heading: Some(90.0),
+                segment: None,
+                source: PositionSource::Station,
+                as_of: at("10:20"),
+            });
+            train
+        };
+        let trains = [train("520", true), train("530", false)];
+        let feed = vehicle_positions(&trains, &[(date(), journey)], at("10:20"));
+
+        assert_eq!(feed.entity.len(), 1);
+        let vehicle = feed.entity[0].vehicle.as_ref().unwrap();
+        assert_eq!(
+            vehicle.trip.as_ref().unwrap().trip_id.as_deref(),
+            Some("520_20260314")
+        );
+        assert_eq!(vehicle.position.as_ref().unwrap().latitude, 39.0);
+        assert_eq!(vehicle.current_stop_sequence, Some(2));
+        assert_eq!(
+            vehicle.current_status,
+            Some(VehicleStopStatus::StoppedAt as i32)
+        );
+        assert_eq!(vehicle.stop_id.as_deref(), Some("942"));
+        assert_eq!(vehicle.timestamp, unix(at("10:20")).map(|t| t as u64));
+    }
+
+    #[test]
+    fn overnight_trains_keep_their_own_service_date() {
+        let yesterday = date().pred_opt().unwrap();
+        let mut overnight = journey("520", JourneyStatus::InProgress);
+        overnight.stops[1].status = StopStatus::AtStop;
+        let journeys = [
+            (yesterday, overnight.clone()),
+            (date(), journey("530", JourneyStatus::InProgress)),
+        ];
+        let after_midnight = date().and_hms_opt(0, 20, 0).unwrap();
+
+        let feed = trip_updates(&journeys, after_midnight);
+        let ids: Vec<_> = feed.entity.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["520_20260313", "530_20260314"]);
+        assert_eq!(
+            feed.entity[0]
+                .trip_update
+                .as_ref()
+                .unwrap()
+                .trip
+                .start_date
+                .as_deref(),
+            Some("20260313")
+        );
+
+        let mut train = LiveTrain::from_journey(&overnight, None, after_midnight);
+        train.position = Some(TrainPosition {
+            train_number: "520".to_string(),
+            coordinates: Coordinates::new(39.0, -9.0),
+            heading: None,
+            segment: None,
+            source: PositionSource::Station,
+            as_of: after_midnight,
+        });
+        let feed = vehicle_positions(&[train], &journeys, after_midnight);
+        let trip = feed.entity[0]
+            .vehicle
+            .as_ref()
+            .unwrap()
+            .trip
+            .as_ref()
+            .unwrap();
+        assert_eq!(trip.trip_id.as_deref(), Some("520_20260313"));
+        assert_eq!(trip.start_date.as_deref(), Some("20260313"));
+    }
+}
@@ -0,0 +1,691 @@
+//! Shared upstream polling for streaming clients.
+//!
+//! Every client following a train subscribes to one broadcast feed per
+//! train and date, and every client following a departure board to one feed
+//! per station. The first subscriber starts the poller behind the feed; it
+//! stops once the last subscriber has gone or the journey is over, so a
+//! popular train or station costs one upstream request per poll however many
+//! clients watch it. Board feeds are keyed by the resolved station id, so a
+//! station given by id or by name shares one poller. At most
+//! [`LiveHub::max_feeds`] feeds run at once.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use comboios_core::Comboios;
+use comboios_core::adapters::{normalize_station_id, to_cp_id};
+use comboios_core::domain::journey::TrainJourney;
+use comboios_core::domain::station_timetable::StationTimetable;
+use comboios_core::error::CoreError;
+use comboios_core::query_builder::BoardWindow;
+use comboios_core::watch::{
+    BOARD_POLL_INTERVAL, BoardEvent, JourneyEvent, JourneyTracker, next_poll,
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, broadcast};
+
+use crate::error::AppError;
+
+/// Updates buffered per feed for slow subscribers; a subscriber that falls
+/// further behind skips ahead.
+const FEED_CAPACITY: usize = 64;
+
+/// Feeds running at once unless told otherwise.
+const DEFAULT_MAX_FEEDS: usize = 200;
+
+/// How far ahead of the clock a shared departure board reaches.
+pub const BOARD_HORIZON: chrono::Duration = chrono::Duration::hours(2);
+
+/// Where the hub's pollers get their data: [`Comboios`] in the server, a
+/// fake in tests.
+pub trait Upstream: Clone + Send + Sync + 'static {
+    fn train_journey(
+        &self,
+        train_number: &str,
+        date: &str,
+    ) -> impl Future<Output = Result<TrainJourney, CoreError>> + Send;
+
+    /// Changes to the departure board of `station` from now to
+    /// [`BOARD_HORIZON`] ahead, as [`Comboios::watch_board`] reports them.
+    fn board_events(
+        &self,
+        station: &str,
+    ) -> impl Stream<Item = Result<BoardEvent, CoreError>> + Send + 'static;
+
+    /// Portugal-local time now.
+    fn now(&self) -> NaiveDateTime;
+
+    /// The id of `station`, given by id or name.
+    fn resolve_station(
+        &self,
+        station: &str,
+    ) -> impl Future<Output = Result<String, CoreError>> + Send;
+}
+
+impl Upstream for Comboios {
+    fn train_journey(
+        &self,
+        train_number: &str,
+        date: &str,
+    ) -> impl Future<Output = Result<TrainJourney, CoreError>> + Send {
+        self.get_train_journey(train_number, date)
+    }
+
+    fn board_events(
+        &self,
+        station: &str,
+    ) -> impl Stream<Item = Result<BoardEvent, CoreError>> + Send + 'static {
+        let now = Upstream::now(self);
+        self.watch_board(
+            station,
+            BoardWindow::new(now..now + BOARD_HORIZON),
+            BOARD_POLL_INTERVAL,
+        )
+    }
+
+    fn now(&self) -> NaiveDateTime {
+        self.clock().now().naive_local()
+    }
+
+    fn resolve_station(
+        &self,
+        station: &str,
+    ) -> impl Future<Output = Result<String, CoreError>> + Send {
+        Comboios::resolve_station(self, station)
+    }
+}
+
+/// One message on a train feed.
+#[derive(Debug, Clone)]
+pub enum TrainUpdate {
+    /// The latest journey, sent after every successful poll.
+    Journey(Arc<TrainJourney>),
+    /// A change found between two polls.
+    Event(JourneyEvent),
+    /// A poll failed; the poller carries on.
+    Error(String),
+}
+
+/// A board or train change, serialized as the core event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Change {
+    Board(BoardEvent),
+    Train(JourneyEvent),
+}
+
+/// Feed key: train number and service date.
+type TrainKey = (String, String);
+
+#[derive(Debug)]
+struct TrainFeed {
+    sender: broadcast::Sender<TrainUpdate>,
+    latest: Option<Arc<TrainJourney>>,
+    /// Held while the feed runs, counting it against the feed limit.
+    _permit: OwnedSemaphorePermit,
+}
+
+/// A subscription to a train feed: the latest journey, when one has been
+/// fetched already, and the receiver for what comes next.
+#[derive(Debug)]
+pub struct TrainSubscription {
+    pub latest: Option<Arc<TrainJourney>>,
+    pub updates: broadcast::Receiver<TrainUpdate>,
+}
+
+/// One message on a board feed.
+#[derive(Debug, Clone)]
+pub enum BoardUpdate {
+    Event(BoardEvent),
+    /// A poll failed; the poller carries on.
+    Error(String),
+}
+
+#[derive(Debug)]
+struct BoardFeed {
+    sender: broadcast::Sender<BoardUpdate>,
+    /// The board as the events sent so far describe it.
+    trains: Vec<StationTimetable>,
+    /// Held while the feed runs, counting it against the feed limit.
+    _permit: OwnedSemaphorePermit,
+}
+
+/// A subscription to a board feed: the trains on the board now and the
+/// receiver for the changes that follow.
+#[derive(Debug)]
+pub struct BoardSubscription {
+    pub trains: Vec<StationTimetable>,
+    pub updates: broadcast::Receiver<BoardUpdate>,
+}
+
+/// Why a feed could not be subscribed to.
+#[derive(Debug, thiserror::Error)]
+pub enum HubError {
+    /// Starting its poller would exceed [`LiveHub::max_feeds`].
+    #[error("live feed limit of {0} reached")]
+    Full(usize),
+    /// The station could not be resolved.
+    #[error(transparent)]
+    Station(#[from] CoreError),
+}
+
+impl From<HubError> for AppError {
+    fn from(e: HubError) -> Self {
+        match e {
+            HubError::Full(_) => AppError::TooManyRequests(e.to_string()),
+            HubError::Station(e) => AppError::CoreError(e),
+        }
+    }
+}
+
+/// Registry of the train and board pollers currently running.
+#[derive(Debug, Clone)]
+pub struct LiveHub<U = Comboios> {
+    api: U,
+    interval: Duration,
+    trains: Arc<Mutex<HashMap<TrainKey, TrainFeed>>>,
+    boards: Arc<Mutex<HashMap<String, BoardFeed>>>,
+    /// One permit per feed that may run.
+    feeds: Arc<Semaphore>,
+    max_feeds: usize,
+}
+
+impl<U: Upstream> LiveHub<U> {
+    /// A hub polling running trains every `interval`.
+    pub fn new(api: U, interval: Duration) -> Self {
+        Self {
+            api,
+            interval,
+            trains: Arc::default(),
+            boards: Arc::default(),
+            feeds: Arc::new(Semaphore::new(DEFAULT_MAX_FEEDS)),
+            max_feeds: DEFAULT_MAX_FEEDS,
+        }
+    }
+
+    /// Run at most `max` train and board feeds at once; subscribing to
+    /// another one fails with [`HubError::Full`] until one stops.
+    #[must_use]
+    pub fn max_feeds(mut self, max: usize) -> Self {
+        self.feeds = Arc::new(Semaphore::new(max));
+        self.max_feeds = max;
+        self
+    }
+
+    /// Subscribe to `train_number` on `date`, starting its poller if no one
+    /// else is following it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HubError::Full`] if its poller would exceed the feed limit.
+    pub fn subscribe_train(
+        &self,
+        train_number: &str,
+        date: &str,
+    ) -> Result<TrainSubscription, HubError> {
+        let key = (train_number.to_string(), date.to_string());
+        let mut trains = self.trains.lock().expect("train feeds lock poisoned");
+
+        if let Some(feed) = trains.get(&key) {
+            return Ok(TrainSubscription {
+                latest: feed.latest.clone(),
+                updates: feed.sender.subscribe(),
+            });
+        }
+
+        let permit = self.permit()?;
+        let (sender, updates) = broadcast::channel(FEED_CAPACITY);
+        trains.insert(
+            key.clone(),
+            TrainFeed {
+                sender: sender.clone(),
+                latest: None,
+                _permit: permit,
+            },
+        );
+        tokio::spawn(self.clone().poll_train(key, sender));
+
+        Ok(TrainSubscription {
+            latest: None,
+            updates,
+        })
+    }
+
+    /// Number of trains being polled.
+    pub fn train_pollers(&self) -> usize {
+        self.trains.lock().expect("train feeds lock poisoned").len()
+    }
+
+    /// Subscribe to the departure board of `station` (id or name), starting
+    /// its poller if no one else is following it. The board is polled every
+    /// [`BOARD_POLL_INTERVAL`](comboios_core::watch::BOARD_POLL_INTERVAL).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HubError::Station`] if `station` cannot be resolved, or
+    /// [`HubError::Full`] if its poller would exceed the feed limit.
+    pub async fn subscribe_board(&self, station: &str) -> Result<BoardSubscription, HubError> {
+        let station = self.api.resolve_station(station.trim()).await?;
+        let key = to_cp_id(&normalize_station_id(&station));
+        let mut boards = self.boards.lock().expect("board feeds lock poisoned");
+
+        if let Some(feed) = boards.get(&key) {
+            return Ok(BoardSubscription {
+                trains: feed.trains.clone(),
+                updates: feed.sender.subscribe(),
+            });
+        }
+
+        let permit = self.permit()?;
+        let (sender, updates) = broadcast::channel(FEED_CAPACITY);
+        boards.insert(
+            key.clone(),
+            BoardFeed {
+                sender: sender.clone(),
+                trains: Vec::new(),
+                _permit: permit,
+            },
+        );
+        tokio::spawn(self.clone().poll_board(key, sender));
+
+        Ok(BoardSubscription {
+            trains: Vec::new(),
+            updates,
+        })
+    }
+
+    /// Number of boards being polled.
+    pub fn board_pollers(&self) -> usize {
+        self.boards.lock().expect("board feeds lock poisoned").len()
+    }
+
+    /// Portugal-local time now, by the upstream's clock.
+    pub fn now(&self) -> NaiveDateTime {
+        self.api.now()
+    }
+
+    fn permit(&self) -> Result<OwnedSemaphorePermit, HubError> {
+        self.feeds
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| HubError::Full(self.max_feeds))
+    }
+
+    async fn poll_train(self, key: TrainKey, sender: broadcast::Sender<TrainUpdate>) {
+        let (train_number, date) = &key;
+        tracing::debug!("Starting poller for train {train_number} on {date}");
+        let mut tracker = JourneyTracker::new();
+
+        loop {
+            let wait = match self.api.train_journey(train_number, date).await {
+                Ok(journey) => {
+                    let wait = next_poll(&journey, self.api.now(), self.interval);
+                    let events = tracker.update(journey.clone());
+                    let journey = Arc::new(journey);
+                    if let Some(feed) = self
+                        .trains
+                        .lock()
+                        .expect("train feeds lock poisoned")
+                        .get_mut(&key)
+                    {
+                        feed.latest = Some(journey.clone());
+                    }
+                    // Sending only fails without subscribers, which is
+                    // checked below.
+                    let _ = sender.send(TrainUpdate::Journey(journey));
+                    for event in events {
+                        let _ = sender.send(TrainUpdate::Event(event));
+                    }
+                    wait
+                }
+                Err(e) => {
+                    tracing::warn!("Poll of train {train_number} failed: {e}");
+                    let _ = sender.send(TrainUpdate::Error(e.to_string()));
+                    Some(self.interval)
+                }
+            };
+
+            let Some(wait) = wait else {
+                break;
+            };
+            tokio::select! {
+                () = tokio::time::sleep(wait) => {}
+                () = sender.closed() => {}
+            }
+
+            // Checked under the lock so a client subscribing right now either
+            // keeps this poller alive or starts a new one.
+            let mut trains = self.trains.lock().expect("train feeds lock poisoned");
+            if sender.receiver_count() == 0 {
+                trains.remove(&key);
+                tracing::debug!("No subscribers left for train {train_number}, poller stopped");
+                return;
+            }
+        }
+
+        self.trains
+            .lock()
+            .expect("train feeds lock poisoned")
+            .remove(&key);
+        tracing::debug!("Train {train_number} journey over, poller stopped");
+    }
+
+    async fn poll_board(self, station: String, sender: broadcast::Sender<BoardUpdate>) {
+        tracing::debug!("Starting poller for board {station}");
+        let events = self.api.board_events(&station);
+        futures::pin_mut!(events);
+
+        loop {
+            tokio::select! {
+                next = events.next() => {
+                    let update = match next {
+                        Some(Ok(event)) => BoardUpdate::Event(event),
+                        Some(Err(e)) => {
+                            tracing::warn!("Poll of board {station} failed: {e}");
+                            BoardUpdate::Error(e.to_string())
+                        }
+                        None => break,
+                    };
+                    // Applied and sent under the lock so a new subscriber's
+                    // snapshot and its first update never overlap.
+                    let mut boards = self.boards.lock().expect("board feeds lock poisoned");
+                    if let (Some(feed), BoardUpdate::Event(event)) =
+                        (boards.get_mut(&station), &update)
+                    {
+                        apply_board_event(&mut feed.trains, event);
+                    }
+                    let _ = sender.send(update);
+                }
+                () = sender.closed() => {}
+            }
+
+            let mut boards = self.boards.lock().expect("board feeds lock poisoned");
+            if sender.receiver_count() == 0 {
+                boards.remove(&station);
+                tracing::debug!("No subscribers left for board {station}, poller stopped");
+                return;
+            }
+        }
+
+        self.boards
+            .lock()
+            .expect("board feeds lock poisoned")
+            .remove(&station);
+    }
+}
+
+/// Bring a board snapshot up to date with `event`.
+pub(crate) fn apply_board_event(trains: &mut Vec<StationTimetable>, event: &BoardEvent) {
+    fn row(trains: &mut [StationTimetable], number: u64) -> Option<&mut StationTimetable> {
+        trains.iter_mut().find(|row| row.train_number == number)
+    }
+
+    match event {
+        BoardEvent::Added { train } => trains.push(train.as_ref().clone()),
+        BoardEvent::Removed { train_number } | BoardEvent::Departed { train_number, .. } => {
+            trains.retain(|row| row.train_number != *train_number);
+        }
+        BoardEvent::PlatformAssigned {
+            train_number,
+            platform: current,
+        }
+        | BoardEvent::PlatformChanged {
+            train_number,
+            current,
+            ..
+        } => {
+            if let Some(row) = row(trains, *train_number) {
+                row.platform = Some(current.clone());
+            }
+        }
+        BoardEvent::DelayChanged {
+            train_number,
+            current,
+            ..
+        } => {
+            if let Some(row) = row(trains, *train_number) {
+                row.delay = *current;
+            }
+        }
+        BoardEvent::Cancelled { train_number } => {
+            if let Some(row) = row(trains, *train_number) {
+                row.observations = Some("Suprimido".to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use comboios_core::domain::journey::JourneyStatus;
+    use futures::stream;
+    use tokio::sync::broadcast::error::RecvError;
+
+    use super::*;
+    use crate::fixtures::{at, journey, row};
+
+    /// Serves the same journey with `status` and a board adding train 1,
+    /// then giving it platform 3; counts the polls and board pollers.
+    #[derive(Debug, Clone)]
+    struct Fake {
+        status: JourneyStatus,
+        polls: Arc<AtomicUsize>,
+    }
+
+    fn board_row(train_number: u64) -> StationTimetable {
+        row(train_number, "94-1", "94-2")
+    }
+
+    impl Upstream for Fake {
+        async fn train_journey(
+            &self,
+            train_number: &str,
+            _date: &str,
+        ) -> Result<TrainJourney, CoreError> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            Ok(TrainJourney {
+                status: self.status.clone(),
+                ..journey(train_number, Vec::new())
+            })
+        }
+
+        fn board_events(
+            &self,
+            _station: &str,
+        ) -> impl Stream<Item = Result<BoardEvent, CoreError>> + Send + 'static {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            let events = vec![
+                Ok(BoardEvent::Added {
+                    train: Box::new(board_row(1)),
+                }),
+                Ok(BoardEvent::PlatformAssigned {
+                    train_number: 1,
+                    platform: "3".to_string(),
+                }),
+            ];
+            stream::iter(events).chain(stream::pending())
+        }
+
+        fn now(&self) -> NaiveDateTime {
+            at("10:00")
+        }
+
+        async fn resolve_station(&self, station: &str) -> Result<String, CoreError> {
+            match station {
+                "Lisboa - Oriente" => Ok("94-31039".to_string()),
+                id if id.starts_with("94") => Ok(id.to_string()),
+                _ => Err(CoreError::InvalidInput(format!(
+                    "unknown station: {station}"
+                ))),
+            }
+        }
+    }
+
+    fn hub(status: JourneyStatus) -> (LiveHub<Fake>, Arc<AtomicUsize>) {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let fake = Fake {
+            status,
+            polls: polls.clone(),
+        };
+        (LiveHub::new(fake, Duration::from_millis(20)), polls)
+    }
+
+    async fn next_journey(updates: &mut broadcast::Receiver<TrainUpdate>) -> Arc<TrainJourney> {
+        loop {
+            if let TrainUpdate::Journey(journey) = updates.recv().await.unwrap() {
+                return journey;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribers_share_one_poller_until_the_last_leaves() {
+        let (hub, polls) = hub(JourneyStatus::InProgress);
+
+        let mut first = hub.subscribe_train("100", "2026-03-14").unwrap();
+        let mut second = hub.subscribe_train("100", "2026-03-14").unwrap();
+        assert_eq!(hub.train_pollers(), 1);
+
+        let a = next_journey(&mut first.updates).await;
+        let b = next_journey(&mut second.updates).await;
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(polls.load(Ordering::SeqCst), 1);
+
+        // A late subscriber starts from the latest journey.
+        let late = hub.subscribe_train("100", "2026-03-14").unwrap();
+        assert!(late.latest.is_some());
+
+        drop((first, second, late));
+        for _ in 0..50 {
+            if hub.train_pollers() == 0 {
+                let stopped_at = polls.load(Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(60)).await;
+                assert_eq!(polls.load(Ordering::SeqCst), stopped_at);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("poller still running without subscribers");
+    }
+
+    #[tokio::test]
+    async fn feed_closes_when_the_journey_is_over() {
+        let (hub, _) = hub(JourneyStatus::Completed);
+        let mut subscription = hub.subscribe_train("100", "2026-03-14").unwrap();
+
+        assert!(matches!(
+            subscription.updates.recv().await,
+            Ok(TrainUpdate::Journey(_))
+        ));
+        assert!(matches!(
+            subscription.updates.recv().await,
+            Ok(TrainUpdate::Event(JourneyEvent::Completed))
+        ));
+        assert!(matches!(
+            subscription.updates.recv().await,
+            Err(RecvError::Closed)
+        ));
+        assert_eq!(hub.train_pollers(), 0);
+    }
+
+    #[tokio::test]
+    async fn board_subscribers_share_one_poller_and_get_the_board_so_far() {
+        let (hub, polls) = hub(JourneyStatus::InProgress);
+
+        let mut first = hub.subscribe_board("94-1").await.unwrap();
+        assert!(first.trains.is_empty());
+        assert!(matches!(
+            first.updates.recv().await,
+            Ok(BoardUpdate::Event(BoardEvent::Added { .. }))
+        ));
+        assert!(matches!(
+            first.updates.recv().await,
+            Ok(BoardUpdate::Event(BoardEvent::PlatformAssigned { .. }))
+        ));
+
+        let second = hub.subscribe_board(" 94-1 ").await.unwrap();
+        assert_eq!(hub.board_pollers(), 1);
+        assert_eq!(polls.load(Ordering::SeqCst), 1);
+        assert_eq!(second.trains.len(), 1);
+        assert_eq!(second.trains[0].platform.as_deref(), Some("3"));
+
+        drop((first, second));
+        for _ in 0..50 {
+            if hub.board_pollers() == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("board poller still running without subscribers");
+    }
+
+    #[tokio::test]
+    async fn a_station_by_name_and_by_id_shares_one_board_feed() {
+        let (hub, _) = hub(JourneyStatus::InProgress);
+
+        let by_name = hub.subscribe_board("Lisboa - Oriente").await.unwrap();
+        let by_id = hub.subscribe_board("9431039").await.unwrap();
+        assert_eq!(hub.board_pollers(), 1);
+        assert!(matches!(
+            hub.subscribe_board("Atlantis").await,
+            Err(HubError::Station(CoreError::InvalidInput(_)))
+        ));
+        drop((by_name, by_id));
+    }
+
+    #[tokio::test]
+    async fn feeds_beyond_the_limit_are_refused_until_one_stops() {
+        let (hub, _) = hub(JourneyStatus::InProgress);
+        let hub = hub.max_feeds(2);
+
+        let train = hub.subscribe_train("100", "2026-03-14").unwrap();
+        let board = hub.subscribe_board("94-1").await.unwrap();
+        // Joining a running feed needs no room.
+        let again = hub.subscribe_train("100", "2026-03-14").unwrap();
+        assert!(matches!(
+            hub.subscribe_train("200", "2026-03-14"),
+            Err(HubError::Full(2))
+        ));
+
+        drop((train, again));
+        for _ in 0..50 {
+            if hub.subscribe_train("200", "2026-03-14").is_ok() {
+                drop(board);
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("stopped feed still counted against the limit");
+    }
+
+    #[test]
+    fn board_snapshot_follows_events() {
+        let mut trains = vec![board_row(1), board_row(2)];
+        apply_board_event(
+            &mut trains,
+            &BoardEvent::DelayChanged {
+                train_number: 2,
+                previous: None,
+                current: Some(5),
+            },
+        );
+        apply_board_event(&mut trains, &BoardEvent::Cancelled { train_number: 2 });
+        apply_board_event(
+            &mut trains,
+            &BoardEvent::Departed {
+                train_number: 1,
+                time: None,
+            },
+        );
+
+        assert_eq!(trains.len(), 1);
+        assert_eq!(trains[0].delay, Some(5));
+        assert!(trains[0].is_cancelled());
+    }
+}
@@ -1,6 +1,15 @@
+pub mod auth;
+pub mod commutes;
 pub mod configuration;
 pub mod domain;
 pub mod error;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod gtfs_rt;
+pub mod hub;
+pub mod mqtt;
 pub mod routes;
 pub mod startup;
+pub mod store;
 pub mod telemetry;
+pub mod webhooks;
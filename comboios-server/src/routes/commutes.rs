@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use comboios_core::domain::calendar::Calendar;
+use comboios_core::error::CoreError;
+use futures::stream::{self, StreamExt};
+use uuid::Uuid;
+
+use crate::{
+    auth::Caller,
+    commutes::{Commute, NewCommute},
+    domain::{AppResponse, AppState},
+    error::AppError,
+};
+
+const CALENDAR: &str = "text/calendar; charset=utf-8";
+
+/// Journeys fetched at once for a commute calendar.
+const CALENDAR_CONCURRENCY: usize = 4;
+
+/// # Errors
+///
+/// Returns [`AppError::TooManyRequests`] if the key has saved
+/// `COMMUTE_MAX_PER_KEY` commutes already, or [`AppError`] if the request is
+/// invalid or the commute cannot be stored.
+#[tracing::instrument(skip_all)]
+pub async fn create_commute(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+    Json(request): Json<NewCommute>,
+) -> Result<(StatusCode, Json<AppResponse<Commute>>), AppError> {
+    let created_at = state.api.clock().now().to_rfc3339();
+    let commute = request.into_commute(&caller, created_at)?;
+    let max = state.settings.commute_max_per_key;
+    let fits =
+        |commutes: &[Commute]| commutes.iter().filter(|c| c.owner == commute.owner).count() < max;
+    if !state.commutes.insert_if(commute.clone(), fits)? {
+        return Err(AppError::TooManyRequests(format!(
+            "commute limit of {max} reached"
+        )));
+    }
+
+    Ok((StatusCode::CREATED, Json(AppResponse { data: commute })))
+}
+
+/// The commutes saved with the caller's API key.
+#[tracing::instrument(skip_all)]
+pub async fn list_commutes(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+) -> Json<AppResponse<Vec<Commute>>> {
+    let mut commutes = state.commutes.list();
+    commutes.retain(|commute| commute.owner == caller.id());
+    Json(AppResponse { data: commutes })
+}
+
+/// # Errors
+///
+/// Returns [`AppError::NotFound`] for an unknown commute or one saved with
+/// another API key, or [`AppError`] if the store cannot be saved.
+#[tracing::instrument(skip(state, caller))]
+pub async fn delete_commute(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let owned = state
+        .commutes
+        .get(id)
+        .is_some_and(|commute| commute.owner == caller.id());
+    if owned && state.commutes.remove(id)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!("commute {id}")))
+    }
+}
+
+/// iCalendar subscription of a commute: one event per train on each commute
+/// day over the next `COMMUTE_CALENDAR_DAYS`. Journeys are fetched live, or
+/// taken from the [`JourneyCache`](crate::commutes::JourneyCache) when
+/// another refresh fetched them recently. Trains that do not run on a day
+/// are left out; so are those whose fetch failed, which are not cached and
+/// are tried again on the next refresh. Calendar apps cannot send an API key, so the commute's id
+/// is all it takes.
+///
+/// The router cannot match a parameter followed by `.ics` within a segment,
+/// so this is `GET /commutes/{file}` and checks the extension itself.
+///
+/// # Errors
+///
+/// Returns [`AppError::NotFound`] for an unknown commute or a file other
+/// than `{id}.ics`.
+#[tracing::instrument(skip(state))]
+pub async fn commute_calendar(
+    State(state): State<Arc<AppState>>,
+    Path(file): Path<String>,
+) -> Result<Response, AppError> {
+    let id = file
+        .strip_suffix(".ics")
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| AppError::NotFound(format!("commute calendar {file}")))?;
+    let commute = state
+        .commutes
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("commute {id}")))?;
+
+    let today = state.api.clock().today();
+    let runs: Vec<_> = commute
+        .dates(today, state.settings.commute_calendar_days)
+        .into_iter()
+        .flat_map(|date| {
+            commute
+                .trains
+                .iter()
+                .map(move |train| (date, train.clone()))
+        })
+        .collect();
+    let journeys: Vec<_> = stream::iter(runs)
+        .map(|(date, train)| {
+            let state = state.clone();
+            async move {
+                if let Some(journey) = state.commute_journeys.get(&train, date) {
+                    return journey.map(|journey| (date, journey));
+                }
+                let service_date = date.format("%Y-%m-%d").to_string();
+                let journey = match state.api.get_train_journey(&train, &service_date).await {
+                    Ok(journey) => Some(journey),
+                    // The train does not run that day, which will not change
+                    // before the cache expires.
+                    Err(CoreError::ApiError { status: 404, .. }) => None,
+                    Err(e) => {
+                        tracing::debug!("No journey for train {train} on {service_date}: {e}");
+                        return None;
+                    }
+                };
+                state.commute_journeys.insert(train, date, journey.clone());
+                journey.map(|journey| (date, journey))
+            }
+        })
+        .buffered(CALENDAR_CONCURRENCY)
+        .collect()
+        .await;
+
+    let calendar = journeys.iter().flatten().fold(
+        subscription(&state).name(commute.name.clone()),
+        |calendar, (date, journey)| calendar.journey(journey, *date),
+    );
+    Ok(ics(&calendar))
+}
+
+/// An empty calendar stamped now, refreshed every
+/// `CALENDAR_REFRESH_SECS`.
+pub(crate) fn subscription(state: &AppState) -> Calendar {
+    Calendar::new(state.api.clock().now().with_timezone(&Utc))
+        .refresh_interval(state.settings.calendar_refresh_interval)
+}
+
+pub(crate) fn ics(calendar: &Calendar) -> Response {
+    ([(header::CONTENT_TYPE, CALENDAR)], calendar.to_string()).into_response()
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use prost::Message;
+
+use crate::domain::AppState;
+use crate::gtfs_rt::{self, FeedMessage};
+
+const PROTOBUF: &str = "application/x-protobuf";
+
+/// GTFS-Realtime `TripUpdate`s for the trains found by the last network
+/// sweep, as protobuf.
+#[tracing::instrument(skip(state))]
+pub async fn trip_updates(State(state): State<Arc<AppState>>) -> Response {
+    protobuf(&trip_updates_feed(&state).await)
+}
+
+/// [`trip_updates`] as JSON, for debugging.
+#[tracing::instrument(skip(state))]
+pub async fn trip_updates_json(State(state): State<Arc<AppState>>) -> Json<FeedMessage> {
+    Json(trip_updates_feed(&state).await)
+}
+
+/// GTFS-Realtime `VehiclePosition`s for the running trains that can be
+/// placed, as protobuf.
+#[tracing::instrument(skip(state))]
+pub async fn vehicle_positions(State(state): State<Arc<AppState>>) -> Response {
+    protobuf(&vehicle_positions_feed(&state).await)
+}
+
+/// [`vehicle_positions`] as JSON, for debugging.
+#[tracing::instrument(skip(state))]
+pub async fn vehicle_positions_json(State(state): State<Arc<AppState>>) -> Json<FeedMessage> {
+    Json(vehicle_positions_feed(&state).await)
+}
+
+/// Until the first sweep finishes the feeds are empty, timestamped now.
+async fn trip_updates_feed(state: &AppState) -> FeedMessage {
+    let swept = state.monitor.last_sweep().await;
+    let now = swept.unwrap_or_else(|| state.api.clock().now().naive_local());
+    let journeys = state.monitor.journeys().await;
+    gtfs_rt::trip_updates(&journeys, now)
+}
+
+async fn vehicle_positions_feed(state: &AppState) -> FeedMessage {
+    let swept = state.monitor.last_sweep().await;
+    let now = swept.unwrap_or_else(|| state.api.clock().now().naive_local());
+    let (trains, journeys) = (state.monitor.trains().await, state.monitor.journeys().await);
+    gtfs_rt::vehicle_positions(&trains, &journeys, now)
+}
+
+fn protobuf(feed: &FeedMessage) -> Response {
+    ([(header::CONTENT_TYPE, PROTOBUF)], feed.encode_to_vec()).into_response()
+}
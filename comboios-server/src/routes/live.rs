@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDateTime;
+use comboios_core::monitor::LiveTrain;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::domain::{AppResponse, AppState};
+
+/// Snapshot of the trains currently running on the network.
+#[derive(Debug, Serialize)]
+pub struct LiveTrains {
+    /// Portugal-local time of the last completed sweep; `None` until the
+    /// first sweep finishes.
+    pub updated_at: Option<NaiveDateTime>,
+    pub trains: Vec<LiveTrain>,
+}
+
+/// Every train the network monitor has seen running, with its status,
+/// delay and position.
+#[tracing::instrument(skip(state))]
+pub async fn live_trains(State(state): State<Arc<AppState>>) -> Json<AppResponse<LiveTrains>> {
+    Json(AppResponse {
+        data: LiveTrains {
+            updated_at: state.monitor.last_sweep().await,
+            trains: state.monitor.trains().await,
+        },
+    })
+}
+
+/// The same snapshot as [`live_trains`], as a `GeoJSON` `FeatureCollection`
+/// with one point per train that can be placed on the map.
+#[tracing::instrument(skip(state))]
+pub async fn live_trains_geojson(State(state): State<Arc<AppState>>) -> Response {
+    geojson(&feature_collection(&state.monitor.trains().await))
+}
+
+/// Serve `collection` as `application/geo+json`.
+pub(crate) fn geojson(collection: &Value) -> Response {
+    (
+        [(header::CONTENT_TYPE, "application/geo+json")],
+        Json(collection),
+    )
+        .into_response()
+}
+
+/// Build a `GeoJSON` `FeatureCollection` from `trains`, skipping those
+/// without a position. Coordinates are `[longitude, latitude]`.
+fn feature_collection(trains: &[LiveTrain]) -> Value {
+    let features: Vec<Value> = trains
+        .iter()
+        .filter_map(|train| {
+            let position = train.position.as_ref()?;
+            Some(json!({
+                "type": "Feature",
+                "id": train.train_number,
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [
+                        position.coordinates.longitude,
+                        position.coordinates.latitude,
+                    ],
+                },
+                "properties": {
+                    "train_number": train.train_number,
+                    "service_type": train.service_type,
+                    "origin": train.origin.designation,
+                    "destination": train.destination.designation,
+                    "status": train.status,
+                    "delay_minutes": train.delay_minutes,
+                    "heading": position.heading,
+                    "source": position.source,
+                    "next_stop": train.next_stop.as_ref().map(|s| &s.designation),
+                    "progress": position.segment.as_ref().map(|s| s.progress),
+                },
+            }))
+        })
+        .collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use comboios_core::domain::journey::JourneyStatus;
+    use comboios_core::domain::position::{Coordinates, PositionSource, TrainPosition};
+    use comboios_core::domain::station::Station;
+
+    fn station(name: &str) -> Station {
+        Station {
+            code: format!("94-{name}"),
+            designation: name.to_string(),
+        }
+    }
+
+    fn train(number: &str, coordinates: Option<Coordinates>) -> LiveTrain {
+        let now = NaiveDate::from_ymd_opt(2026, 3, 14)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        LiveTrain {
+            train_number: number.to_string(),
+            service_type: "IC|Intercidades".to_string(),
+            origin: station("Lisboa"),
+            destination: station("Porto"),
+            operator: "CP".to_string(),
+            status: JourneyStatus::InProgress,
+            delay_minutes: Some(3),
+            next_stop: Some(station("Coimbra")),
+            predicted_arrival: None,
+            position: coordinates.map(|coordinates| TrainPosition {
+                train_number: number.to_string(),
+                coordinates,
+                heading: Some(10.0),
+                segment: None,
+                source: PositionSource::Interpolated,
+                as_of: now,
+            }),
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn features_skip_trains_without_position() {
+        let collection = feature_collection(&[
+            train("520", Some(Coordinates::new(39.5, -8.5))),
+            train("521", None),
+        ]);
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+
+        let feature = &features[0];
+        assert_eq!(feature["geometry"]["coordinates"], json!([-8.5, 39.5]));
+        assert_eq!(feature["properties"]["train_number"], "520");
+        assert_eq!(feature["properties"]["status"], "IN_PROGRESS");
+        assert_eq!(feature["properties"]["source"], "INTERPOLATED");
+        assert_eq!(feature["properties"]["next_stop"], "Coimbra");
+        assert_eq!(feature["properties"]["progress"], Value::Null);
+    }
+}
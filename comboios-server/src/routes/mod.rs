@@ -1,6 +1,12 @@
+pub mod commutes;
 pub mod diagnostics;
+pub mod gtfs_rt;
 pub mod health_check;
+pub mod live;
 pub mod refresh;
 pub mod station_timetables;
 pub mod stations;
 pub mod trains;
+pub mod trips;
+pub mod webhooks;
+pub mod ws;
@@ -2,17 +2,23 @@ use std::sync::Arc;
 
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    response::Response,
 };
-use chrono::{DateTime, NaiveDate};
-use chrono::{Duration, TimeZone, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use chrono::{Duration, TimeZone};
 use chrono_tz::Europe::Lisbon;
 use chrono_tz::Tz;
 use comboios_core::domain::station_timetable::{StationBoard, StationTimetable};
+use comboios_core::error::CoreError;
+use comboios_core::query_builder::{BoardFilter, BoardStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 
 use crate::{
     domain::{AppResponse, AppState},
     error::AppError,
+    routes::live::geojson,
 };
 
 /// How far back in time to ask CP for train movements. CP filters `start` by
@@ -21,20 +27,145 @@ use crate::{
 /// locally afterwards using their real effective time.
 const LOOKBACK: Duration = Duration::minutes(60);
 
+/// Upper bound used when only `from` is given in [`TimetableQuery`].
+const END_OF_DAY: NaiveTime = NaiveTime::from_hms_opt(23, 59, 59).expect("valid time");
+
+/// Optional board filters accepted as query parameters, e.g.
+/// `?service_type=IC&destination=Porto&status=delayed&from=17:00&to=19:00`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimetableQuery {
+    pub service_type: Option<String>,
+    /// Origin station id or name fragment.
+    pub origin: Option<String>,
+    /// Destination station id or name fragment.
+    pub destination: Option<String>,
+    pub platform: Option<String>,
+    pub operator: Option<String>,
+    /// `delayed` or `cancelled`.
+    pub status: Option<BoardStatus>,
+    /// Minimum delay in minutes.
+    pub min_delay: Option<i32>,
+    /// Start of the time range, `HH:MM`.
+    pub from: Option<String>,
+    /// End of the time range, `HH:MM`.
+    pub to: Option<String>,
+}
+
+impl TimetableQuery {
+    /// Build the core [`BoardFilter`] described by these parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError`] wrapping [`CoreError::InvalidInput`] when `from` or
+    /// `to` is not a valid `HH:MM` time.
+    pub fn to_filter(&self) -> Result<BoardFilter, AppError> {
+        let mut filter = BoardFilter::new();
+
+        if let Some(ref v) = self.service_type {
+            filter = filter.service_type(v);
+        }
+        if let Some(ref v) = self.origin {
+            filter = filter.origin(v);
+        }
+        if let Some(ref v) = self.destination {
+            filter = filter.destination(v);
+        }
+        if let Some(ref v) = self.platform {
+            filter = filter.platform(v);
+        }
+        if let Some(ref v) = self.operator {
+            filter = filter.operator(v);
+        }
+        if let Some(status) = self.status {
+            filter = filter.status(status);
+        }
+        if let Some(min) = self.min_delay {
+            filter = filter.min_delay(min);
+        }
+
+        let from = self.from.as_deref().map(parse_hhmm).transpose()?;
+        let to = self.to.as_deref().map(parse_hhmm).transpose()?;
+        if from.is_some() || to.is_some() {
+            filter = filter.time_range(from.unwrap_or(NaiveTime::MIN), to.unwrap_or(END_OF_DAY));
+        }
+
+        Ok(filter)
+    }
+}
+
+fn parse_hhmm(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| {
+        AppError::CoreError(CoreError::InvalidInput(format!(
+            "invalid time '{value}', expected HH:MM"
+        )))
+    })
+}
+
 /// # Errors
 ///
-/// Returns [`AppError`] if the CP API call fails.
+/// Returns [`AppError`] if the query parameters are invalid or the CP API call
+/// fails.
 #[tracing::instrument(skip(state))]
 pub async fn station_timetables(
     State(state): State<Arc<AppState>>,
     Path(station_id): Path<String>,
+    Query(query): Query<TimetableQuery>,
 ) -> Result<Json<AppResponse<Vec<StationBoard>>>, AppError> {
     tracing::info!("Finding timetable for station {}", station_id);
 
-    // CP times are Portugal-local; always compute "now" in Europe/Lisbon so
-    // boards are identical regardless of the host timezone (containers run in
-    // UTC and would otherwise show trains that already departed).
-    let now = Utc::now().with_timezone(&Lisbon);
+    let boards = upcoming_boards(&state, &station_id, &query).await?;
+
+    Ok(Json(AppResponse { data: boards }))
+}
+
+/// The same board as [`station_timetables`], as a `GeoJSON`
+/// `FeatureCollection`: the station as a point and each train as a line
+/// from its origin through the station to its destination.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if the query parameters are invalid or the CP API call
+/// fails.
+#[tracing::instrument(skip(state))]
+pub async fn station_timetable_geojson(
+    State(state): State<Arc<AppState>>,
+    Path(station_id): Path<String>,
+    Query(query): Query<TimetableQuery>,
+) -> Result<Response, AppError> {
+    tracing::info!("Exporting timetable for station {station_id} as GeoJSON");
+
+    let boards = upcoming_boards(&state, &station_id, &query).await?;
+    let stations = state.api.station_index().await;
+    // CP answers with one board per station id; join them into one map.
+    let features: Vec<Value> = boards
+        .iter()
+        .flat_map(|board| {
+            board.to_geojson(&stations)["features"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    Ok(geojson(&json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })))
+}
+
+/// Boards of `station_id` for today with the trains that have not passed
+/// yet, narrowed by `query`.
+async fn upcoming_boards(
+    state: &AppState,
+    station_id: &str,
+    query: &TimetableQuery,
+) -> Result<Vec<StationBoard>, AppError> {
+    let filter = query.to_filter()?;
+
+    // CP times are Portugal-local; the client's clock reports "now" in
+    // Europe/Lisbon so boards are identical regardless of the host timezone
+    // (containers run in UTC and would otherwise show departed trains).
+    let now = state.api.clock().now();
     let date = now.format("%Y-%m-%d").to_string();
     let today = now.date_naive();
 
@@ -51,23 +182,14 @@ pub async fn station_timetables(
 
     let mut boards = state
         .api
-        .get_station_timetable(&station_id, &date, start_time.as_deref())
+        .get_station_timetable_filtered(station_id, &date, start_time.as_deref(), &filter)
         .await?;
 
     for board in &mut boards.response {
-        if board.station_name.is_empty()
-            && let Ok(names) = state.station_names.read()
-            && let Some(name) = names.get(&board.station_id)
-        {
-            board.station_name = name.clone();
-        }
-
         retain_upcoming(&mut board.trains, today, now);
     }
 
-    Ok(Json(AppResponse {
-        data: boards.response,
-    }))
+    Ok(boards.response)
 }
 
 /// Drop trains that have already passed the station (by effective time) and
@@ -131,6 +253,7 @@ fn compute_has_passed(
 mod tests {
     use super::*;
     use chrono::TimeZone;
+    use comboios_core::domain::occupancy::Occupancy;
 
     fn now_at(h: u32, m: u32) -> DateTime<Tz> {
         Lisbon.with_ymd_and_hms(2024, 6, 7, h, m, 0).unwrap()
@@ -293,12 +416,47 @@ mod tests {
             estimated_departure: None,
             estimated_arrival: None,
             observations: None,
+            occupancy: Occupancy::Unknown,
             operator: "CP".to_string(),
             has_passed: true,
             is_departure: true,
         }
     }
 
+    #[test]
+    fn empty_query_builds_match_all_filter() {
+        let filter = TimetableQuery::default().to_filter().unwrap();
+        assert!(filter.matches(&make_train(Some("13:30"), None)));
+    }
+
+    #[test]
+    fn query_parameters_are_applied_to_filter() {
+        let query = TimetableQuery {
+            service_type: Some("ic".to_string()),
+            status: Some(BoardStatus::Delayed),
+            from: Some("13:00".to_string()),
+            to: Some("14:00".to_string()),
+            ..TimetableQuery::default()
+        };
+        let filter = query.to_filter().unwrap();
+
+        assert!(filter.matches(&make_train(Some("13:30"), Some(5))));
+        assert!(!filter.matches(&make_train(Some("13:30"), None)));
+        assert!(!filter.matches(&make_train(Some("15:00"), Some(5))));
+    }
+
+    #[test]
+    fn invalid_time_parameter_is_rejected() {
+        let query = TimetableQuery {
+            from: Some("25:99".to_string()),
+            ..TimetableQuery::default()
+        };
+        assert!(matches!(
+            query.to_filter(),
+            Err(AppError::CoreError(CoreError::InvalidInput(_)))
+        ));
+    }
+
     #[test]
     fn retain_upcoming_drops_passed_and_clears_flag() {
         let mut trains = vec![
@@ -1,23 +1,43 @@
+use std::convert::Infallible;
 use std::sync::Arc;
 
 use axum::{
     Json,
     extract::{Path, Query, State},
+    response::{
+        Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
+use chrono::NaiveDate;
+use futures::stream::{self, Stream, StreamExt};
 use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::{
     domain::{AppResponse, AppState, TrainId},
     error::AppError,
+    hub::{TrainSubscription, TrainUpdate},
+    routes::commutes::{ics, subscription},
+    routes::live::geojson,
 };
-use chrono::Local;
 use comboios_core::domain::journey::TrainJourney;
+use comboios_core::domain::position::TrainPosition;
+use comboios_core::domain::segment::JourneySegment;
+use comboios_core::error::CoreError;
 
 #[derive(Debug, Deserialize)]
 pub struct JourneyQuery {
     pub date: Option<String>,
 }
 
+/// Parse a `YYYY-MM-DD` service date from a request.
+pub(crate) fn parse_date(date: &str) -> Result<NaiveDate, CoreError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| CoreError::InvalidInput(format!("invalid date {date}, expected YYYY-MM-DD")))
+}
+
 /// # Errors
 ///
 /// Returns [`AppError`] if the request handling fails.
@@ -46,10 +66,152 @@ pub async fn get_train_journey(
 ) -> Result<Json<AppResponse<TrainJourney>>, AppError> {
     tracing::info!("Fetching train journey for {train_id}");
 
-    let date = query
-        .date
-        .unwrap_or_else(|| Local::now().format("%Y-%m-%d").to_string());
+    let date = query.date.unwrap_or_else(|| state.api.today());
     let train = state.api.get_train_journey(&train_id, &date).await?;
 
     Ok(Json(AppResponse { data: train }))
 }
+
+/// The journey as an iCalendar event in `Europe/Lisbon` time, with the
+/// current delay and platforms in its description. Subscribing to the URL
+/// keeps the event up to date.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if `date` is malformed or the CP or IP API call
+/// fails.
+#[tracing::instrument(skip(state))]
+pub async fn get_train_journey_ics(
+    State(state): State<Arc<AppState>>,
+    Path(train_id): Path<String>,
+    Query(query): Query<JourneyQuery>,
+) -> Result<Response, AppError> {
+    tracing::info!("Exporting train journey for {train_id} as iCalendar");
+
+    let date = query.date.unwrap_or_else(|| state.api.today());
+    let service_date = parse_date(&date)?;
+    let train = state.api.get_train_journey(&train_id, &date).await?;
+
+    let calendar = subscription(&state)
+        .name(format!("Train {train_id}"))
+        .journey(&train, service_date);
+    Ok(ics(&calendar))
+}
+
+/// The journey as a `GeoJSON` `FeatureCollection`: the route as a line
+/// through the stops and a point per stop with its status, delay and
+/// platform.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if the CP or IP API call fails.
+#[tracing::instrument(skip(state))]
+pub async fn get_train_journey_geojson(
+    State(state): State<Arc<AppState>>,
+    Path(train_id): Path<String>,
+    Query(query): Query<JourneyQuery>,
+) -> Result<Response, AppError> {
+    tracing::info!("Exporting train journey for {train_id} as GeoJSON");
+
+    let date = query.date.unwrap_or_else(|| state.api.today());
+    let train = state.api.get_train_journey(&train_id, &date).await?;
+    let stations = state.api.station_index().await;
+
+    Ok(geojson(&train.to_geojson(&stations)))
+}
+
+/// Running and dwell times per leg of a train's journey, with the delay
+/// gained or recovered on each.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if the CP or IP API call fails.
+#[tracing::instrument]
+pub async fn get_train_segments(
+    State(state): State<Arc<AppState>>,
+    Path(train_id): Path<String>,
+    Query(query): Query<JourneyQuery>,
+) -> Result<Json<AppResponse<Vec<JourneySegment>>>, AppError> {
+    tracing::info!("Fetching journey segments for {train_id}");
+
+    let date = query.date.unwrap_or_else(|| state.api.today());
+    let train = state.api.get_train_journey(&train_id, &date).await?;
+
+    Ok(Json(AppResponse {
+        data: train.segments(),
+    }))
+}
+
+/// Current (live or estimated) position of a train; `data` is `null` when
+/// it cannot be placed on the map.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if the CP or IP API call fails.
+#[tracing::instrument]
+pub async fn get_train_position(
+    State(state): State<Arc<AppState>>,
+    Path(train_id): Path<String>,
+    Query(query): Query<JourneyQuery>,
+) -> Result<Json<AppResponse<Option<TrainPosition>>>, AppError> {
+    tracing::info!("Estimating position of train {train_id}");
+
+    let date = query.date.unwrap_or_else(|| state.api.today());
+    let position = state.api.train_position(&train_id, &date).await?;
+
+    Ok(Json(AppResponse { data: position }))
+}
+
+/// Server-Sent Events feed of a train: a `journey` event with the full
+/// [`TrainJourney`] after every poll and a `change` event per
+/// [`JourneyEvent`](comboios_core::watch::JourneyEvent). Failed polls send an
+/// `error` event. Clients following the same train share one upstream
+/// poller, and the stream ends once the journey is completed or cancelled.
+///
+/// # Errors
+///
+/// Returns [`AppError`] if `date` is malformed, or
+/// [`AppError::TooManyRequests`] if the train is not followed already and
+/// `LIVE_MAX_FEEDS` feeds are running.
+#[tracing::instrument(skip(state))]
+pub async fn get_train_journey_stream(
+    State(state): State<Arc<AppState>>,
+    Path(train_id): Path<String>,
+    Query(query): Query<JourneyQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    tracing::info!("Streaming train journey for {train_id}");
+
+    let date = query.date.unwrap_or_else(|| state.api.today());
+    parse_date(&date)?;
+    let TrainSubscription { latest, updates } = state.hub.subscribe_train(&train_id, &date)?;
+
+    let first = latest.map(TrainUpdate::Journey);
+    let events = stream::unfold((first, updates), |(first, mut updates)| async move {
+        if let Some(update) = first {
+            return Some((update, (None, updates)));
+        }
+        loop {
+            match updates.recv().await {
+                Ok(update) => return Some((update, (None, updates))),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Journey stream lagged, {skipped} updates skipped");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .map(|update| Ok(sse_event(&update)));
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn sse_event(update: &TrainUpdate) -> Event {
+    let (name, data) = match update {
+        TrainUpdate::Journey(journey) => ("journey", serde_json::to_string(journey.as_ref())),
+        TrainUpdate::Event(event) => ("change", serde_json::to_string(event)),
+        TrainUpdate::Error(error) => ("error", serde_json::to_string(&json!({ "error": error }))),
+    };
+    Event::default()
+        .event(name)
+        .data(data.unwrap_or_else(|e| format!("{{\"error\":\"{e}\"}}")))
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use comboios_core::planner::{Itinerary, MAX_TRANSFERS, PlannerConfig};
+use serde::Deserialize;
+
+use crate::{
+    domain::{AppResponse, AppState},
+    error::AppError,
+};
+
+/// Query parameters of `/trips`, e.g.
+/// `?from=Braga&to=Faro&after=07:00&max_transfers=2&min_transfer=8`.
+#[derive(Debug, Deserialize)]
+pub struct TripQuery {
+    /// Origin station id or name.
+    pub from: String,
+    /// Destination station id or name.
+    pub to: String,
+    /// Service date (`YYYY-MM-DD`); defaults to today.
+    pub date: Option<String>,
+    /// Earliest departure (`HH:MM`); defaults to now when planning for today.
+    pub after: Option<String>,
+    /// Most changes of train; capped at [`MAX_TRANSFERS`].
+    pub max_transfers: Option<usize>,
+    /// Minimum time to change trains, in minutes.
+    pub min_transfer: Option<u64>,
+}
+
+/// Itineraries from `from` to `to`, possibly changing trains: the fastest
+/// for each number of changes that arrives earlier than with fewer changes.
+///
+/// At most `TRIPS_MAX_CONCURRENT` plans run at once; plans share the
+/// boards and journeys fetched in the last couple of minutes.
+///
+/// # Errors
+///
+/// Returns [`AppError::TooManyRequests`] while too many plans are running,
+/// or [`AppError`] if the query is invalid or the origin's board cannot be
+/// fetched.
+#[tracing::instrument(skip(state))]
+pub async fn trips(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TripQuery>,
+) -> Result<Json<AppResponse<Vec<Itinerary>>>, AppError> {
+    let _permit = state.trip_permits.try_acquire().map_err(|_| {
+        AppError::TooManyRequests("Too many trip planning requests; try again shortly".to_string())
+    })?;
+    tracing::info!("Planning trips from {} to {}", query.from, query.to);
+
+    let today = state.api.today();
+    let date = query.date.unwrap_or_else(|| today.clone());
+    let after = query
+        .after
+        .or_else(|| (date == today).then(|| state.api.clock().now().format("%H:%M").to_string()));
+
+    let mut config = PlannerConfig::new();
+    if let Some(max) = query.max_transfers {
+        config = config.max_transfers(max.min(MAX_TRANSFERS));
+    }
+    if let Some(minutes) = query.min_transfer {
+        config = config.min_transfer(Duration::from_mins(minutes));
+    }
+
+    let itineraries = state
+        .planner
+        .with_config(config)
+        .plan(&query.from, &query.to, &date, after.as_deref())
+        .await?;
+
+    Ok(Json(AppResponse { data: itineraries }))
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::Caller,
+    domain::{AppResponse, AppState},
+    error::AppError,
+    webhooks::{Delivery, NewWebhook, Webhook, WebhookInfo},
+};
+
+/// Register a webhook for the caller's API key. The response is the only
+/// place its secret is shown.
+///
+/// # Errors
+///
+/// Returns [`AppError::TooManyRequests`] if the key has registered
+/// `WEBHOOK_MAX_PER_KEY` webhooks already, or [`AppError`] if the request is
+/// invalid or the webhook cannot be stored.
+#[tracing::instrument(skip_all)]
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+    Json(request): Json<NewWebhook>,
+) -> Result<(StatusCode, Json<AppResponse<Webhook>>), AppError> {
+    let clock = state.api.clock();
+    let hook = request.into_webhook(
+        &caller,
+        clock.today(),
+        clock.now().to_rfc3339(),
+        state.settings.webhook_allow_private_networks,
+    )?;
+    if !state.webhooks.register(hook.clone())? {
+        return Err(AppError::TooManyRequests(format!(
+            "webhook limit of {} reached",
+            state.settings.webhook_max_per_key
+        )));
+    }
+
+    Ok((StatusCode::CREATED, Json(AppResponse { data: hook })))
+}
+
+/// The webhooks registered with the caller's API key.
+#[tracing::instrument(skip_all)]
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+) -> Json<AppResponse<Vec<WebhookInfo>>> {
+    let hooks = state.webhooks.list(&caller);
+    Json(AppResponse {
+        data: hooks.iter().map(WebhookInfo::from).collect(),
+    })
+}
+
+/// # Errors
+///
+/// Returns [`AppError::NotFound`] for a webhook unknown to the caller, or
+/// [`AppError`] if the store cannot be saved.
+#[tracing::instrument(skip(state, caller))]
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    if state.webhooks.remove(&caller, id)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!("webhook {id}")))
+    }
+}
+
+/// Recent deliveries to a webhook, oldest first.
+///
+/// # Errors
+///
+/// Returns [`AppError::NotFound`] for a webhook unknown to the caller.
+#[tracing::instrument(skip(state, caller))]
+pub async fn webhook_deliveries(
+    State(state): State<Arc<AppState>>,
+    caller: Caller,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AppResponse<Vec<Delivery>>>, AppError> {
+    let deliveries = state
+        .webhooks
+        .deliveries(&caller, id)
+        .ok_or_else(|| AppError::NotFound(format!("webhook {id}")))?;
+
+    Ok(Json(AppResponse { data: deliveries }))
+}
@@ -0,0 +1,492 @@
+//! `/ws`: board and train updates multiplexed over one WebSocket.
+//!
+//! Clients send JSON messages tagged by `type`:
+//!
+//! ```json
+//! {"type": "subscribe", "id": "oriente", "topic": "board", "station": "94-31039"}
+//! {"type": "subscribe", "id": "ic-520", "topic": "train", "train": "520", "date": "2026-03-14"}
+//! {"type": "unsubscribe", "id": "oriente"}
+//! {"type": "ping"}
+//! ```
+//!
+//! Each subscription is named by the client's `id`, which every message
+//! about it carries. After `subscribed` the server sends the current state
+//! (`board` with the trains on it, or `journey`), then a `change` per
+//! [`BoardEvent`](comboios_core::watch::BoardEvent) or
+//! [`JourneyEvent`](comboios_core::watch::JourneyEvent), a fresh `journey` after every train
+//! poll, and `ended` once a journey is over. A subscription that falls
+//! behind its feed is sent the current state again and carries on from
+//! there. Failures come as `error`, with the `id` when they concern one
+//! subscription, and a `heartbeat` is sent every [`HEARTBEAT_INTERVAL`].
+//! Polling is shared with every other client through
+//! [`LiveHub`](crate::hub::LiveHub).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use comboios_core::domain::journey::TrainJourney;
+use comboios_core::domain::station_timetable::StationTimetable;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+use crate::domain::AppState;
+use crate::hub::{
+    BoardSubscription, BoardUpdate, Change, HubError, TrainSubscription, TrainUpdate,
+};
+use crate::routes::trains::parse_date;
+
+/// How often an idle connection is sent a `heartbeat`.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Messages queued for one connection before its subscriptions wait.
+const OUTGOING_CAPACITY: usize = 64;
+
+/// A message from the client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        id: String,
+        #[serde(flatten)]
+        topic: Topic,
+    },
+    Unsubscribe {
+        id: String,
+    },
+    Ping,
+}
+
+/// What a subscription follows.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "topic", rename_all = "snake_case")]
+pub enum Topic {
+    /// The departure board of a station, by id or name.
+    Board { station: String },
+    /// A train on `date` (`YYYY-MM-DD`, today when absent).
+    Train { train: String, date: Option<String> },
+}
+
+/// A message to the client.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        id: String,
+    },
+    Unsubscribed {
+        id: String,
+    },
+    /// The trains on a board when the subscription starts.
+    Board {
+        id: String,
+        trains: Vec<StationTimetable>,
+    },
+    /// A train's journey, at subscription and after every poll.
+    Journey {
+        id: String,
+        journey: Box<TrainJourney>,
+    },
+    Change {
+        id: String,
+        event: Change,
+    },
+    /// The journey is completed or cancelled; the subscription is closed.
+    Ended {
+        id: String,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        message: String,
+    },
+    Heartbeat {
+        /// Portugal-local server time, RFC 3339.
+        time: String,
+        subscriptions: usize,
+    },
+    Pong,
+}
+
+/// Upgrade to a WebSocket speaking the protocol described in the
+/// [module docs](self).
+pub async fn ws(State(state): State<Arc<AppState>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| serve(socket, state))
+}
+
+async fn serve(socket: WebSocket, state: Arc<AppState>) {
+    tracing::info!("WebSocket client connected");
+    let (mut sink, mut incoming) = socket.split();
+    let (outgoing, mut queue) = mpsc::channel(OUTGOING_CAPACITY);
+    let mut connection = Connection {
+        state,
+        outgoing,
+        subscriptions: HashMap::new(),
+    };
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    loop {
+        let reply = tokio::select! {
+            message = incoming.next() => match message {
+                Some(Ok(Message::Text(text))) => connection.handle(&text).await,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                // Pings are answered by axum; binary frames are not part of
+                // the protocol.
+                Some(Ok(_)) => continue,
+            },
+            Some(message) = queue.recv() => message,
+            _ = heartbeat.tick() => connection.heartbeat(),
+        };
+
+        let text = match serde_json::to_string(&reply) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::error!("Failed to serialize WebSocket message: {e}");
+                continue;
+            }
+        };
+        if sink.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+    tracing::info!("WebSocket client disconnected");
+}
+
+/// The subscriptions of one client. Each runs as a task forwarding its hub
+/// feed to the connection's queue; they are stopped with the connection.
+struct Connection {
+    state: Arc<AppState>,
+    outgoing: mpsc::Sender<ServerMessage>,
+    subscriptions: HashMap<String, JoinHandle<()>>,
+}
+
+impl Connection {
+    async fn handle(&mut self, text: &str) -> ServerMessage {
+        match serde_json::from_str(text) {
+            Ok(ClientMessage::Subscribe { id, topic }) => self.subscribe(id, topic).await,
+            Ok(ClientMessage::Unsubscribe { id }) => self.unsubscribe(id),
+            Ok(ClientMessage::Ping) => ServerMessage::Pong,
+            Err(e) => ServerMessage::Error {
+                id: None,
+                message: format!("invalid message: {e}"),
+            },
+        }
+    }
+
+    async fn subscribe(&mut self, id: String, topic: Topic) -> ServerMessage {
+        // Subscriptions whose journey has ended no longer count.
+        self.subscriptions.retain(|_, task| !task.is_finished());
+
+        let error = |message: String| ServerMessage::Error {
+            id: Some(id.clone()),
+            message,
+        };
+        if self.subscriptions.contains_key(&id) {
+            return error(format!("subscription '{id}' already exists"));
+        }
+        let limit = self.state.settings.ws_max_subscriptions;
+        if self.subscriptions.len() >= limit {
+            return error(format!("subscription limit of {limit} reached"));
+        }
+
+        if let Topic::Train {
+            date: Some(date), ..
+        } = &topic
+            && let Err(e) = parse_date(date)
+        {
+            return error(e.to_string());
+        }
+
+        let outgoing = self.outgoing.clone();
+        let task = match topic {
+            Topic::Board { station } => {
+                tracing::info!("WebSocket subscription {id} to board {station}");
+                let (state, board_id) = (self.state.clone(), id.clone());
+                let board = move || {
+                    let (state, id, station) = (state.clone(), board_id.clone(), station.clone());
+                    async move {
+                        let BoardSubscription { trains, updates } =
+                            state.hub.subscribe_board(&station).await?;
+                        Ok::<_, HubError>((Some(ServerMessage::Board { id, trains }), updates))
+                    }
+                };
+                let (first, updates) = match board().await {
+                    Ok(subscription) => subscription,
+                    Err(e) => return error(e.to_string()),
+                };
+                tokio::spawn(forward(
+                    id.clone(),
+                    first,
+                    updates,
+                    outgoing,
+                    board_message,
+                    board,
+                ))
+            }
+            Topic::Train { train, date } => {
+                tracing::info!("WebSocket subscription {id} to train {train}");
+                let date = date.unwrap_or_else(|| self.state.api.today());
+                let (state, journey_id) = (self.state.clone(), id.clone());
+                let journey = move || {
+                    let subscription = state.hub.subscribe_train(&train, &date).map(
+                        |TrainSubscription { latest, updates }| {
+                            let snapshot = latest.map(|journey| ServerMessage::Journey {
+                                id: journey_id.clone(),
+                                journey: Box::new(journey.as_ref().clone()),
+                            });
+                            (snapshot, updates)
+                        },
+                    );
+                    std::future::ready(subscription)
+                };
+                let (first, updates) = match journey().await {
+                    Ok(subscription) => subscription,
+                    Err(e) => return error(e.to_string()),
+                };
+                tokio::spawn(forward(
+                    id.clone(),
+                    first,
+                    updates,
+                    outgoing,
+                    train_message,
+                    journey,
+                ))
+            }
+        };
+        self.subscriptions.insert(id.clone(), task);
+
+        ServerMessage::Subscribed { id }
+    }
+
+    fn unsubscribe(&mut self, id: String) -> ServerMessage {
+        match self.subscriptions.remove(&id) {
+            Some(task) => {
+                task.abort();
+                ServerMessage::Unsubscribed { id }
+            }
+            None => ServerMessage::Error {
+                message: format!("no subscription '{id}'"),
+                id: Some(id),
+            },
+        }
+    }
+
+    fn heartbeat(&mut self) -> ServerMessage {
+        self.subscriptions.retain(|_, task| !task.is_finished());
+        ServerMessage::Heartbeat {
+            time: self.state.api.clock().now().to_rfc3339(),
+            subscriptions: self.subscriptions.len(),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in self.subscriptions.values() {
+            task.abort();
+        }
+    }
+}
+
+/// The current state of a feed, if any, and the receiver for what comes
+/// next.
+type Snapshot<T> = (Option<ServerMessage>, broadcast::Receiver<T>);
+
+/// Send `first`, then every update on `updates` as a message, until the
+/// feed closes or the connection goes away. When the subscription falls
+/// behind, it starts over from a fresh snapshot taken with `resubscribe`.
+async fn forward<T: Clone, F>(
+    id: String,
+    mut first: Option<ServerMessage>,
+    mut updates: broadcast::Receiver<T>,
+    outgoing: mpsc::Sender<ServerMessage>,
+    to_message: fn(String, T) -> ServerMessage,
+    resubscribe: impl Fn() -> F,
+) where
+    F: Future<Output = Result<Snapshot<T>, HubError>>,
+{
+    loop {
+        if let Some(snapshot) = first.take()
+            && outgoing.send(snapshot).await.is_err()
+        {
+            return;
+        }
+        let message = match updates.recv().await {
+            Ok(update) => to_message(id.clone(), update),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!("WebSocket subscription {id} lagged, {skipped} updates skipped");
+                match resubscribe().await {
+                    Ok(snapshot) => (first, updates) = snapshot,
+                    Err(e) => {
+                        let _ = outgoing
+                            .send(ServerMessage::Error {
+                                id: Some(id),
+                                message: e.to_string(),
+                            })
+                            .await;
+                        return;
+                    }
+                }
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                let _ = outgoing.send(ServerMessage::Ended { id }).await;
+                return;
+            }
+        };
+        if outgoing.send(message).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn board_message(id: String, update: BoardUpdate) -> ServerMessage {
+    match update {
+        BoardUpdate::Event(event) => ServerMessage::Change {
+            id,
+            event: Change::Board(event),
+        },
+        BoardUpdate::Error(message) => ServerMessage::Error {
+            id: Some(id),
+            message,
+        },
+    }
+}
+
+fn train_message(id: String, update: TrainUpdate) -> ServerMessage {
+    match update {
+        TrainUpdate::Journey(journey) => ServerMessage::Journey {
+            id,
+            journey: Box::new(journey.as_ref().clone()),
+        },
+        TrainUpdate::Event(event) => ServerMessage::Change {
+            id,
+            event: Change::Train(event),
+        },
+        TrainUpdate::Error(message) => ServerMessage::Error {
+            id: Some(id),
+            message,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use comboios_core::watch::BoardEvent;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn subscribe_messages_name_their_topic() {
+        let board: ClientMessage = serde_json::from_value(json!({
+            "type": "subscribe", "id": "a", "topic": "board", "station": "94-31039"
+        }))
+        .unwrap();
+        assert!(matches!(
+            board,
+            ClientMessage::Subscribe { ref id, topic: Topic::Board { ref station } }
+                if id == "a" && station == "94-31039"
+        ));
+
+        let train: ClientMessage = serde_json::from_value(json!({
+            "type": "subscribe", "id": "b", "topic": "train", "train": "520"
+        }))
+        .unwrap();
+        assert!(matches!(
+            train,
+            ClientMessage::Subscribe { topic: Topic::Train { ref train, date: None }, .. }
+                if train == "520"
+        ));
+
+        let unsubscribe: ClientMessage =
+            serde_json::from_value(json!({ "type": "unsubscribe", "id": "a" })).unwrap();
+        assert!(matches!(unsubscribe, ClientMessage::Unsubscribe { ref id } if id == "a"));
+    }
+
+    #[test]
+    fn malformed_messages_are_rejected() {
+        for message in [
+            json!({ "type": "subscribe", "id": "a", "topic": "weather" }),
+            json!({ "type": "subscribe", "topic": "board", "station": "x" }),
+            json!({ "type": "hello" }),
+        ] {
+            assert!(serde_json::from_value::<ClientMessage>(message).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriptions_resync_from_a_fresh_snapshot() {
+        let (tx, updates) = broadcast::channel(1);
+        // Overruns the receiver: the first of these is lost.
+        for train_number in [520, 4410] {
+            tx.send(BoardUpdate::Event(BoardEvent::Cancelled { train_number }))
+                .unwrap();
+        }
+        let (fresh_tx, fresh_updates) = broadcast::channel(4);
+        fresh_tx
+            .send(BoardUpdate::Event(BoardEvent::Cancelled {
+                train_number: 4410,
+            }))
+            .unwrap();
+        drop(fresh_tx);
+        let snapshot = ServerMessage::Board {
+            id: "a".to_string(),
+            trains: Vec::new(),
+        };
+        let fresh = std::sync::Mutex::new(Some((Some(snapshot), fresh_updates)));
+        let (outgoing, mut queue) = mpsc::channel(8);
+
+        forward(
+            "a".to_string(),
+            None,
+            updates,
+            outgoing,
+            board_message,
+            async || Ok(fresh.lock().unwrap().take().expect("resynced once")),
+        )
+        .await;
+        drop(tx);
+
+        let mut sent = Vec::new();
+        while let Ok(message) = queue.try_recv() {
+            sent.push(serde_json::to_value(message).unwrap()["type"].clone());
+        }
+        assert_eq!(sent, [json!("board"), json!("change"), json!("ended")]);
+    }
+
+    #[test]
+    fn server_messages_carry_the_subscription_id() {
+        let change = ServerMessage::Change {
+            id: "a".to_string(),
+            event: Change::Board(BoardEvent::Cancelled { train_number: 520 }),
+        };
+        assert_eq!(
+            serde_json::to_value(change).unwrap(),
+            json!({
+                "type": "change",
+                "id": "a",
+                "event": { "type": "CANCELLED", "train_number": 520 }
+            })
+        );
+
+        let error = ServerMessage::Error {
+            id: None,
+            message: "invalid message".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            json!({ "type": "error", "message": "invalid message" })
+        );
+    }
+}
@@ -1,12 +1,18 @@
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 use anyhow::Result;
-use axum::{BoxError, Json, Router, error_handling::HandleErrorLayer, routing::get};
+use axum::{
+    BoxError, Json, Router,
+    error_handling::HandleErrorLayer,
+    routing::{delete, get},
+};
 use comboios_core::Comboios;
+use comboios_core::monitor::{MonitorConfig, NetworkMonitor};
+use comboios_core::planner::{PlannerConfig, TripPlanner};
 use reqwest::StatusCode;
 use serde::Serialize;
 use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
 use tokio::time::interval;
 use tower::ServiceBuilder;
 use tower_http::{
@@ -17,16 +23,28 @@ use tower_http::{
 };
 
 use crate::{
+    commutes::{CommuteStore, JourneyCache},
     configuration::Settings,
     domain::AppState,
+    hub::LiveHub,
     routes::{
+        commutes::{commute_calendar, create_commute, delete_commute, list_commutes},
         diagnostics::diagnostics,
+        gtfs_rt::{trip_updates, trip_updates_json, vehicle_positions, vehicle_positions_json},
         health_check::health_check,
+        live::{live_trains, live_trains_geojson},
         refresh::refresh_credentials,
-        station_timetables::station_timetables,
+        station_timetables::{station_timetable_geojson, station_timetables},
         stations::stations,
-        trains::{get_train_journey, trains},
+        trains::{
+            get_train_journey, get_train_journey_geojson, get_train_journey_ics,
+            get_train_journey_stream, get_train_position, get_train_segments, trains,
+        },
+        trips::trips,
+        webhooks::{create_webhook, delete_webhook, list_webhooks, webhook_deliveries},
+        ws::ws,
     },
+    webhooks::{Deliverer, WebhookStore, Webhooks},
 };
 
 #[derive(Serialize)]
@@ -45,25 +63,48 @@ pub async fn run(listener: TcpListener, settings: Settings) -> Result<()> {
 
     tracing::info!("CP credentials loaded from cp.pt on startup");
 
-    let station_names = match api.search_stations("").await {
-        Ok(response) => {
-            let mut map = HashMap::new();
-            for station in response.response {
-                map.insert(station.code, station.designation);
-            }
-            tracing::info!("Loaded {} stations into cache", map.len());
-            RwLock::new(map)
-        }
-        Err(e) => {
-            tracing::warn!("Failed to fetch station list at startup: {e}");
-            RwLock::new(HashMap::new())
-        }
-    };
+    let mut monitor_config = MonitorConfig::new().concurrency(settings.live_sweep_concurrency);
+    if let Some(interval) = settings.live_sweep_interval {
+        monitor_config = monitor_config.interval(interval);
+    }
+    let monitor = NetworkMonitor::new(api.clone(), monitor_config);
+    if settings.live_sweep_interval.is_some() {
+        monitor.spawn();
+    } else {
+        tracing::info!("Live network sweep disabled; set LIVE_SWEEP_SECS to enable it");
+    }
+
+    let hub =
+        LiveHub::new(api.clone(), settings.stream_poll_interval).max_feeds(settings.live_max_feeds);
+    let webhooks = Webhooks::new(
+        WebhookStore::open(&settings.webhooks_path)?,
+        hub.clone(),
+        Deliverer::new(settings.webhook_max_attempts)
+            .allow_private_networks(settings.webhook_allow_private_networks),
+    )
+    .max_per_owner(settings.webhook_max_per_key);
+    webhooks.start();
+    if settings.api_keys.is_empty() {
+        tracing::info!("No API keys set; /webhooks answers 401 until API_KEYS is set");
+    }
+
+    #[cfg(feature = "mqtt")]
+    crate::mqtt::spawn(api.clone(), &settings)?;
+    #[cfg(not(feature = "mqtt"))]
+    if settings.mqtt_url.is_some() {
+        tracing::warn!("MQTT_URL is set but the server was built without the `mqtt` feature");
+    }
 
     let app_state = Arc::new(AppState {
         api: api.clone(),
+        monitor,
+        planner: TripPlanner::new(api.clone(), PlannerConfig::new()),
+        trip_permits: Semaphore::new(settings.trips_max_concurrent),
+        hub,
+        webhooks,
+        commutes: CommuteStore::open(&settings.commutes_path)?,
+        commute_journeys: JourneyCache::default(),
         settings: settings.clone(),
-        station_names,
     });
 
     let refresh_interval_duration = settings.credential_refresh_interval;
@@ -80,6 +121,9 @@ pub async fn run(listener: TcpListener, settings: Settings) -> Result<()> {
                 Ok(()) => tracing::info!("Background credential refresh succeeded"),
                 Err(e) => tracing::warn!("Background credential refresh failed: {e}"),
             }
+            if let Err(e) = api_for_bg.refresh_station_index().await {
+                tracing::warn!("Background station index refresh failed: {e}");
+            }
             refresh_ticker.tick().await;
         }
     });
@@ -93,8 +137,42 @@ pub async fn run(listener: TcpListener, settings: Settings) -> Result<()> {
         .route("/diagnostics", get(diagnostics))
         .route("/stations", get(stations))
         .route("/stations/timetable/{station_id}", get(station_timetables))
+        .route(
+            "/stations/{station_id}/timetable.geojson",
+            get(station_timetable_geojson),
+        )
+        .route("/trips", get(trips))
+        .route("/trains/live", get(live_trains))
+        .route("/trains/live.geojson", get(live_trains_geojson))
         .route("/trains/{train_id}", get(trains))
         .route("/trains/{train_id}/journey", get(get_train_journey))
+        .route("/trains/{train_id}/journey.ics", get(get_train_journey_ics))
+        .route(
+            "/trains/{train_id}/journey.geojson",
+            get(get_train_journey_geojson),
+        )
+        .route(
+            "/trains/{train_id}/journey/stream",
+            get(get_train_journey_stream),
+        )
+        .route("/trains/{train_id}/segments", get(get_train_segments))
+        .route("/trains/{train_id}/position", get(get_train_position))
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/{id}", delete(delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(webhook_deliveries))
+        .route("/commutes", get(list_commutes).post(create_commute))
+        .route(
+            "/commutes/{id}",
+            get(commute_calendar).delete(delete_commute),
+        )
+        .route("/ws", get(ws))
+        .route("/gtfs-rt/trip-updates", get(trip_updates))
+        .route("/gtfs-rt/trip-updates.json", get(trip_updates_json))
+        .route("/gtfs-rt/vehicle-positions", get(vehicle_positions))
+        .route(
+            "/gtfs-rt/vehicle-positions.json",
+            get(vehicle_positions_json),
+        )
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -0,0 +1,176 @@
+//! Records persisted as a JSON array in one file, rewritten on every change.
+//!
+//! Webhooks and commutes are kept this way: few enough to hold in memory and
+//! save whole, and small enough that a database would be overkill.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+/// A record of a [`JsonFileStore`], known by its id.
+pub trait Record: Clone + Serialize + DeserializeOwned {
+    fn id(&self) -> Uuid;
+}
+
+/// Records of type `T` persisted as a JSON array in one file, rewritten on
+/// every change.
+#[derive(Debug)]
+pub struct JsonFileStore<T> {
+    path: PathBuf,
+    records: Mutex<Vec<T>>,
+}
+
+impl<T: Record> JsonFileStore<T> {
+    /// Load the records in `path`; a missing file is an empty store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            path,
+            records: Mutex::new(records),
+        })
+    }
+
+    #[must_use]
+    pub fn list(&self) -> Vec<T> {
+        self.records().clone()
+    }
+
+    #[must_use]
+    pub fn get(&self, id: Uuid) -> Option<T> {
+        self.records()
+            .iter()
+            .find(|record| record.id() == id)
+            .cloned()
+    }
+
+    /// Add `record` and save.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written; the store is left
+    /// unchanged.
+    pub fn insert(&self, record: T) -> io::Result<()> {
+        self.insert_if(record, |_| true).map(drop)
+    }
+
+    /// Add `record` and save if `fits` accepts the records stored now,
+    /// returning whether it was added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written; the store is left
+    /// unchanged.
+    pub fn insert_if(&self, record: T, fits: impl FnOnce(&[T]) -> bool) -> io::Result<bool> {
+        let mut records = self.records();
+        if !fits(&records) {
+            return Ok(false);
+        }
+        let mut updated = records.clone();
+        updated.push(record);
+        self.save(&updated)?;
+        *records = updated;
+        Ok(true)
+    }
+
+    /// Remove the record `id` and save, returning whether it existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written; the store is left
+    /// unchanged.
+    pub fn remove(&self, id: Uuid) -> io::Result<bool> {
+        let mut records = self.records();
+        if !records.iter().any(|record| record.id() == id) {
+            return Ok(false);
+        }
+        let updated: Vec<_> = records.iter().filter(|r| r.id() != id).cloned().collect();
+        self.save(&updated)?;
+        *records = updated;
+        Ok(true)
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, Vec<T>> {
+        self.records.lock().expect("store lock poisoned")
+    }
+
+    /// Write through a temporary file so a crash never leaves half a store.
+    fn save(&self, records: &[T]) -> io::Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(records)?)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Note {
+        id: Uuid,
+        text: String,
+    }
+
+    impl Record for Note {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+    }
+
+    fn note(text: &str) -> Note {
+        Note {
+            id: Uuid::new_v4(),
+            text: text.to_string(),
+        }
+    }
+
+    fn path() -> PathBuf {
+        std::env::temp_dir().join(format!("store-{}.json", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn store_survives_a_reopen() {
+        let path = path();
+        let store = JsonFileStore::open(&path).unwrap();
+        assert!(store.list().is_empty());
+
+        let (first, second) = (note("a"), note("b"));
+        store.insert(first.clone()).unwrap();
+        store.insert(second.clone()).unwrap();
+        assert_eq!(store.get(first.id), Some(first.clone()));
+        assert!(store.remove(first.id).unwrap());
+        assert!(!store.remove(first.id).unwrap());
+
+        let reopened = JsonFileStore::<Note>::open(&path).unwrap();
+        assert_eq!(reopened.list(), [second]);
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn store_inserts_only_what_fits() {
+        let path = path();
+        let store = JsonFileStore::open(&path).unwrap();
+        let fits = |notes: &[Note]| notes.is_empty();
+
+        assert!(store.insert_if(note("a"), fits).unwrap());
+        assert!(!store.insert_if(note("b"), fits).unwrap());
+        assert_eq!(store.list().len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,1359 @@
+//! Outbound webhooks for watched trains and stations.
+//!
+//! A client registers a URL for a train on a date, or for a station's
+//! departure board narrowed by the same filters as
+//! `/stations/timetable/{id}`. Each webhook follows its feed on the
+//! [`LiveHub`] and POSTs a JSON [`Payload`] for every delay, platform,
+//! cancellation or departure event, signed with HMAC-SHA256 over the body in
+//! the [`SIGNATURE_HEADER`] (`sha256=<hex>`). Events are queued per webhook
+//! and delivered in order by a task of their own, so a slow receiver never
+//! holds up the feed. Failed deliveries are retried with exponential backoff;
+//! the outcome of each is logged and kept in a short per-webhook history.
+//!
+//! Webhooks belong to the API key that registered them ([`Caller`]) and only
+//! reach public addresses unless private networks are allowed. They are
+//! stored in a JSON file ([`WebhookStore`]) and resume after a restart. A
+//! train webhook is dropped once its journey is over, its date has passed or
+//! [`MAX_FAILED_POLLS`] polls in a row have failed.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use comboios_core::domain::station_timetable::StationTimetable;
+use comboios_core::error::CoreError;
+use comboios_core::query_builder::BoardFilter;
+use comboios_core::watch::{BoardEvent, JourneyEvent};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::auth::Caller;
+use crate::error::AppError;
+use crate::hub::{
+    BoardSubscription, BoardUpdate, Change, HubError, LiveHub, TrainSubscription, TrainUpdate,
+    apply_board_event,
+};
+use crate::routes::station_timetables::TimetableQuery;
+use crate::routes::trains::parse_date;
+use crate::store::{JsonFileStore, Record};
+
+/// `sha256=` followed by the hex HMAC-SHA256 of the body, keyed with the
+/// webhook's secret.
+pub const SIGNATURE_HEADER: &str = "X-Comboios-Signature";
+
+/// The event `type`, e.g. `DELAY_CHANGED`.
+pub const EVENT_HEADER: &str = "X-Comboios-Event";
+
+/// The delivery id, the same on every attempt.
+pub const DELIVERY_HEADER: &str = "X-Comboios-Delivery";
+
+/// Deliveries kept per webhook for `/webhooks/{id}/deliveries`.
+const DELIVERY_HISTORY: usize = 50;
+
+/// Time allowed for the receiver to answer one attempt.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events waiting for delivery per webhook; further events are dropped
+/// until the receiver catches up.
+const DELIVERY_QUEUE: usize = 64;
+
+/// Polls of a train in a row that may fail before its webhooks are dropped.
+pub const MAX_FAILED_POLLS: u32 = 10;
+
+/// Webhooks one API key may register unless told otherwise.
+const DEFAULT_MAX_PER_OWNER: usize = 20;
+
+/// How long a webhook waits before subscribing again to a feed it could not
+/// follow.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_mins(1);
+
+/// How often train webhooks whose date has passed are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_mins(10);
+
+/// Kinds of event a webhook can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Delay,
+    Platform,
+    Cancellation,
+    Departure,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 4] = [
+        EventKind::Delay,
+        EventKind::Platform,
+        EventKind::Cancellation,
+        EventKind::Departure,
+    ];
+
+    /// The kind of a journey event, if webhooks deliver it.
+    #[must_use]
+    pub fn of_journey(event: &JourneyEvent) -> Option<Self> {
+        match event {
+            JourneyEvent::DelayChanged { .. } => Some(Self::Delay),
+            JourneyEvent::PlatformChanged { .. } => Some(Self::Platform),
+            JourneyEvent::Cancelled { .. } => Some(Self::Cancellation),
+            JourneyEvent::Departed { .. } => Some(Self::Departure),
+            JourneyEvent::ArrivedAtStop { .. } | JourneyEvent::Completed => None,
+        }
+    }
+
+    /// The kind of a board event, if webhooks deliver it.
+    #[must_use]
+    pub fn of_board(event: &BoardEvent) -> Option<Self> {
+        match event {
+            BoardEvent::DelayChanged { .. } => Some(Self::Delay),
+            BoardEvent::PlatformAssigned { .. } | BoardEvent::PlatformChanged { .. } => {
+                Some(Self::Platform)
+            }
+            BoardEvent::Cancelled { .. } => Some(Self::Cancellation),
+            BoardEvent::Departed { .. } => Some(Self::Departure),
+            BoardEvent::Added { .. } | BoardEvent::Removed { .. } => None,
+        }
+    }
+}
+
+/// What a webhook watches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WebhookTarget {
+    /// A train on a service date (`YYYY-MM-DD`).
+    Train { train: String, date: String },
+    /// The departure board of a station (id or name), restricted to the
+    /// trains matching `filter`.
+    Station {
+        station: String,
+        #[serde(default)]
+        filter: TimetableQuery,
+    },
+}
+
+/// A registered webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    /// HMAC key for [`SIGNATURE_HEADER`]; only shown when the webhook is
+    /// created.
+    pub secret: String,
+    pub target: WebhookTarget,
+    pub events: Vec<EventKind>,
+    /// RFC 3339.
+    pub created_at: String,
+    /// [`Caller::id`] of the API key that registered the webhook.
+    #[serde(default)]
+    pub owner: String,
+}
+
+/// A webhook as listed, without its secret.
+#[derive(Debug, Serialize)]
+pub struct WebhookInfo {
+    pub id: Uuid,
+    pub url: String,
+    pub target: WebhookTarget,
+    pub events: Vec<EventKind>,
+    pub created_at: String,
+}
+
+impl From<&Webhook> for WebhookInfo {
+    fn from(hook: &Webhook) -> Self {
+        Self {
+            id: hook.id,
+            url: hook.url.clone(),
+            target: hook.target.clone(),
+            events: hook.events.clone(),
+            created_at: hook.created_at.clone(),
+        }
+    }
+}
+
+/// Body of `POST /webhooks`: `train` (with an optional `date`, today by
+/// default) or `station` (with an optional `filter`).
+#[derive(Debug, Deserialize)]
+pub struct NewWebhook {
+    pub url: String,
+    /// Generated when absent.
+    pub secret: Option<String>,
+    pub train: Option<String>,
+    pub date: Option<String>,
+    pub station: Option<String>,
+    #[serde(default)]
+    pub filter: TimetableQuery,
+    /// Every kind when absent.
+    pub events: Option<Vec<EventKind>>,
+}
+
+impl NewWebhook {
+    /// Check the request and build the webhook it describes for `owner`,
+    /// dating a train without `date` on `today`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError`] wrapping [`CoreError::InvalidInput`] when the URL
+    /// is not http(s) or, unless `allow_private` is set, points at a
+    /// loopback, private or link-local address; when neither or both of
+    /// `train` and `station` are given, `date` is malformed or past, the
+    /// filter is invalid or `events` is empty.
+    pub fn into_webhook(
+        self,
+        owner: &Caller,
+        today: NaiveDate,
+        created_at: String,
+        allow_private: bool,
+    ) -> Result<Webhook, AppError> {
+        let invalid = |message: &str| AppError::CoreError(CoreError::InvalidInput(message.into()));
+
+        let url = reqwest::Url::parse(&self.url).map_err(|_| invalid("invalid webhook url"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid("webhook url must be http or https"));
+        }
+        if !allow_private && !is_public_host(&url) {
+            return Err(invalid(
+                "webhook url must not point at a loopback, private or link-local address",
+            ));
+        }
+        let target = match (self.train, self.station) {
+            (Some(train), None) => {
+                let date = match self.date {
+                    Some(date) => parse_date(&date)?,
+                    None => today,
+                };
+                if date < today {
+                    return Err(invalid("date must not be in the past"));
+                }
+                WebhookTarget::Train {
+                    train,
+                    date: date.format("%Y-%m-%d").to_string(),
+                }
+            }
+            (None, Some(station)) => {
+                self.filter.to_filter()?;
+                WebhookTarget::Station {
+                    station,
+                    filter: self.filter,
+                }
+            }
+            _ => return Err(invalid("give either a train or a station")),
+        };
+        let events = self.events.unwrap_or_else(|| EventKind::ALL.to_vec());
+        if events.is_empty() {
+            return Err(invalid("events must not be empty"));
+        }
+
+        Ok(Webhook {
+            id: Uuid::new_v4(),
+            url: self.url,
+            secret: self
+                .secret
+                .unwrap_or_else(|| Uuid::new_v4().simple().to_string()),
+            target,
+            events,
+            created_at,
+            owner: owner.id().to_string(),
+        })
+    }
+}
+
+/// Whether `url` names a host outside loopback, private and link-local
+/// networks. Names are checked again when they are resolved for delivery.
+fn is_public_host(url: &reqwest::Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let name = host.trim_end_matches('.').to_ascii_lowercase();
+            name != "localhost" && !name.ends_with(".localhost")
+        }
+    }
+}
+
+/// Whether `ip` is reachable on the public internet: not loopback, private,
+/// shared (carrier-grade NAT), link-local, unspecified, broadcast or
+/// multicast.
+#[must_use]
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Resolves webhook hosts keeping only public addresses, so a name cannot
+/// lead a delivery into the local network.
+#[derive(Debug)]
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} has no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Webhooks persisted as a JSON array in one file.
+pub type WebhookStore = JsonFileStore<Webhook>;
+
+impl Record for Webhook {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// JSON body POSTed to a webhook.
+#[derive(Debug, Serialize)]
+pub struct Payload<'a> {
+    pub webhook_id: Uuid,
+    pub delivery_id: Uuid,
+    pub target: &'a WebhookTarget,
+    pub event: &'a Change,
+    /// The board row of the train concerned, for station webhooks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub train: Option<&'a StationTimetable>,
+}
+
+/// Outcome of one delivery, after its retries.
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub id: Uuid,
+    /// The event `type`.
+    pub event: String,
+    pub attempts: u32,
+    pub delivered: bool,
+    /// HTTP status of the last attempt, if the receiver answered.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// `sha256=<hex>` HMAC-SHA256 of `body` keyed with `secret`.
+#[must_use]
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POSTs payloads, retrying failures with exponential backoff.
+#[derive(Debug, Clone)]
+pub struct Deliverer {
+    client: reqwest::Client,
+    max_attempts: u32,
+    first_retry: Duration,
+    allow_private: bool,
+}
+
+impl Deliverer {
+    /// A deliverer giving up after `max_attempts`, waiting 2 s before the
+    /// first retry and twice as long before each next one. It only connects
+    /// to public addresses and does not follow redirects.
+    #[must_use]
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            client: client(false),
+            max_attempts: max_attempts.max(1),
+            first_retry: Duration::from_secs(2),
+            allow_private: false,
+        }
+    }
+
+    /// Also deliver to loopback, private and link-local addresses.
+    #[must_use]
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.client = client(allow);
+        self.allow_private = allow;
+        self
+    }
+
+    /// Wait `first_retry` before the first retry.
+    #[must_use]
+    pub fn first_retry(mut self, first_retry: Duration) -> Self {
+        self.first_retry = first_retry;
+        self
+    }
+
+    /// Deliver `payload` to `hook`. Network errors, timeouts, `408`, `429`
+    /// and `5xx` answers are retried; other answers are final.
+    pub async fn deliver(&self, hook: &Webhook, payload: &Payload<'_>) -> Delivery {
+        let event = event_type(payload.event);
+        let mut delivery = Delivery {
+            id: payload.delivery_id,
+            event: event.clone(),
+            attempts: 0,
+            delivered: false,
+            status: None,
+            error: None,
+        };
+        let body = match serde_json::to_vec(payload) {
+            Ok(body) => body,
+            Err(e) => {
+                delivery.error = Some(e.to_string());
+                return delivery;
+            }
+        };
+        let signature = sign(&hook.secret, &body);
+        // Webhooks stored before addresses were checked may still name one.
+        if !self.allow_private
+            && reqwest::Url::parse(&hook.url).map_or(true, |url| !is_public_host(&url))
+        {
+            delivery.error = Some("webhook url is not a public address".to_string());
+            return delivery;
+        }
+
+        let mut backoff = self.first_retry;
+        loop {
+            delivery.attempts += 1;
+            let result = self
+                .client
+                .post(&hook.url)
+                .timeout(DELIVERY_TIMEOUT)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, &event)
+                .header(DELIVERY_HEADER, delivery.id.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+
+            let retry = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    delivery.status = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.delivered = true;
+                        delivery.error = None;
+                        return delivery;
+                    }
+                    delivery.error = Some(format!("receiver answered {status}"));
+                    status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => {
+                    delivery.status = None;
+                    delivery.error = Some(e.to_string());
+                    true
+                }
+            };
+            if !retry || delivery.attempts >= self.max_attempts {
+                return delivery;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+/// The HTTP client for deliveries, resolving hosts to public addresses only
+/// unless `allow_private` is set.
+fn client(allow_private: bool) -> reqwest::Client {
+    let builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    let builder = if allow_private {
+        builder
+    } else {
+        builder.dns_resolver(Arc::new(PublicResolver))
+    };
+    builder.build().expect("webhook HTTP client builds")
+}
+
+/// The `type` tag of an event.
+fn event_type(change: &Change) -> String {
+    serde_json::to_value(change)
+        .ok()
+        .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+type DeliveryLog = Arc<Mutex<HashMap<Uuid, VecDeque<Delivery>>>>;
+
+/// An event to deliver, with the board row it concerns for station webhooks.
+type Outgoing = (Change, Option<StationTimetable>);
+
+/// The registered webhooks and the tasks following their feeds.
+#[derive(Debug)]
+pub struct Webhooks {
+    registry: Registry,
+    hub: LiveHub,
+    deliverer: Deliverer,
+    max_per_owner: usize,
+}
+
+/// The stored webhooks with their running tasks and delivery history,
+/// shared with the tasks so a finished train webhook can drop itself.
+#[derive(Debug, Clone)]
+struct Registry {
+    store: Arc<WebhookStore>,
+    /// The feed and delivery tasks of each webhook.
+    watchers: Arc<Mutex<HashMap<Uuid, [AbortHandle; 2]>>>,
+    log: DeliveryLog,
+}
+
+impl Registry {
+    /// Delete the webhook `id` and its history, returning its tasks if it
+    /// existed.
+    fn forget(&self, id: Uuid) -> io::Result<Option<[AbortHandle; 2]>> {
+        if !self.store.remove(id)? {
+            return Ok(None);
+        }
+        self.log
+            .lock()
+            .expect("delivery log lock poisoned")
+            .remove(&id);
+        Ok(self
+            .watchers
+            .lock()
+            .expect("webhook watchers lock poisoned")
+            .remove(&id))
+    }
+
+    /// Delete the train webhooks dated before `today`, or with no valid
+    /// date, and stop their tasks.
+    fn prune(&self, today: NaiveDate) {
+        let expired = self
+            .store
+            .list()
+            .into_iter()
+            .filter(|hook| match &hook.target {
+                WebhookTarget::Train { date, .. } => parse_date(date).map_or(true, |d| d < today),
+                WebhookTarget::Station { .. } => false,
+            });
+        for hook in expired {
+            tracing::info!("Dropping webhook {} for a past train", hook.id);
+            match self.forget(hook.id) {
+                Ok(tasks) => tasks.into_iter().flatten().for_each(|task| task.abort()),
+                Err(e) => tracing::warn!("Failed to drop webhook {}: {e}", hook.id),
+            }
+        }
+    }
+}
+
+impl Webhooks {
+    #[must_use]
+    pub fn new(store: WebhookStore, hub: LiveHub, deliverer: Deliverer) -> Self {
+        Self {
+            registry: Registry {
+                store: Arc::new(store),
+                watchers: Arc::default(),
+                log: Arc::default(),
+            },
+            hub,
+            deliverer,
+            max_per_owner: DEFAULT_MAX_PER_OWNER,
+        }
+    }
+
+    /// Let each API key register at most `max` webhooks.
+    #[must_use]
+    pub fn max_per_owner(mut self, max: usize) -> Self {
+        self.max_per_owner = max;
+        self
+    }
+
+    /// Drop the train webhooks whose date has passed, start following the
+    /// feeds of the others and keep dropping expired ones every
+    /// [`PRUNE_INTERVAL`].
+    pub fn start(&self) {
+        self.registry.prune(self.hub.now().date());
+        let hooks = self.registry.store.list();
+        tracing::info!("Resuming {} webhooks", hooks.len());
+        for hook in hooks {
+            self.watch(hook);
+        }
+
+        let (registry, hub) = (self.registry.clone(), self.hub.clone());
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(PRUNE_INTERVAL);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                registry.prune(hub.now().date());
+            }
+        });
+    }
+
+    /// Store `hook` and start delivering its events, unless its owner has
+    /// registered the most webhooks allowed already. Returns whether it was
+    /// registered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn register(&self, hook: Webhook) -> io::Result<bool> {
+        let fits = |hooks: &[Webhook]| {
+            hooks.iter().filter(|h| h.owner == hook.owner).count() < self.max_per_owner
+        };
+        if !self.registry.store.insert_if(hook.clone(), fits)? {
+            return Ok(false);
+        }
+        tracing::info!("Registered webhook {} for {}", hook.id, hook.url);
+        self.watch(hook);
+        Ok(true)
+    }
+
+    /// Delete the webhook `id` of `owner`, returning whether it existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be saved.
+    pub fn remove(&self, owner: &Caller, id: Uuid) -> io::Result<bool> {
+        if self.get(owner, id).is_none() {
+            return Ok(false);
+        }
+        let Some(tasks) = self.registry.forget(id)? else {
+            return Ok(false);
+        };
+        for task in tasks {
+            task.abort();
+        }
+        tracing::info!("Removed webhook {id}");
+        Ok(true)
+    }
+
+    /// The webhooks registered by `owner`.
+    #[must_use]
+    pub fn list(&self, owner: &Caller) -> Vec<Webhook> {
+        let mut hooks = self.registry.store.list();
+        hooks.retain(|hook| hook.owner == owner.id());
+        hooks
+    }
+
+    /// Recent deliveries to the webhook `id` of `owner`, oldest first;
+    /// `None` for an unknown webhook.
+    #[must_use]
+    pub fn deliveries(&self, owner: &Caller, id: Uuid) -> Option<Vec<Delivery>> {
+        self.get(owner, id)?;
+        let log = self
+            .registry
+            .log
+            .lock()
+            .expect("delivery log lock poisoned");
+        Some(
+            log.get(&id)
+                .map(|d| d.iter().cloned().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn get(&self, owner: &Caller, id: Uuid) -> Option<Webhook> {
+        self.registry
+            .store
+            .list()
+            .into_iter()
+            .find(|hook| hook.id == id && hook.owner == owner.id())
+    }
+
+    fn watch(&self, hook: Webhook) {
+        let id = hook.id;
+        let (queue, outbox) = mpsc::channel(DELIVERY_QUEUE);
+        let sender = HookSender {
+            hook: hook.clone(),
+            deliverer: self.deliverer.clone(),
+            log: self.registry.log.clone(),
+        };
+        let feed = HookFeed { hook, queue };
+        let hub = self.hub.clone();
+        let registry = self.registry.clone();
+
+        // Held until both tasks are recorded, so a feed ending at once cannot
+        // forget the webhook before it is watched.
+        let mut watchers = self
+            .registry
+            .watchers
+            .lock()
+            .expect("webhook watchers lock poisoned");
+        let delivery = tokio::spawn(sender.run(outbox));
+        let delivery_handle = delivery.abort_handle();
+        let follow = tokio::spawn(async move {
+            match feed.hook.target.clone() {
+                WebhookTarget::Train { train, date } => {
+                    let subscription = subscribe(id, || {
+                        std::future::ready(hub.subscribe_train(&train, &date))
+                    })
+                    .await;
+                    feed.follow_train(subscription).await;
+                }
+                WebhookTarget::Station { station, filter } => {
+                    let filter = filter.to_filter().unwrap_or_default();
+                    let board = || {
+                        subscribe(id, || {
+                            let (hub, station) = (hub.clone(), station.clone());
+                            async move { hub.subscribe_board(&station).await }
+                        })
+                    };
+                    feed.follow_board(board().await, &filter, board).await;
+                    return;
+                }
+            }
+            // The journey is over: deliver what is queued, then drop the
+            // webhook.
+            drop(feed);
+            let _ = delivery.await;
+            tracing::info!("Dropping webhook {id}, its train is no longer followed");
+            if let Err(e) = registry.forget(id) {
+                tracing::warn!("Failed to drop webhook {id}: {e}");
+            }
+        });
+        watchers.insert(id, [follow.abort_handle(), delivery_handle]);
+    }
+}
+
+/// Follows the feed of one webhook and queues the events it asked for.
+struct HookFeed {
+    hook: Webhook,
+    queue: mpsc::Sender<Outgoing>,
+}
+
+impl HookFeed {
+    /// Queue the train's events until its feed ends or [`MAX_FAILED_POLLS`]
+    /// polls in a row fail.
+    async fn follow_train(&self, subscription: TrainSubscription) {
+        let TrainSubscription { mut updates, .. } = subscription;
+        let mut failures = 0;
+        while let Some(update) = next(&mut updates, self.hook.id).await {
+            match update {
+                TrainUpdate::Journey(_) => failures = 0,
+                TrainUpdate::Event(event) => {
+                    if EventKind::of_journey(&event).is_some_and(|kind| self.wants(kind)) {
+                        self.queue(Change::Train(event), None);
+                    }
+                }
+                TrainUpdate::Error(_) => {
+                    failures += 1;
+                    if failures >= MAX_FAILED_POLLS {
+                        tracing::warn!(
+                            "Webhook {} train feed failed {failures} polls in a row",
+                            self.hook.id
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+        tracing::info!("Webhook {} train feed ended", self.hook.id);
+    }
+
+    /// Queue the board's events for the trains matching `filter`, taking a
+    /// fresh snapshot from `resubscribe` when the feed runs ahead.
+    async fn follow_board<F>(
+        &self,
+        subscription: BoardSubscription,
+        filter: &BoardFilter,
+        resubscribe: impl Fn() -> F,
+    ) where
+        F: Future<Output = BoardSubscription>,
+    {
+        let BoardSubscription {
+            mut trains,
+            mut updates,
+        } = subscription;
+        loop {
+            let event = match updates.recv().await {
+                Ok(BoardUpdate::Event(event)) => event,
+                Ok(BoardUpdate::Error(_)) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "Webhook {} fell behind, {skipped} updates skipped; resyncing the board",
+                        self.hook.id
+                    );
+                    BoardSubscription { trains, updates } = resubscribe().await;
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let train_number = board_train_number(&event);
+            let before = trains.iter().find(|row| row.train_number == train_number);
+            let wanted = EventKind::of_board(&event).is_some_and(|kind| self.wants(kind))
+                && before.is_some_and(|row| filter.matches(row));
+            let row = before.cloned();
+            apply_board_event(&mut trains, &event);
+            if wanted {
+                let row = trains
+                    .iter()
+                    .find(|row| row.train_number == train_number)
+                    .cloned()
+                    .or(row);
+                self.queue(Change::Board(event), row);
+            }
+        }
+    }
+
+    fn wants(&self, kind: EventKind) -> bool {
+        self.hook.events.contains(&kind)
+    }
+
+    fn queue(&self, event: Change, train: Option<StationTimetable>) {
+        if let Err(TrySendError::Full(_)) = self.queue.try_send((event, train)) {
+            tracing::warn!(
+                "Webhook {} delivery queue is full, event dropped",
+                self.hook.id
+            );
+        }
+    }
+}
+
+/// Delivers the events of one webhook, in order.
+struct HookSender {
+    hook: Webhook,
+    deliverer: Deliverer,
+    log: DeliveryLog,
+}
+
+impl HookSender {
+    /// Deliver every event queued on `outbox` until it closes.
+    async fn run(self, mut outbox: mpsc::Receiver<Outgoing>) {
+        while let Some((event, train)) = outbox.recv().await {
+            self.send(&event, train.as_ref()).await;
+        }
+    }
+
+    async fn send(&self, event: &Change, train: Option<&StationTimetable>) {
+        let payload = Payload {
+            webhook_id: self.hook.id,
+            delivery_id: Uuid::new_v4(),
+            target: &self.hook.target,
+            event,
+            train,
+        };
+        let delivery = self.deliverer.deliver(&self.hook, &payload).await;
+        if delivery.delivered {
+            tracing::info!(
+                "Webhook {} delivery {} ({}) succeeded after {} attempts",
+                self.hook.id,
+                delivery.id,
+                delivery.event,
+                delivery.attempts
+            );
+        } else {
+            tracing::warn!(
+                "Webhook {} delivery {} ({}) failed after {} attempts: {}",
+                self.hook.id,
+                delivery.id,
+                delivery.event,
+                delivery.attempts,
+                delivery.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+
+        let mut log = self.log.lock().expect("delivery log lock poisoned");
+        let history = log.entry(self.hook.id).or_default();
+        if history.len() == DELIVERY_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(delivery);
+    }
+}
+
+/// Subscribe to the feed of webhook `hook` with `subscribe`, trying again
+/// every [`RESUBSCRIBE_DELAY`] while the hub is full or the station cannot
+/// be resolved.
+async fn subscribe<T, F>(hook: Uuid, subscribe: impl Fn() -> F) -> T
+where
+    F: Future<Output = Result<T, HubError>>,
+{
+    loop {
+        match subscribe().await {
+            Ok(subscription) => return subscription,
+            Err(e) => {
+                tracing::warn!("Webhook {hook} cannot follow its feed yet: {e}");
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        }
+    }
+}
+
+/// The next update on a feed, skipping over lag; `None` once it closes.
+async fn next<T: Clone>(updates: &mut broadcast::Receiver<T>, hook: Uuid) -> Option<T> {
+    loop {
+        match updates.recv().await {
+            Ok(update) => return Some(update),
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Webhook {hook} fell behind, {skipped} updates skipped");
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+fn board_train_number(event: &BoardEvent) -> u64 {
+    match event {
+        BoardEvent::Added { train } => train.train_number,
+        BoardEvent::Removed { train_number }
+        | BoardEvent::PlatformAssigned { train_number, .. }
+        | BoardEvent::PlatformChanged { train_number, .. }
+        | BoardEvent::DelayChanged { train_number, .. }
+        | BoardEvent::Cancelled { train_number }
+        | BoardEvent::Departed { train_number, .. } => *train_number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{header_exists, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn hook(url: &str, target: WebhookTarget) -> Webhook {
+        Webhook {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            secret: "s3cret".to_string(),
+            target,
+            events: EventKind::ALL.to_vec(),
+            created_at: "2026-03-14T10:00:00+00:00".to_string(),
+            owner: Caller::from_key("key").id().to_string(),
+        }
+    }
+
+    fn train_target() -> WebhookTarget {
+        WebhookTarget::Train {
+            train: "520".to_string(),
+            date: "2026-03-14".to_string(),
+        }
+    }
+
+    fn row(train_number: u64, destination: &str) -> StationTimetable {
+        StationTimetable {
+            origin_station_name: "Lisboa - Santa Apolónia".to_string(),
+            destination_station_name: destination.to_string(),
+            ..crate::fixtures::row(train_number, "94-30007", "94-1")
+        }
+    }
+
+    fn sender(hook: Webhook) -> HookSender {
+        HookSender {
+            hook,
+            deliverer: Deliverer::new(3)
+                .first_retry(Duration::from_millis(10))
+                .allow_private_networks(true),
+            log: DeliveryLog::default(),
+        }
+    }
+
+    fn feed(hook: Webhook) -> (HookFeed, mpsc::Receiver<Outgoing>) {
+        let (queue, outbox) = mpsc::channel(DELIVERY_QUEUE);
+        (HookFeed { hook, queue }, outbox)
+    }
+
+    fn queued(mut outbox: mpsc::Receiver<Outgoing>) -> Vec<String> {
+        let mut events = Vec::new();
+        while let Ok((event, _)) = outbox.try_recv() {
+            events.push(event_type(&event));
+        }
+        events
+    }
+
+    #[test]
+    fn signature_is_hex_hmac_sha256() {
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    fn request(body: serde_json::Value, allow_private: bool) -> Result<Webhook, AppError> {
+        serde_json::from_value::<NewWebhook>(body)
+            .unwrap()
+            .into_webhook(
+                &Caller::from_key("key"),
+                crate::fixtures::day(),
+                String::new(),
+                allow_private,
+            )
+    }
+
+    #[test]
+    fn new_webhook_needs_one_target_and_an_http_url() {
+        let hook = request(
+            serde_json::json!({ "url": "https://example.com/hook", "train": "520" }),
+            false,
+        )
+        .unwrap();
+        assert!(
+            matches!(hook.target, WebhookTarget::Train { ref date, .. } if date == "2026-03-14")
+        );
+        assert_eq!(hook.events, EventKind::ALL);
+        assert!(!hook.secret.is_empty());
+        assert_eq!(hook.owner, Caller::from_key("key").id());
+
+        let hook = request(
+            serde_json::json!({
+                "url": "https://example.com", "station": "94-31039",
+                "filter": { "destination": "Porto" }, "events": ["delay"]
+            }),
+            false,
+        )
+        .unwrap();
+        assert_eq!(hook.events, vec![EventKind::Delay]);
+
+        for body in [
+            serde_json::json!({ "url": "ftp://example.com", "train": "520" }),
+            serde_json::json!({ "url": "https://example.com" }),
+            serde_json::json!({ "url": "https://example.com", "train": "520", "station": "x" }),
+            serde_json::json!({ "url": "https://example.com", "train": "520", "events": [] }),
+            serde_json::json!({
+                "url": "https://example.com", "station": "x", "filter": { "from": "25:00" }
+            }),
+        ] {
+            assert!(request(body, false).is_err());
+        }
+    }
+
+    #[test]
+    fn new_train_webhooks_need_a_valid_date_from_today_on() {
+        let dated = |date: &str| {
+            request(
+                serde_json::json!({ "url": "https://example.com", "train": "520", "date": date }),
+                false,
+            )
+        };
+
+        assert!(dated("2026-03-15").is_ok());
+        assert!(dated("2026-03-14").is_ok());
+        assert!(dated("2026-03-13").is_err());
+        assert!(dated("14/03/2026").is_err());
+    }
+
+    #[test]
+    fn new_webhooks_stay_off_private_networks_unless_allowed() {
+        for url in [
+            "http://localhost:9000",
+            "http://api.localhost",
+            "http://127.0.0.1:8123",
+            "http://10.0.0.5",
+            "http://192.168.1.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]:8080",
+            "http://[fe80::1]",
+            "http://[::ffff:10.0.0.1]",
+        ] {
+            let body = serde_json::json!({ "url": url, "train": "520" });
+            assert!(request(body.clone(), false).is_err(), "{url}");
+            assert!(request(body, true).is_ok(), "{url}");
+        }
+        assert!(is_public("8.8.8.8".parse().unwrap()));
+        assert!(is_public("2001:4860:4860::8888".parse().unwrap()));
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn deliveries_to_private_addresses_are_refused() {
+        let server = MockServer::start().await;
+        let hook = hook(&server.uri(), train_target());
+        let event = Change::Train(JourneyEvent::Completed);
+        let payload = Payload {
+            webhook_id: hook.id,
+            delivery_id: Uuid::new_v4(),
+            target: &hook.target,
+            event: &event,
+            train: None,
+        };
+        let delivery = Deliverer::new(5).deliver(&hook, &payload).await;
+
+        assert!(!delivery.delivered);
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delivery_is_signed_and_retried_until_accepted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header_exists(SIGNATURE_HEADER))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let hook = hook(&server.uri(), train_target());
+        let event = Change::Train(JourneyEvent::DelayChanged {
+            previous: Some(0),
+            current: Some(5),
+        });
+        let payload = Payload {
+            webhook_id: hook.id,
+            delivery_id: Uuid::new_v4(),
+            target: &hook.target,
+            event: &event,
+            train: None,
+        };
+        let delivery = Deliverer::new(5)
+            .first_retry(Duration::from_millis(10))
+            .allow_private_networks(true)
+            .deliver(&hook, &payload)
+            .await;
+
+        assert!(delivery.delivered);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.status, Some(204));
+        assert_eq!(delivery.event, "DELAY_CHANGED");
+
+        let requests = server.received_requests().await.unwrap();
+        let last = requests.last().unwrap();
+        assert_eq!(
+            last.headers[SIGNATURE_HEADER],
+            sign("s3cret", &last.body).as_str()
+        );
+        assert_eq!(last.headers[EVENT_HEADER], "DELAY_CHANGED");
+        let body: serde_json::Value = serde_json::from_slice(&last.body).unwrap();
+        assert_eq!(body["event"]["current"], 5);
+        assert_eq!(body["target"]["kind"], "train");
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(410))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let hook = hook(&server.uri(), train_target());
+        let event = Change::Train(JourneyEvent::Completed);
+        let payload = Payload {
+            webhook_id: hook.id,
+            delivery_id: Uuid::new_v4(),
+            target: &hook.target,
+            event: &event,
+            train: None,
+        };
+        let delivery = Deliverer::new(5)
+            .allow_private_networks(true)
+            .deliver(&hook, &payload)
+            .await;
+
+        assert!(!delivery.delivered);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.status, Some(410));
+    }
+
+    #[tokio::test]
+    async fn station_webhooks_deliver_wanted_events_of_matching_trains() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let filter = TimetableQuery {
+            destination: Some("Porto".to_string()),
+            ..TimetableQuery::default()
+        };
+        let mut hook = hook(
+            &server.uri(),
+            WebhookTarget::Station {
+                station: "94-30007".to_string(),
+                filter: filter.clone(),
+            },
+        );
+        hook.events = vec![EventKind::Platform, EventKind::Departure];
+        let id = hook.id;
+        let sender = sender(hook.clone());
+        let log = sender.log.clone();
+        let (feed, outbox) = feed(hook);
+
+        let (tx, updates) = broadcast::channel(16);
+        let subscription = BoardSubscription {
+            trains: vec![row(520, "Porto - Campanhã"), row(4410, "Tomar")],
+            updates,
+        };
+        for event in [
+            BoardEvent::PlatformAssigned {
+                train_number: 4410,
+                platform: "2".to_string(),
+            },
+            BoardEvent::DelayChanged {
+                train_number: 520,
+                previous: None,
+                current: Some(4),
+            },
+            BoardEvent::PlatformAssigned {
+                train_number: 520,
+                platform: "5".to_string(),
+            },
+            BoardEvent::Departed {
+                train_number: 520,
+                time: Some("10:34".to_string()),
+            },
+        ] {
+            tx.send(BoardUpdate::Event(event)).unwrap();
+        }
+        drop(tx);
+        feed.follow_board(subscription, &filter.to_filter().unwrap(), || async {
+            unreachable!("the feed does not lag")
+        })
+        .await;
+        drop(feed);
+        sender.run(outbox).await;
+
+        let events: Vec<_> = log.lock().unwrap()[&id]
+            .iter()
+            .map(|d| d.event.clone())
+            .collect();
+        assert_eq!(events, vec!["PLATFORM_ASSIGNED", "DEPARTED"]);
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["train"]["train_number"], 520);
+        assert_eq!(body["train"]["platform"], "5");
+        assert_eq!(body["train"]["delay"], 4);
+    }
+
+    #[tokio::test]
+    async fn lagging_board_webhooks_resync_from_a_fresh_snapshot() {
+        let (feed, outbox) = feed(hook(
+            "https://example.com",
+            WebhookTarget::Station {
+                station: "94-30007".to_string(),
+                filter: TimetableQuery::default(),
+            },
+        ));
+
+        let (tx, updates) = broadcast::channel(1);
+        let subscription = BoardSubscription {
+            trains: vec![row(520, "Porto - Campanhã")],
+            updates,
+        };
+        // Overruns the receiver: the first of these is lost.
+        for train_number in [520, 4410] {
+            tx.send(BoardUpdate::Event(BoardEvent::Cancelled { train_number }))
+                .unwrap();
+        }
+        let (fresh_tx, fresh_updates) = broadcast::channel(4);
+        fresh_tx
+            .send(BoardUpdate::Event(BoardEvent::Departed {
+                train_number: 4410,
+                time: Some("10:40".to_string()),
+            }))
+            .unwrap();
+        drop(fresh_tx);
+        let fresh = std::sync::Mutex::new(Some(BoardSubscription {
+            trains: vec![row(4410, "Tomar")],
+            updates: fresh_updates,
+        }));
+
+        feed.follow_board(subscription, &BoardFilter::default(), || {
+            std::future::ready(fresh.lock().unwrap().take().expect("resynced once"))
+        })
+        .await;
+        drop(tx);
+
+        // 4410 was not on the stale snapshot, so only the fresh one lets its
+        // departure through.
+        assert_eq!(queued(outbox), vec!["DEPARTED"]);
+    }
+
+    #[tokio::test]
+    async fn pruning_drops_and_stops_train_webhooks_of_past_days() {
+        let path = std::env::temp_dir().join(format!("webhooks-{}.json", Uuid::new_v4()));
+        let registry = Registry {
+            store: Arc::new(WebhookStore::open(&path).unwrap()),
+            watchers: Arc::default(),
+            log: Arc::default(),
+        };
+        let dated = |date: &str| {
+            hook(
+                "https://example.com",
+                WebhookTarget::Train {
+                    train: "520".to_string(),
+                    date: date.to_string(),
+                },
+            )
+        };
+        let (past, today) = (dated("2026-03-13"), dated("2026-03-14"));
+        let station = hook(
+            "https://example.com",
+            WebhookTarget::Station {
+                station: "94-30007".to_string(),
+                filter: TimetableQuery::default(),
+            },
+        );
+        for hook in [&past, &today, &station] {
+            registry.store.insert(hook.clone()).unwrap();
+        }
+        let task = tokio::spawn(std::future::pending::<()>());
+        registry
+            .watchers
+            .lock()
+            .unwrap()
+            .insert(past.id, [task.abort_handle(), task.abort_handle()]);
+
+        registry.prune(NaiveDate::from_ymd_opt(2026, 3, 14).unwrap());
+
+        let kept: Vec<_> = registry.store.list().iter().map(|h| h.id).collect();
+        assert_eq!(kept, [today.id, station.id]);
+        assert!(task.await.unwrap_err().is_cancelled());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn train_webhooks_stop_after_repeated_failed_polls() {
+        let (feed, outbox) = feed(hook("https://example.com", train_target()));
+        let (tx, updates) = broadcast::channel(32);
+        tx.send(TrainUpdate::Event(JourneyEvent::DelayChanged {
+            previous: Some(0),
+            current: Some(5),
+        }))
+        .unwrap();
+        for _ in 0..MAX_FAILED_POLLS {
+            tx.send(TrainUpdate::Error("503".to_string())).unwrap();
+        }
+
+        // Returns although the feed is still open.
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            feed.follow_train(TrainSubscription {
+                latest: None,
+                updates,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(queued(outbox), vec!["DELAY_CHANGED"]);
+        drop(tx);
+    }
+}
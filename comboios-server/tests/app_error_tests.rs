@@ -85,3 +85,51 @@ async fn test_parse_error_returns_bad_gateway() {
     let body = parse_error_body(response).await;
     assert_eq!(body["error_type"], "ParseError");
 }
+
+#[tokio::test]
+async fn test_not_found_returns_not_found() {
+    let err = AppError::NotFound("webhook 42".to_string());
+
+    let response = err.into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body = parse_error_body(response).await;
+    assert_eq!(body["error_type"], "NotFound");
+    assert_eq!(body["error"], "webhook 42 not found");
+}
+
+#[tokio::test]
+async fn test_storage_error_returns_internal_server_error() {
+    let err = AppError::Storage(std::io::Error::other("/data/webhooks.json.tmp: disk full"));
+
+    let response = err.into_response();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let body = parse_error_body(response).await;
+    assert_eq!(body["error_type"], "StorageError");
+    assert_eq!(body["error"], "Failed to save the change");
+}
+
+#[tokio::test]
+async fn test_too_many_requests_returns_too_many_requests() {
+    let err = AppError::TooManyRequests("webhook limit of 20 reached".to_string());
+
+    let response = err.into_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let body = parse_error_body(response).await;
+    assert_eq!(body["error_type"], "TooManyRequests");
+    assert_eq!(body["error"], "webhook limit of 20 reached");
+}
+
+#[tokio::test]
+async fn test_unauthorized_returns_unauthorized() {
+    let err = AppError::Unauthorized("unknown API key".to_string());
+
+    let response = err.into_response();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let body = parse_error_body(response).await;
+    assert_eq!(body["error_type"], "Unauthorized");
+    assert_eq!(body["error"], "unknown API key");
+}
@@ -72,6 +72,8 @@ function mapToTrainDetails(journey: any): TrainDetails {
     delay_minutes: delayMinutes,
     observations: journey.observations,
     duration: journey.duration,
+    predicted_arrival: journey.predicted_arrival,
+    prediction_confidence: journey.prediction_confidence,
     stops: journey.stops.map((stop: any) => {
       const statusStr = (stop.status || "").toLowerCase();
       const hasPassed = stop.has_passed === true || statusStr === "passed";
@@ -90,6 +92,7 @@ function mapToTrainDetails(journey: any): TrainDetails {
         status: hasPassed ? "passed" : "upcoming",
         has_passed: hasPassed,
         predicted_time: stop.predicted_time,
+        prediction_confidence: stop.prediction_confidence,
       };
     }),
   };
@@ -28,8 +28,11 @@ export interface TrainEntry {
   is_departure: boolean;
   operator: string;
   observations?: string | null;
+  occupancy?: Occupancy;
 }
 
+export type Occupancy = "LOW" | "MEDIUM" | "HIGH" | "UNKNOWN";
+
 export interface StationBoard {
   station_id: string;
   station_name: string;
@@ -71,8 +74,11 @@ export interface JourneyStop {
   status: "passed" | "current" | "upcoming";
   has_passed?: boolean;
   predicted_time?: string;
+  prediction_confidence?: PredictionConfidence;
 }
 
+export type PredictionConfidence = "HIGH" | "MEDIUM" | "LOW";
+
 export interface TrainDetails {
   train_number: number;
   service_type: string;
@@ -85,6 +91,8 @@ export interface TrainDetails {
   delay_minutes?: number;
   observations?: string;
   duration?: string;
+  predicted_arrival?: string;
+  prediction_confidence?: PredictionConfidence;
 }
 
 export function parseDelayMinutes(observations: string): number | null {